@@ -1,61 +1,111 @@
-use std::cmp::max;
-use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::collections::HashSet;
 use std::collections::HashMap;
-use std::io;
-use std::io::Write;
 
-use colored::Colorize;
-use rand::prelude::IteratorRandom;
-use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
 
+use crate::game::action::{Action, ActionError, ActionOutcome};
+use crate::game::agent::PlayerAgent;
+use crate::game::betting::{BettingStructure, LegalActions};
+use crate::game::blinds::{BlindStructure, MissedBlinds};
+use crate::game::deck::{Deck, DeckSource, ShuffledDecks, StackedDecks};
+use crate::game::fair_shuffle::{FairShuffle, ShuffleError, ShuffleReveal};
+use crate::game::hand_history::{ActionKind, ActionRecord, HandHistory, HandHistorySink, HistorySinkError, PotRecord, RunRecord, SeatRecord};
 use crate::game::hand::{Card, OnePlayerAllPossibleCards};
+use crate::game::hand_state::{HandState, Street};
 use crate::game::player::{Player, PlayerId};
-use crate::utils;
+use crate::game::pot_manager::{Pot, PotManager};
+use crate::game::runout::RunoutError;
+use crate::game::showdown::ShowdownResult;
+use crate::game::table::{HandPositions, Table};
+use crate::game::view::{PlayerView, SeatView, SpectatorView};
 
 pub mod player;
 pub mod hand;
+pub mod evaluator;
+pub mod pot_manager;
+pub mod action;
+pub mod agent;
+pub mod view;
+pub mod hand_state;
+pub mod betting;
+pub mod blinds;
+pub mod table;
+pub mod deck;
+pub mod fair_shuffle;
+pub mod hand_history;
+pub mod replay;
+pub mod showdown;
+pub mod runout;
 
 
 const PLAYER_NOT_FOUND_ERROR: &str = "Expected another player in the round. There was none.";
 
 
+/// Decides who receives the chips left over when a pot cannot be split evenly between its winners.
+///
+/// Each leftover chip goes to a different winner, in the order given by the rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OddChipRule {
+    /// The winner in the first seat to the left of the button receives the first odd chip.
+    FirstLeftOfButton,
+    /// The winner with the lowest `PlayerId` receives the first odd chip.
+    LowestPlayerId
+}
+
+
 /// Represents a game of poker.
 ///
 /// # Fields
 ///
 /// * `game_id`: An `i32` that uniquely identifies the game.
 /// * `num_players`: An `i32` that represents the number of players in the game.
-/// * `players`: A `VecDeque` of `PlayerId`s that represents the order of players in the game.
-/// * `players_in_round`: A `VecDeque` of `PlayerId`s that represents the order of players in the current round.
-/// * `player_id_to_player`: A `HashMap` that maps `PlayerId`s to `Player`s.
-/// * `big_blind`: An `i32` that represents the big blind amount.
-/// * `dealer_location`: An `i16` that represents the index of the dealer in the `players` `VecDeque`.
+/// * `table`: The `Table` that holds the seats and the dealer button.
+/// * `player_id_to_player`: A `HashMap` that maps `PlayerId`s to `Player`s. Players who bust stay in here after they are removed from the table.
+/// * `blind_structure`: The `BlindStructure` that sets the blinds, antes and straddle.
+/// * `deck`: The `Deck` of cards that have not been dealt this hand.
+/// * `deck_source`: The `DeckSource` that gives a new deck for each hand. It shuffles with OS randomness unless it is replaced with `set_deck_source`.
+/// * `fair_shuffle`: The `FairShuffle` for the next hand, or `None` if the game does not use provably fair shuffles.
+/// * `dealt_shuffle`: The `FairShuffle` the current or last hand was dealt with.
+/// * `hand_number`: The number of hands that have been started.
+/// * `hand_history`: The `HandHistory` of the current or last hand.
+/// * `history_sinks`: The `HandHistorySink`s each `HandHistory` is sent to when its hand ends.
+/// * `history_errors`: The failures of the `history_sinks` that have not been taken with `take_history_errors`.
 /// * `community_cards`: A `Vec` of `hand::Card`s that represents the community cards.
-/// * `curr_bet`: An `i32` that represents the current bet amount.
-/// * `last_player_to_raise`: A `PlayerId` that represents the last player to raise.
-/// * `bet_this_round`: A `HashSet` of `PlayerId`s that represents the players who have bet in this round.
-/// * `has_raised`: A `bool` that indicates whether a player has raised in the current round.
-/// * `bets`: A `HashMap` that maps `PlayerId`s to the amount they have bet.
-/// * `pots`: A `BTreeMap` that maps from a bet amount to the players that have bet that amount.
+/// * `hand_state`: The `HandState` of the current hand, or `None` if no hand has been started.
+/// * `pot_manager`: A `PotManager` that tracks how much each player has put in this hand and builds the side pots.
+/// * `odd_chip_rule`: An `OddChipRule` that decides who receives the odd chips of a split pot.
+/// * `betting_structure`: The `BettingStructure` that limits how much players can bet and raise.
+/// * `sitting_out`: A `HashSet` of the `PlayerId`s that are not being dealt in.
+/// * `missed_blinds`: A `HashMap` that maps `PlayerId`s to the blinds they missed while sitting out.
+/// * `shows_losing_hands`: A `HashSet` of the `PlayerId`s that show their hand at showdown even when they could muck it.
+/// * `max_runs`: The most times the board can be run when the players are all in. If it is 1, the board is only run once.
+/// * `run_choices`: A `HashMap` that maps `PlayerId`s to how many times they agreed to run the board this hand.
 #[derive(Debug)]
 pub struct Game {
     game_id: u128,
     num_players: i32,
-    players: VecDeque<PlayerId>,
-    players_in_round: HashSet<PlayerId>,
-    turn_queue: VecDeque<PlayerId>,
+    table: Table,
     player_id_to_player: HashMap<PlayerId, Player>,
-    big_blind: i32,
+    blind_structure: BlindStructure,
     initial_money: i32,
-    dealer_location: i16,
+    deck: Deck,
+    deck_source: Box<dyn DeckSource>,
+    fair_shuffle: Option<FairShuffle>,
+    dealt_shuffle: Option<FairShuffle>,
+    hand_number: u64,
+    hand_history: Option<HandHistory>,
+    history_sinks: Vec<Box<dyn HandHistorySink>>,
+    history_errors: Vec<HistorySinkError>,
     community_cards: Vec<hand::Card>,
-    curr_bet: i32,
-    last_player_to_raise: PlayerId,
-    bet_this_round: HashSet<PlayerId>,
-    has_raised: bool,
-    bets: HashMap<PlayerId, i32>,
-    /// A `BTreeMap` that maps from a bet amount to the players that have bet that amount.
-    pots: BTreeMap<i32, HashSet<PlayerId>>,
+    hand_state: Option<HandState>,
+    pot_manager: PotManager,
+    odd_chip_rule: OddChipRule,
+    betting_structure: BettingStructure,
+    sitting_out: HashSet<PlayerId>,
+    missed_blinds: HashMap<PlayerId, MissedBlinds>,
+    shows_losing_hands: HashSet<PlayerId>,
+    max_runs: u32,
+    run_choices: HashMap<PlayerId, u32>,
 }
 
 
@@ -64,30 +114,150 @@ impl Game {
         Game {
             game_id,
             num_players: 0,
-            players: VecDeque::new(),
-            players_in_round: HashSet::new(),
-            turn_queue: VecDeque::new(),
+            table: Table::default(),
             player_id_to_player: HashMap::new(),
-            big_blind,
+            blind_structure: BlindStructure::new(big_blind / 2, big_blind),
             initial_money,
-            dealer_location: 0,
+            deck: Deck::new(),
+            deck_source: Box::new(ShuffledDecks::from_os_rng()),
+            fair_shuffle: None,
+            dealt_shuffle: None,
+            hand_number: 0,
+            hand_history: None,
+            history_sinks: Vec::new(),
+            history_errors: Vec::new(),
             community_cards: Vec::<hand::Card>::new(),
-            curr_bet: big_blind,
-            last_player_to_raise: 0,
-            bet_this_round: HashSet::new(),
-            has_raised: false,
-            bets: HashMap::new(),
-            pots: BTreeMap::new(),
+            hand_state: None,
+            pot_manager: PotManager::new(),
+            odd_chip_rule: OddChipRule::FirstLeftOfButton,
+            betting_structure: BettingStructure::NoLimit,
+            sitting_out: HashSet::new(),
+            missed_blinds: HashMap::new(),
+            shows_losing_hands: HashSet::new(),
+            max_runs: 1,
+            run_choices: HashMap::new(),
+        }
+    }
+
+    /// Rebuilds the game `hand_history` was played in and starts the hand again.
+    ///
+    /// # Parameters
+    ///
+    /// * `hand_history` - The `HandHistory` of the hand to play again.
+    ///
+    /// # Returns
+    ///
+    /// * `Game` - A game with the same table, seats, stacks, blinds, betting structure and odd chip rule, dealing from
+    ///   the deck the hand was dealt from. The hole cards have been dealt and the blinds and antes posted, so the
+    ///   recorded actions can be applied with `apply_action`.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `hand_history` has no big blind, or seats two players in the same seat.
+    ///
+    /// # Notes
+    ///
+    /// The button and blinds are taken from the history rather than moved by the `Table`. A player who posted a big
+    /// blind or a dead small blind without being one of the blinds is given the blinds they missed, so they post them
+    /// again. The game has no `HandHistorySink`s.
+    pub fn from_hand_history(hand_history: &HandHistory) -> Game {
+        let blinds = *hand_history.blind_structure();
+        let mut game = Game::new(*hand_history.game_id(), blinds.get_big_blind(), 0);
+        game.table = Table::new(*hand_history.num_seats());
+        game.blind_structure = blinds;
+        game.betting_structure = *hand_history.betting_structure();
+        game.odd_chip_rule = *hand_history.odd_chip_rule();
+        game.hand_number = hand_history.hand_number() - 1;
+        game.deck_source = Box::new(StackedDecks::new(vec![hand_history.get_deck()]));
+
+        for seat in hand_history.seats() {
+            assert!(game.table.add_player_to_seat(*seat.player_id(), *seat.seat()), "Two players cannot sit in the same seat");
+            game.player_id_to_player.insert(*seat.player_id(), Player::new(*seat.player_id(), seat.name().clone(), *seat.stack()));
+            game.num_players += 1;
+        }
+        // Players who showed a hand they could have mucked keep doing so.
+        if let Some(showdown) = hand_history.showdown() {
+            game.shows_losing_hands.extend(showdown.shown().iter().map(|shown| *shown.player_id()));
+        }
+
+        let poster = |kind: ActionKind| hand_history.actions().iter().find(|action| *action.kind() == kind).map(|action| *action.player_id());
+        let small_blind = poster(ActionKind::SmallBlind);
+        let big_blind = poster(ActionKind::BigBlind).expect("Expected the hand history to have a big blind");
+        for action in hand_history.actions() {
+            let player_id = *action.player_id();
+            if Some(player_id) == small_blind || player_id == big_blind {
+                continue;
+            }
+
+            match action.kind() {
+                ActionKind::BigBlind => game.missed_blinds.entry(player_id).or_default().miss_big_blind(),
+                ActionKind::DeadSmallBlind => game.missed_blinds.entry(player_id).or_default().miss_small_blind(),
+                _ => {}
+            }
+        }
+
+        let players = hand_history.seats().iter().map(|seat| *seat.player_id()).collect();
+        game.clear_round_data();
+        // Everyone agreed to run the board as many times as it was run.
+        if !hand_history.runs().is_empty() {
+            game.max_runs = hand_history.runs().len() as u32;
+            game.run_choices = hand_history.seats().iter().map(|seat| (*seat.player_id(), game.max_runs)).collect();
+        }
+        game.deal_in(HandPositions::new(*hand_history.button(), small_blind, big_blind, players), false);
+        if let Some(replayed_history) = game.hand_history.as_mut() {
+            replayed_history.set_start_time(*hand_history.start_time());
         }
+
+        game
     }
 
+
+    /// Seats `player` in the first empty seat at the table.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the table is full.
     pub fn add_player(&mut self, player: Player) {
-        self.players.push_back(player.get_player_id());
+        self.table.add_player(player.get_player_id()).expect("The table is full");
         self.player_id_to_player.insert(player.get_player_id(), player.clone());
         self.num_players += 1;
     }
 
 
+    /// Removes `player_id` from the table.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Player>` - The player who left, with the chips they left with, or `None` if they were not at the table.
+    ///
+    /// # Notes
+    ///
+    /// If the player is still in the hand being played, their hand is folded and the chips they have put in stay in the pot.
+    pub fn remove_player(&mut self, player_id: PlayerId) -> Option<Player> {
+        if !self.table.remove_player(&player_id) {
+            return None;
+        }
+
+        if self.is_hand_in_progress() && self.get_hand_state_mut().is_in_hand(&player_id) {
+            self.record_action(player_id, ActionKind::Fold, 0, false);
+            self.get_hand_state_mut().remove(player_id);
+            self.pot_manager.fold(player_id);
+            self.advance_hand();
+        }
+
+        self.num_players -= 1;
+        self.sitting_out.remove(&player_id);
+        self.missed_blinds.remove(&player_id);
+        self.shows_losing_hands.remove(&player_id);
+        self.player_id_to_player.remove(&player_id)
+    }
+
+
+    pub fn get_table(&self) -> &Table {
+        &self.table
+    }
+
+
     pub fn get_num_players(&self) -> i32 {
         self.num_players
     }
@@ -99,717 +269,1084 @@ impl Game {
 
 
     pub fn get_big_blind(&self) -> i32 {
-        self.big_blind
+        self.blind_structure.get_big_blind()
     }
 
 
-    fn print_community_cards(&self) {
-        println!("Community Cards:");
-        if self.community_cards.len() != 0 {
-            for card in &self.community_cards {
-                println!("{}", card);
-            }
-        } else {
-            println!("Community cards have not been dealt.")
-        }
-
+    pub fn get_blind_structure(&self) -> BlindStructure {
+        self.blind_structure
     }
 
-    fn format_community_cards(&self) -> VecDeque<String> {
-        let mut output = VecDeque::<String>::new();
-        if self.community_cards.len() != 0 {
-            for card in &self.community_cards {
-                output.push_back(card.to_string());
-            }
-        } else {
-            output.push_back("Community cards have not been dealt.".to_string());
-        }
 
-        output
+    /// Sets the `BlindStructure`. It takes effect from the next hand.
+    pub fn set_blind_structure(&mut self, blind_structure: BlindStructure) {
+        self.blind_structure = blind_structure;
     }
 
 
-    /// Prints the current turn state of the game.
-    ///
-    /// # Notes
-    ///
-    /// This function prints the current turn state of the game in a formatted manner. It displays the players in the current round,
-    /// with arrows pointing to the player whose turn it is. The players are displayed in the order they will play their turns.
-    ///
-    /// This function does not return any value and does not modify the game state.
-    ///
-    /// # Panics
-    ///
-    /// This function will panic if there are no players in the current round.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// // Assuming `game` is an instance of the `Game` struct
-    /// game.print_turn_state();
-    /// ```
-    fn print_turn_state(&self) {
-        if self.turn_queue.len() == 0 {
-            return;
-        }
-
-        let players = self.turn_queue.clone();
-        let width =  players.front().expect(PLAYER_NOT_FOUND_ERROR).to_string().len() / 2
-            + "Player: ".len();
-
-        let mut output = VecDeque::<VecDeque<String>>::new();
+    /// Stops dealing `player_id` into new hands. Blinds that pass them while they sit out are recorded in
+    /// `missed_blinds`.
+    pub fn sit_out(&mut self, player_id: PlayerId) {
+        self.sitting_out.insert(player_id);
+    }
 
-        players.iter().enumerate()
-            .for_each(|(i, player)| {
-                let player_str = player.to_string();
-                let player_contribution = self.bets.get(player).unwrap_or(&0).to_string();
-                let player_money = self.player_id_to_player.get(player).expect(PLAYER_NOT_FOUND_ERROR).get_money().to_string();
 
-                let mut this_output = VecDeque::<String>::new();
+    /// Deals `player_id` back in from the next hand. They post any blinds they missed when they are next dealt in.
+    pub fn sit_in(&mut self, player_id: PlayerId) {
+        self.sitting_out.remove(&player_id);
+    }
 
-                if i == 0 {
-                    this_output.push_back(" ".repeat(width) + "|");
-                    this_output.push_back(" ".repeat(width) + "|");
-                    this_output.push_back(" ".repeat(width) + "v");
-                    // this_output.push_back(" ".repeat(width) + "a");
-                    // this_output.push_back("Next to go.".to_string());
-                } else {
-                    for _ in 0..3 {
-                        this_output.push_back("".to_string());
-                    }
-                }
 
-                this_output.push_back("Player: ".to_string() + &*player_str);
-                this_output.push_back("Contribution: ".to_string() + &*player_contribution);
-                this_output.push_back("Money: ".to_string() + &*player_money);
+    pub fn is_sitting_out(&self, player_id: &PlayerId) -> bool {
+        self.sitting_out.contains(player_id)
+    }
 
-                output.push_back(this_output);
-            });
-            // .collect::<Vec<_>>()
-            // .join(", ");
 
-        // let turn_state = " Turn State ";
-        //
-        // let dashes = "-".repeat((max((players_str.len() as i32) - (turn_state.len() as i32), 0) / 2) as usize);
-        //
-        // println!();
-        // println!("{}", dashes.clone() + turn_state + &dashes);
-        // println!("{}", " ".repeat(width) + "|");
-        // println!("{}", " ".repeat(width) + "|");
-        // println!("{}", " ".repeat(width) + "▼");
-        // println!("{}", players_str);
-        // println!();
+    /// Returns the blinds `player_id` missed while sitting out and has not posted yet.
+    pub fn get_missed_blinds(&self, player_id: &PlayerId) -> MissedBlinds {
+        self.missed_blinds.get(player_id).cloned().unwrap_or_default()
+    }
 
-        let output_str = utils::format_next_to_each_other(output);
 
-        let turn_state = " Turn State ";
+    /// Sets whether `player_id` shows their hand at showdown when they could muck it. Players muck by default.
+    pub fn set_shows_losing_hands(&mut self, player_id: PlayerId, shows_losing_hands: bool) {
+        if shows_losing_hands {
+            self.shows_losing_hands.insert(player_id);
+        } else {
+            self.shows_losing_hands.remove(&player_id);
+        }
+    }
 
-        let num_dashes_total = output_str.split("\n").collect::<Vec<&str>>().iter().map(|str| str.len()).max().expect("Error in calculating dashes");
 
-        let dashes = "-".repeat((max((num_dashes_total as i32) - (turn_state.len() as i32), 0) / 2) as usize);
+    pub fn mucks_losing_hands(&self, player_id: &PlayerId) -> bool {
+        !self.shows_losing_hands.contains(player_id)
+    }
 
-        println!();
-        println!("{}", dashes.clone() + turn_state + &dashes);
-        println!("{}", output_str);
 
-        // println!("------------------ Turn State ------------------\n{}", output_str);
+    pub fn get_max_runs(&self) -> u32 {
+        self.max_runs
     }
 
-    /// Starts the game and handles the main game loop.
+
+    /// Sets the most times the board can be run when the players are all in before the river. It takes effect from the
+    /// next hand. If it is 1, the board is only run once.
     ///
-    /// # Parameters
+    /// # Panics
     ///
-    /// * `debug`: A `bool` indicating whether to print debug information.
+    /// This function will panic if `max_runs` is 0.
+    pub fn set_max_runs(&mut self, max_runs: u32) {
+        assert!(max_runs > 0, "The board must be run at least once");
+        self.max_runs = max_runs;
+    }
+
+
+    /// Records how many times `player_id` agrees to run the board if everyone still in the hand is all in before the
+    /// river. A player can choose before the runout or while the game is waiting for them.
     ///
-    /// # Panics
+    /// # Returns
     ///
-    /// This function will panic if there are no players in the game, or if a player tries to raise by an amount less than the current bet.
+    /// * `Result<(), RunoutError>` - An error if the game only runs the board once, there is no hand, the player is not
+    ///   in it, or `runs` is 0 or more than `max_runs`.
     ///
     /// # Notes
     ///
-    /// This function starts the game, deals the hole cards, the flop, the turn, and the river, determines the winner, and rotates the dealer's location. It also clears the bets at the start of the game. If `debug` is `true`, it prints a message indicating that the game has started.
-    ///
-    /// This function does not return any value.
-    pub fn start_game(&mut self, debug: bool) {
-        if debug {
-            println!("Game {} has started!", self.game_id);
+    /// The board is run the fewest times any player still in the hand chose, so everyone has to agree to run it more
+    /// than once. Once the last of them has chosen, the rest of the hand is played out.
+    pub fn choose_run_count(&mut self, player_id: PlayerId, runs: u32) -> Result<(), RunoutError> {
+        if self.max_runs <= 1 {
+            return Err(RunoutError::NotAllowed);
+        }
+        if !self.player_id_to_player.contains_key(&player_id) {
+            return Err(RunoutError::UnknownPlayer { player_id });
+        }
+        if !self.is_hand_in_progress() {
+            return Err(RunoutError::NoHandInProgress);
+        }
+        if !self.hand_state.as_ref().is_some_and(|hand_state| hand_state.is_in_hand(&player_id)) {
+            return Err(RunoutError::NotInHand);
+        }
+        if runs == 0 || runs > self.max_runs {
+            return Err(RunoutError::InvalidRunCount { maximum: self.max_runs });
         }
 
-        let max_round = 5;
-        let mut round = 1;
-
-        while self.players.len() > 1 && round < max_round {
-            println!("Starting round #{}", round);
-
-            self.play_one_round(debug);
-            // assert_eq!(self.get_total_player_money(), self.players.len() as i32 * self.initial_money, "Incorrect amount of money in the game");
+        self.run_choices.insert(player_id, runs);
+        self.advance_hand();
+        Ok(())
+    }
 
-            // rotate the dealers and players queue
-            self.players.rotate_left(1);
 
-            self.dealer_location = (self.dealer_location + 1) % self.players.len() as i16;
-            round += 1;
+    /// Returns the players the game is waiting on to choose how many times to run the board, or an empty `Vec` if it is
+    /// not waiting on anyone.
+    pub fn get_players_to_choose_runs(&self) -> Vec<PlayerId> {
+        match self.hand_state.as_ref() {
+            Some(hand_state) if self.max_runs > 1 && !hand_state.is_complete() && hand_state.next_to_act().is_none() && hand_state.is_runout() => {
+                hand_state.get_players_in_hand().into_iter()
+                    .filter(|player_id| !self.run_choices.contains_key(player_id))
+                    .collect()
+            },
+            _ => Vec::new()
+        }
+    }
 
-            // self.players_in_round.clear();
 
+    pub fn get_odd_chip_rule(&self) -> OddChipRule {
+        self.odd_chip_rule
+    }
 
 
-            // for player_id in self.players.iter() {
-            //     let player = self.player_id_to_player.get(player_id).expect(PLAYER_NOT_FOUND_ERROR);
-            //     if player.get_money() > 0 {
-            //         self.turn_queue.push_back(*player_id);
-            //     }
-            // }
+    pub fn set_odd_chip_rule(&mut self, odd_chip_rule: OddChipRule) {
+        self.odd_chip_rule = odd_chip_rule;
+    }
 
-            if self.turn_queue.len() == 1 {
-                println!("There is a winner");
-                for player_id in self.players.iter() {
-                    let player = self.player_id_to_player.get(player_id).expect(PLAYER_NOT_FOUND_ERROR);
 
-                    dbg!(player);
-                }
-                return;
-            }
+    pub fn get_betting_structure(&self) -> BettingStructure {
+        self.betting_structure
+    }
 
 
-        }
+    /// Sets the `BettingStructure`. It takes effect from the next street.
+    pub fn set_betting_structure(&mut self, betting_structure: BettingStructure) {
+        self.betting_structure = betting_structure;
     }
 
-    fn play_one_round(&mut self, debug: bool) {
 
-        // // Initialize the pots map.
-        // self.pots.insert(BTreeSet::from_iter(self.players.iter().cloned().collect::<Vec<_>>()), 0);
+    /// Sets the `DeckSource` that gives the deck for each hand. It takes effect from the next hand.
+    ///
+    /// # Notes
+    ///
+    /// Cards are dealt from the top of the deck with no burn cards: two hole cards to each player in turn, starting
+    /// with the first player after the button, and then the flop, turn and river.
+    pub fn set_deck_source(&mut self, deck_source: Box<dyn DeckSource>) {
+        self.deck_source = deck_source;
+    }
 
-        let mut deck = hand::Card::new_full_deck();
 
-        if !self.deal_hole_cards(&mut deck, debug) { self.determine_winner(); self.clear_round_data(); return }
+    /// Turns provably fair shuffles on or off. While they are on, each hand is dealt from a `FairShuffle` instead of
+    /// the `DeckSource`.
+    ///
+    /// # Notes
+    ///
+    /// Before each hand, the server sends the players `get_shuffle_commitment` and they may add entropy with
+    /// `add_shuffle_entropy`. After the hand, the server sends them `get_shuffle_reveal`, which they can check with
+    /// `fair_shuffle::verify_shuffle`.
+    pub fn set_provably_fair(&mut self, provably_fair: bool) {
+        if !provably_fair {
+            self.fair_shuffle = None;
+        } else if self.fair_shuffle.is_none() {
+            self.fair_shuffle = Some(FairShuffle::new());
+        }
+    }
 
-        if !self.deal_flop(&mut deck, debug) { self.determine_winner(); self.clear_round_data(); return }
 
-        // Deal the turn.
-        if !self.deal_single_card(&mut deck, debug) { self.determine_winner(); self.clear_round_data(); return }
+    pub fn is_provably_fair(&self) -> bool {
+        self.fair_shuffle.is_some()
+    }
 
-        // Deal the river.
-        if !self.deal_single_card(&mut deck, debug) { self.determine_winner(); self.clear_round_data(); return }
 
-        self.determine_winner();
-        self.clear_round_data();
+    /// Returns the commitment to the server seed for the next hand, or `None` if the game does not use provably fair shuffles.
+    pub fn get_shuffle_commitment(&self) -> Option<&str> {
+        self.fair_shuffle.as_ref().map(|fair_shuffle| fair_shuffle.get_commitment())
     }
 
-    fn clear_round_data(&mut self) {
-        self.pots.clear();
-        self.community_cards.clear();
-        self.players_in_round.clear();
-        self.turn_queue.clear();
-        self.curr_bet = 0;
-        self.bet_this_round.clear();
-        self.bets.clear();
-    }
 
-    /// Deals two hole cards to each player from the deck and initiates the first round of betting.
-    ///
-    /// # Arguments
-    ///
-    /// * `deck` - A mutable reference to a `HashSet` of `hand::Card` representing the deck of cards.
-    /// * `debug` - A `bool` indicating whether debug information should be printed to the console.
+    /// Adds entropy from `player_id` to the shuffle for the next hand.
     ///
     /// # Returns
     ///
-    /// * `bool` - Returns `true` if a winner is determined during the first round of betting, otherwise returns `false`.
-    ///
-    /// # Panics
-    ///
-    /// This function will panic if there are no players in the game, or if the deck runs out of cards.
-    ///
-    /// # Notes
-    ///
-    /// This function initializes a full deck of cards, deals two hole cards to each player, and handles the small and big blinds. It then calls `circle_players` to rotate through the players in the current round, prompting each to make a decision. After `circle_players` returns, it resets the current bet to 0.
-    ///
-    /// If `debug` is `true`, this function also prints debug information, such as the size of the deck, the number of players in the current round, the size of the blinds, and the hole cards of each player.
-    fn deal_hole_cards(&mut self, deck: &mut HashSet<hand::Card>, debug: bool) -> bool {
-
-        if debug {
-            println!("Dealing hole cards.");
+    /// * `Result<(), ShuffleError>` - An error if the game does not use provably fair shuffles, the player is not
+    ///   seated, or the entropy is too long.
+    pub fn add_shuffle_entropy(&mut self, player_id: PlayerId, entropy: String) -> Result<(), ShuffleError> {
+        if self.table.get_seat(&player_id).is_none() {
+            return Err(ShuffleError::UnknownPlayer { player_id });
         }
 
-        // Initialize the previous contributions map.
-        let mut prev_contributions = HashMap::<PlayerId, i32>::new();
+        self.fair_shuffle.as_mut().ok_or(ShuffleError::NotProvablyFair)?.add_entropy(player_id, entropy)
+    }
 
-        // deal the hole cards to each player
-        // for _ in 0..self.players.len() {
-        for &player_id in self.players.iter() {
-            // let player_id = self.players.pop_front().expect(PLAYER_NOT_FOUND_ERROR);
-            let mut player = self.player_id_to_player.get_mut(&player_id).expect(PLAYER_NOT_FOUND_ERROR);
 
-            if (player.get_money() == 0) {
-                continue
-            }
+    /// Returns the revealed shuffle of the last hand, or `None` if a hand is still being played or the last hand was
+    /// not dealt with a provably fair shuffle.
+    pub fn get_shuffle_reveal(&self) -> Option<ShuffleReveal> {
+        if self.is_hand_in_progress() {
+            return None;
+        }
 
-            let mut hole_cards = Vec::<Card>::new();
-            for _ in 0..2 {
-                let card = deck.iter().choose(&mut rand::thread_rng()).expect("Deck ran out of cards").clone();
-                hole_cards.push(card);
-                deck.remove(&card);
-            }
+        self.dealt_shuffle.as_ref().map(|fair_shuffle| fair_shuffle.reveal())
+    }
 
-            player.set_hole_cards(hole_cards.clone());
-            self.turn_queue.push_back(player_id);
-            self.players_in_round.insert(player_id);
-        }
 
-        let num_players_in_round = self.turn_queue.len() as i32;
+    /// Adds a sink that the `HandHistory` of every hand is sent to when the hand ends.
+    pub fn add_history_sink(&mut self, history_sink: Box<dyn HandHistorySink>) {
+        self.history_sinks.push(history_sink);
+    }
 
-        if debug {
-            println!("Size of deck: {}", deck.len());
-            println!("Number of players this round: {}", num_players_in_round);
-            println!("Size of blinds: {}, {}", self.big_blind, self.big_blind / 2);
-        }
 
-        // have the small blind and big blind pay
-        let mut prev_player: PlayerId;
+    /// Takes the errors of the sinks that could not record a hand since this was last called, so the caller can report
+    /// the hands that were not recorded.
+    pub fn take_history_errors(&mut self) -> Vec<HistorySinkError> {
+        std::mem::take(&mut self.history_errors)
+    }
 
-        let player_id = self.turn_queue.pop_front().expect(PLAYER_NOT_FOUND_ERROR);
-        prev_player = player_id;
 
+    /// Returns the `HandHistory` of the current hand so far, or of the last hand if none is being played.
+    pub fn get_hand_history(&self) -> Option<&HandHistory> {
+        self.hand_history.as_ref()
+    }
 
-        self.make_player_bet(player_id, self.big_blind / 2, 0);
-        prev_contributions.insert(player_id, self.big_blind / 2);
 
-        self.last_player_to_raise = player_id;
-        self.turn_queue.push_back(player_id);
+    /// Returns the `HandState` of the current hand, or `None` if no hand has been started.
+    pub fn get_hand_state(&self) -> Option<&HandState> {
+        self.hand_state.as_ref()
+    }
 
-        let player_id = self.turn_queue.pop_front().expect(PLAYER_NOT_FOUND_ERROR);
 
-        prev_player = player_id;
+    /// Returns the street of the current hand, or `None` if no hand has been started.
+    pub fn get_street(&self) -> Option<Street> {
+        self.hand_state.as_ref().map(|hand_state| hand_state.get_street())
+    }
 
-        self.make_player_bet(player_id, self.big_blind, 0);
-        prev_contributions.insert(player_id, self.big_blind);
 
-        self.last_player_to_raise = player_id;
-        self.turn_queue.push_back(player_id);
+    pub fn get_community_cards(&self) -> &Vec<hand::Card> {
+        &self.community_cards
+    }
 
 
-        self.has_raised = false;
-        let has_winner: bool = self.circle_players(&mut Some(prev_contributions), &mut Some(prev_player), true);
+    /// Returns the player whose turn it is, or `None` if no hand is being played.
+    pub fn next_to_act(&self) -> Option<PlayerId> {
+        self.hand_state.as_ref().and_then(|hand_state| hand_state.next_to_act())
+    }
 
-        self.curr_bet = 0;
 
-        has_winner
+    /// Returns whether a hand has been started and has not finished yet.
+    pub fn is_hand_in_progress(&self) -> bool {
+        self.hand_state.as_ref().is_some_and(|hand_state| !hand_state.is_complete())
     }
 
 
-    /// Deals the flop (the first three community cards) in the game.
+    /// Starts the game and handles the main game loop.
     ///
     /// # Parameters
     ///
-    /// * `deck`: A mutable reference to a `HashSet` of `hand::Card`s representing the current deck.
-    ///
-    /// # Returns
-    ///
-    /// * `bool` - Returns `true` if a winner is determined during the second round of betting, otherwise returns `false`.
+    /// * `agents`: A `HashMap` that maps each `PlayerId` to the `PlayerAgent` that makes their decisions.
+    /// * `max_hands`: The most hands to play, or `None` to play until only one player is left.
+    /// * `debug`: A `bool` indicating whether to print debug information.
     ///
     /// # Panics
     ///
-    /// This function will panic if there are no cards left in the deck.
+    /// This function will panic if there are no players in the game, or if a player has no agent.
     ///
     /// # Notes
     ///
-    /// This function randomly selects three cards from the deck, removes them from the deck, and adds them to the community cards. It then prints the community cards and the size of the deck. After that, it calls `circle_players` to rotate through the players in the current round, prompting each to make a decision. After `circle_players` returns, it resets the current bet to 0.
-    fn deal_flop(&mut self, deck: &mut HashSet<hand::Card>, debug: bool) -> bool {
+    /// This function plays hands until only one player is left at the table or `max_hands` have been played. The `Table` moves the button after each hand, and players who bust are removed from it. If `debug` is `true`, it prints when the game starts, each hand, and the winner.
+    ///
+    /// This function does not return any value.
+    pub fn start_game(&mut self, agents: &mut HashMap<PlayerId, Box<dyn PlayerAgent>>, max_hands: Option<u64>, debug: bool) {
         if debug {
-            println!("Dealing flop.");
+            println!("Game {} has started!", self.game_id);
+        }
+
+        let total_money = self.get_total_player_money();
+        let mut num_hands = 0;
+
+        while self.table.get_players().len() > 1 && max_hands.is_none_or(|max_hands| num_hands < max_hands) {
+            if debug {
+                println!("Starting hand #{}", num_hands + 1);
+            }
+
+            self.play_one_round(agents, debug);
+            for error in self.take_history_errors() {
+                eprintln!("{}", error);
+            }
+            assert_eq!(self.get_total_player_money(), total_money, "Incorrect amount of money in the game");
+            num_hands += 1;
+        }
+
+        if debug && self.table.get_players().len() == 1 {
+            let winner = self.player_id_to_player.get(&self.table.get_players()[0]).expect(PLAYER_NOT_FOUND_ERROR);
+            println!("{} wins the game with {} chips", winner.get_name(), winner.get_money());
         }
+    }
 
-        let mut community_cards = Vec::<hand::Card>::new();
 
-        for _ in 0..3 {
-            let card = deck.iter().choose(&mut rand::thread_rng()).expect("Deck ran out of cards").clone();
-            community_cards.push(card);
-            deck.remove(&card);
+    /// Plays one hand from the deal to the payout, asking each player's agent for their actions.
+    fn play_one_round(&mut self, agents: &mut HashMap<PlayerId, Box<dyn PlayerAgent>>, debug: bool) {
+        self.start_hand(debug);
+
+        while self.is_hand_in_progress() {
+            match self.next_to_act() {
+                Some(player_id) => self.ask_player(agents, player_id),
+                None => self.ask_run_counts(agents)
+            }
         }
+    }
+
+
+    fn clear_round_data(&mut self) {
+        self.pot_manager.clear();
+        self.community_cards.clear();
+        // A new fair shuffle with a new server seed takes the place of the one being dealt.
+        self.dealt_shuffle = self.fair_shuffle.as_mut().map(std::mem::take);
+        self.deck = match self.dealt_shuffle.as_ref() {
+            Some(fair_shuffle) => fair_shuffle.deck(),
+            None => self.deck_source.next_deck()
+        };
+        self.hand_state = None;
+        self.run_choices.clear();
+    }
 
-        self.community_cards = community_cards.clone();
 
-        let has_winner: bool = self.circle_players(&mut None, &mut None, false);
+    /// Returns whether `player_id` will be dealt into the next hand. It takes the fields it needs rather than `self`
+    /// so that it can be used while the `Table` is borrowed.
+    fn is_active(player_id_to_player: &HashMap<PlayerId, Player>, sitting_out: &HashSet<PlayerId>, player_id: &PlayerId) -> bool {
+        !sitting_out.contains(player_id) && player_id_to_player.get(player_id).is_some_and(|player| player.get_money() > 0)
+    }
 
-        self.curr_bet = 0;
 
-        has_winner
+    /// Removes the players who have no money left from the table.
+    fn remove_busted_players(&mut self) {
+        for player_id in self.table.get_players() {
+            if self.player_id_to_player.get(&player_id).expect(PLAYER_NOT_FOUND_ERROR).get_money() == 0 {
+                self.table.remove_player(&player_id);
+                self.num_players -= 1;
+            }
+        }
     }
 
 
-    /// Deals a single card to the community cards in the game.
+    /// Starts a new hand. Deals two hole cards to each player with money who is not sitting out, and has them post their blinds and antes.
     ///
     /// # Parameters
     ///
-    /// * `deck`: A mutable reference to a `HashSet` of `hand::Card`s representing the current deck.
-    ///
-    /// # Returns
-    ///
-    /// * `bool` - Returns `true` if a winner is determined during the third round of betting, otherwise returns `false`.
-    ///
+    /// * `debug` - A `bool` indicating whether debug information should be printed to the console.
     ///
     /// # Panics
     ///
-    /// This function will panic if there are no cards left in the deck.
+    /// This function will panic if fewer than two players can be dealt in.
     ///
     /// # Notes
     ///
-    /// This function randomly selects a card from the deck, removes it from the deck, and adds it to the community cards. It then prints the community cards and the size of the deck. After that, it calls `circle_players` to rotate through the players in the current round, prompting each to make a decision. After `circle_players` returns, it resets the current bet to 0.
+    /// The `Table` moves the button and decides who posts the blinds, following the dead button rule. Heads up, the button posts the small blind. The player after the big blind posts the straddle if the `BlindStructure` has one and at least three players are dealt in. Every player then posts the ante and the big blind posts the big blind ante. Antes are dead money, so they go in the pot without counting towards anyone's bet.
+    ///
+    /// A player who missed blinds while sitting out posts the big blind live and the small blind dead when they are dealt back in, unless they are one of the blinds this hand.
     ///
-    /// This function is used for both dealing the turn and the river in a game of Texas Hold'em poker.
-    fn deal_single_card(&mut self, deck: &mut HashSet<hand::Card>, debug: bool) -> bool {
+    /// Preflop, the first player after the last live blind acts first. The hand is then advanced one action at a time with `apply_action`, and `next_to_act` says whose turn it is.
+    ///
+    /// If `debug` is `true`, this function also prints debug information, such as the size of the deck, the number of players in the current round, and the size of the blinds.
+    pub fn start_hand(&mut self, debug: bool) {
         if debug {
-            println!("Dealing single cards.");
+            println!("Dealing hole cards.");
         }
 
-        let card = deck.iter().choose(&mut rand::thread_rng()).expect("Deck ran out of cards").clone();
-        self.community_cards.push(card.clone());
-        deck.remove(&card);
+        self.clear_round_data();
 
-        let has_winner: bool = self.circle_players(&mut None, &mut None, false);
+        let player_id_to_player = &self.player_id_to_player;
+        let sitting_out = &self.sitting_out;
+        let positions = self.table
+            .next_hand(|player_id| Self::is_active(player_id_to_player, sitting_out, player_id))
+            .expect("A hand needs at least two players with money");
 
-        self.curr_bet = 0;
+        for &player_id in positions.get_missed_big_blind() {
+            let missed_blinds = self.missed_blinds.entry(player_id).or_default();
+            missed_blinds.miss_small_blind();
+            missed_blinds.miss_big_blind();
+        }
+        if let Some(player_id) = positions.get_missed_small_blind() {
+            self.missed_blinds.entry(player_id).or_default().miss_small_blind();
+        }
 
-        has_winner
+        self.deal_in(positions, debug);
     }
 
 
-    fn determine_winner(&mut self) {
-        dbg!(&self.turn_queue);
-        dbg!(&self.players_in_round);
+    /// Deals the hole cards and posts the blinds and antes of a hand with the given positions. This is the part of
+    /// `start_hand` that comes after the `Table` has moved the button.
+    fn deal_in(&mut self, positions: HandPositions, debug: bool) {
+        let seats = positions.get_players().clone();
+
+        // deal the hole cards to each player
+        for player_id in seats.iter() {
+            let hole_cards = self.deal_cards(2);
+            let player = self.player_id_to_player.get_mut(player_id).expect(PLAYER_NOT_FOUND_ERROR);
+            player.set_hole_cards(hole_cards);
+        }
 
-        if self.players_in_round.len() == 1 {
+        let blinds = self.blind_structure;
 
-            let money_earned = self.bets.values().sum();
-            let player_id = self.players_in_round.iter().next().expect(PLAYER_NOT_FOUND_ERROR);
-            let mut player: &mut Player = self.player_id_to_player.get_mut(player_id).expect(PLAYER_NOT_FOUND_ERROR);
+        self.hand_number += 1;
+        let seat_records = seats.iter()
+            .map(|player_id| {
+                let player = self.player_id_to_player.get(player_id).expect(PLAYER_NOT_FOUND_ERROR);
+                let seat = self.table.get_seat(player_id).expect("Expected a player in the hand to be seated");
+                SeatRecord::new(seat, *player_id, player.get_name(), player.get_money(), player.get_hole_cards())
+            })
+            .collect();
+        self.hand_history = Some(HandHistory::new(self.game_id, self.hand_number, self.table.get_num_seats(), blinds,
+                                                  self.betting_structure, self.odd_chip_rule, positions.get_button(),
+                                                  seat_records));
 
-            player.increment_money(money_earned);
-            return;
+        if debug {
+            println!("Size of deck: {}", self.deck.len());
+            println!("Number of players this round: {}", seats.len());
+            println!("Size of blinds: {}, {}", blinds.get_big_blind(), blinds.get_small_blind());
         }
 
+        let small_blind = positions.get_small_blind();
+        let big_blind = positions.get_big_blind();
+        let big_blind_index = seats.iter().position(|&p| p == big_blind).expect(PLAYER_NOT_FOUND_ERROR);
+        let straddle = if seats.len() >= 3 {
+            blinds.get_straddle().map(|amount| (seats[(big_blind_index + 1) % seats.len()], amount))
+        } else {
+            None
+        };
+        let first_to_act = (big_blind_index + if straddle.is_some() { 2 } else { 1 }) % seats.len();
 
+        let min_bet = self.betting_structure.get_min_bet(Street::Preflop, blinds.get_big_blind());
+        self.hand_state = Some(HandState::new(seats.clone(), first_to_act, min_bet));
+
+        // have the small blind and big blind pay
+        if let Some(small_blind) = small_blind {
+            self.post_blind(small_blind, blinds.get_small_blind(), ActionKind::SmallBlind);
+        }
+        self.post_blind(big_blind, blinds.get_big_blind(), ActionKind::BigBlind);
+        if let Some((player_id, amount)) = straddle {
+            self.post_blind(player_id, amount, ActionKind::Straddle);
+        }
 
+        for &player_id in seats.iter() {
+            let missed_blinds = self.missed_blinds.remove(&player_id).unwrap_or_default();
+            if Some(player_id) == small_blind || player_id == big_blind {
+                continue;
+            }
 
-        let possible_winners = self.players_in_round.iter().cloned().collect::<HashSet<PlayerId>>();
+            if missed_blinds.has_missed_big_blind() {
+                self.post_blind(player_id, blinds.get_big_blind(), ActionKind::BigBlind);
+            }
+            if missed_blinds.has_missed_small_blind() {
+                self.post_dead_money(player_id, blinds.get_small_blind(), ActionKind::DeadSmallBlind);
+            }
+        }
 
-        let mut winners: HashMap<i32, PlayerId> = HashMap::new();
+        for &player_id in seats.iter() {
+            self.post_dead_money(player_id, blinds.get_ante(), ActionKind::Ante);
+        }
+        self.post_dead_money(big_blind, blinds.get_big_blind_ante(), ActionKind::BigBlindAnte);
 
-        for (i, (pot, players)) in self.pots.iter().enumerate() {
-            let player_to_seven_cards: HashMap<PlayerId, OnePlayerAllPossibleCards> = players.iter()
-                .filter(|&p| possible_winners.contains(p))
-                .fold(HashMap::<PlayerId, OnePlayerAllPossibleCards>::new(), |mut map, player_id| {
-                    let player: &Player = self.player_id_to_player.get(player_id).expect(PLAYER_NOT_FOUND_ERROR);
+        self.advance_hand();
+    }
 
-                    let seven_cards_vec: Vec<Card> = player.get_hole_cards().iter()
-                        .chain(self.community_cards.iter())
-                        .cloned()
-                        .collect::<Vec<_>>();
-                    let seven_cards: OnePlayerAllPossibleCards = OnePlayerAllPossibleCards::new(seven_cards_vec);
 
-                    map.insert(*player_id, seven_cards);
-                    map
-                });
+    /// Removes the top `count` cards from the deck.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the deck runs out of cards.
+    fn deal_cards(&mut self, count: usize) -> Vec<Card> {
+        (0..count).map(|_| self.deck.deal().expect("Deck ran out of cards")).collect()
+    }
 
-            let winner_id: PlayerId = OnePlayerAllPossibleCards::get_winner(&player_to_seven_cards);
 
-            // let winner = self.player_id_to_player.get(&winner_id).expect(PLAYER_NOT_FOUND_ERROR);
+    /// Has `player_id` put in a live blind of `amount`, or all their money if they have less. A live blind counts
+    /// towards the player's bet.
+    fn post_blind(&mut self, player_id: PlayerId, amount: i32, kind: ActionKind) {
+        let money = self.player_id_to_player.get(&player_id).expect(PLAYER_NOT_FOUND_ERROR).get_money();
+        let amount = amount.min(money);
+        let contribution = self.get_street_contribution(&player_id) + amount;
 
-            winners.insert(*pot, winner_id);
-            // winner.increment_money(*pot);
-            //
-            // if i == self.pots.len() - 1 {
-            //     println!("The winner is: {}", winner.get_name());
-            //     println!("With a hand of: {}", player_to_seven_cards.get(&winner_id).expect(PLAYER_NOT_FOUND_ERROR).to_string());
-            //     println!("They now have: {}", winner.get_money());
-            // }
-        }
+        self.make_player_bet(player_id, contribution);
+        self.get_hand_state_mut().post(player_id, contribution, amount == money);
+        self.record_action(player_id, kind, amount, amount == money);
+    }
 
-        for (player_id, bet) in self.bets.iter().filter(|(id, _)| { possible_winners.contains(id) }) {
-            let smaller_bets = self.pots.keys().filter(|&x| x <= bet).cloned().collect::<Vec<_>>();
 
-            let mut sofar = 0;
-            let mut multiplier = 1;
+    /// Has `player_id` put `amount`, or all their money if they have less, straight into the pot. Dead money does not
+    /// count towards the player's bet.
+    fn post_dead_money(&mut self, player_id: PlayerId, amount: i32, kind: ActionKind) {
+        let player = self.player_id_to_player.get_mut(&player_id).expect(PLAYER_NOT_FOUND_ERROR);
+        let amount = amount.min(player.get_money());
+        if amount == 0 {
+            return;
+        }
 
-            for &sub_bet in smaller_bets.iter().rev() {
-                if winners.get(&sub_bet).expect("Bet value was not found in winners map") != player_id {
-                    sofar = max(sofar - sub_bet, 0);
-                    break;
-                }
+        player.set_money(player.get_money() - amount);
+        let is_all_in = player.get_money() == 0;
+        self.pot_manager.add_contribution(player_id, amount);
 
-                if sub_bet > sofar {
-                    sofar = sub_bet;
-                    multiplier = self.pots.get(&sub_bet).expect("Bet value was not found in pots map").len()
-                }
-            }
+        if is_all_in {
+            self.get_hand_state_mut().set_all_in(player_id);
+        }
+        self.record_action(player_id, kind, amount, is_all_in);
+    }
 
-            let money_earned = sofar * (multiplier as i32);
 
-            let player = self.player_id_to_player.get_mut(player_id).expect(PLAYER_NOT_FOUND_ERROR);
-            player.increment_money(money_earned);
+    /// Adds an action by `player_id` on the current street to the `HandHistory`.
+    fn record_action(&mut self, player_id: PlayerId, kind: ActionKind, amount: i32, is_all_in: bool) {
+        let street = self.hand_state.as_ref().expect("Expected a hand to be in progress").get_street();
+        if let Some(hand_history) = self.hand_history.as_mut() {
+            hand_history.record_action(ActionRecord::new(street, player_id, kind, amount, is_all_in));
         }
     }
 
 
-    /// Prompts the current player to make a decision for their turn.
-    ///
-    /// # Parameters
-    ///
-    /// * `prev_contributions`: A mutable reference to a `HashMap` that maps `PlayerId`s to the amount they have contributed to the pot in the current round.
-    ///
-    /// # Panics
-    ///
-    /// This function will panic if there are no players in the current round, or if the player tries to raise by an amount less than the current bet.
+    fn get_hand_state_mut(&mut self) -> &mut HandState {
+        self.hand_state.as_mut().expect("Expected a hand to be in progress")
+    }
+
+
+    /// Moves the hand on once nobody has to act on the current street.
     ///
     /// # Notes
     ///
-    /// This function prompts the current player to fold, raise, or check/call, depending on the current state of the game. It then updates the game state based on the player's decision.
+    /// Once a street's betting is over, any part of a bet that nobody called is returned. If everyone but one player has folded, that player wins the pot straight away. Otherwise the next street's community cards are dealt, and once the river's betting is over the pots are paid out at showdown. If nobody can bet on the new street, because everyone is all in, this keeps dealing until showdown.
     ///
-    /// This function does not return any value.
-    fn ask_player(&mut self, prev_contributions: &mut HashMap<PlayerId, i32>) {
-        Self::print_turn_state(&self);
-        let community_vec = self.format_community_cards();
-
-        let player_id = self.turn_queue.pop_front().expect(PLAYER_NOT_FOUND_ERROR);
-        let (curr_money, curr_contribution) = {
-            // Limiting the scope of the mutable borrow of self here
-            let player = self.player_id_to_player.get(&player_id).expect(PLAYER_NOT_FOUND_ERROR);
-            let curr_money = player.get_money();
-            let curr_contribution = prev_contributions.get(&player.get_player_id()).cloned().unwrap_or(0);
-
-            println!();
-            Self::print_cards(community_vec, player);
-            (curr_money, curr_contribution)
-        };
-
-        self.print_pot_state(player_id, curr_money, curr_contribution);
+    /// If the board can be run more than once, the game waits before an all in runout until every player still in the hand has chosen how many times to run it.
+    fn advance_hand(&mut self) {
+        loop {
+            let hand_state = self.hand_state.as_ref().expect("Expected a hand to be in progress");
 
-        if curr_money == 0 {
-            println!("Skipping your turn because you went all in.");
-            self.turn_queue.push_back(player_id);
-            return;
-        }
+            if hand_state.is_complete() || hand_state.next_to_act().is_some() {
+                return;
+            }
 
-        let player = self.player_id_to_player.get(&player_id).expect(PLAYER_NOT_FOUND_ERROR);
-        let prev_contribution = prev_contributions.get(&player_id).cloned().unwrap_or(0);
-
-        // prompt the player
-        match self.curr_bet.checked_sub(prev_contribution).expect("Something went wrong here") {
-            0 => print!("{}, Would you like to fold, raise, go all in, or check? ", player.get_name()),
-            _ => print!("{}, Would you like to fold, raise, go all in, or call? ", player.get_name())
-        }
-        io::stdout().flush().unwrap();
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
-        let input = input.trim();
-
-
-        match input {
-            "fold" => {
-                // match self.bets.get(&player_id) {
-                //     Some(prev_bet) => {
-                //         self.pots
-                //             .iter_mut()
-                //             .for_each( |(bet, mut player_ids)|
-                //                 if *bet <= *prev_bet {
-                //                     // self.pots.get_mut(bet).unwrap().remove(&player_id);
-                //                     player_ids.remove(&player_id);
-                //                 }
-                //             )
-                //     }
-                //     None => {}
-                // }
-                self.players_in_round.remove(&player_id);
-            },
-            "raise" => {
-                print!("Raise by how much? ");
-                io::stdout().flush().unwrap();
+            self.return_uncalled_bet();
+            let hand_state = self.hand_state.as_ref().expect("Expected a hand to be in progress");
 
-                let mut input = String::new();
-                io::stdin().read_line(&mut input).unwrap();
-                let input = input.trim().parse::<i32>().unwrap();
+            if hand_state.get_players_in_hand().len() == 1 {
+                self.get_hand_state_mut().finish();
+                self.finish_hand(vec![self.community_cards.clone()]);
+                return;
+            }
 
-                if input < self.curr_bet {
-                    panic!("Raise must be at least {}", self.curr_bet);
+            if self.max_runs > 1 && hand_state.is_runout() {
+                match self.get_run_count() {
+                    None => return,
+                    Some(runs) if runs > 1 => {
+                        self.run_board(runs);
+                        return;
+                    },
+                    Some(_) => {}
                 }
+            }
 
-                let this_bet = input - prev_contribution;
+            let hand_state = self.hand_state.as_ref().expect("Expected a hand to be in progress");
+            let street = hand_state.get_street().next();
+            let min_bet = self.betting_structure.get_min_bet(street, self.blind_structure.get_big_blind());
+            self.get_hand_state_mut().next_street(min_bet);
 
-                self.make_player_bet(player_id, input, prev_contribution);
+            if street == Street::Showdown {
+                self.finish_hand(vec![self.community_cards.clone()]);
+                return;
+            }
 
-                prev_contributions.insert(player_id, prev_contribution + this_bet);
-                self.bet_this_round.insert(player_id);
-                self.turn_queue.push_back(player_id);
+            let mut cards = self.deal_cards(street.get_num_cards_dealt());
+            self.community_cards.append(&mut cards);
+        }
+    }
 
-                self.has_raised = true;
-            },
-            "all in" => {
-                self.handle_all_in(&player_id, prev_contribution);
-                // prev_contributions.insert(player_id, *self.bets.get(&player_id).expect(PLAYER_NOT_FOUND_ERROR));
-                // self.prev_contributions.
-                // self.bet_this_round.insert(player_id);
-                // self.turn_queue.push_back(player_id);
-            },
-            _ => {
-                match self.curr_bet {
-                    0 => println!("{} has checked", player.get_name()),
-                    _ => println!("{} has called", player.get_name())
-                }
-                // let prev_contribution = prev_contributions.get(&player_id).cloned().unwrap_or(0);
-                let this_bet = self.curr_bet - prev_contribution;
 
-                self.make_player_bet(player_id, self.curr_bet, prev_contribution);
+    /// Returns how many times to run the board, or `None` if a player still in the hand has not chosen yet. It is the
+    /// fewest times anyone chose, and no more than the deck has cards for.
+    fn get_run_count(&self) -> Option<usize> {
+        let hand_state = self.hand_state.as_ref().expect("Expected a hand to be in progress");
+        let runs = hand_state.get_players_in_hand().iter()
+            .map(|player_id| self.run_choices.get(player_id).cloned())
+            .collect::<Option<Vec<u32>>>()?
+            .into_iter()
+            .min()
+            .unwrap_or(1) as usize;
 
-                prev_contributions.insert(player_id, prev_contribution + this_bet);
-                self.bet_this_round.insert(player_id);
-                self.turn_queue.push_back(player_id);
+        let num_cards = 5 - self.community_cards.len();
+        Some(runs.min(self.deck.len() / num_cards).max(1))
+    }
+
+
+    /// Deals the rest of the board `runs` times from the same deck and pays out each pot split between the runs.
+    fn run_board(&mut self, runs: usize) {
+        let num_cards = 5 - self.community_cards.len();
+        let mut boards = Vec::<Vec<Card>>::new();
+        for _ in 0..runs {
+            let mut board = self.community_cards.clone();
+            board.append(&mut self.deal_cards(num_cards));
+            boards.push(board);
+        }
+
+        self.community_cards = boards[0].clone();
+        self.get_hand_state_mut().finish();
+        self.finish_hand(boards);
+    }
+
+
+    /// Gives the part of the biggest bet on the street that nobody called back to the player who made it, so that it is
+    /// not put in a pot. This happens when everyone else folds to a bet, or when nobody can match an all in.
+    fn return_uncalled_bet(&mut self) {
+        let uncalled_bet = self.hand_state.as_ref().expect("Expected a hand to be in progress").get_uncalled_bet();
+        if let Some((player_id, amount)) = uncalled_bet {
+            self.get_hand_state_mut().return_uncalled_bet(player_id, amount);
+            self.pot_manager.return_uncalled_bet(player_id, amount);
+            self.player_id_to_player.get_mut(&player_id).expect(PLAYER_NOT_FOUND_ERROR).increment_money(amount);
+            self.record_action(player_id, ActionKind::UncalledBetReturned, amount, false);
+        }
+    }
+
+
+    /// Pays out the pots, sends the `HandHistory` to the sinks, and removes the players who busted.
+    ///
+    /// # Parameters
+    ///
+    /// * `boards`: The board of each run. There is only one unless the board was run more than once.
+    fn finish_hand(&mut self, boards: Vec<Vec<Card>>) {
+        let pots = self.pot_manager.get_pots();
+
+        let run_payouts = self.determine_winner(&boards);
+        let mut pot_payouts = vec![HashMap::<PlayerId, i32>::new(); pots.len()];
+        for (pot, payouts) in run_payouts.iter().flat_map(|payouts| payouts.iter().enumerate()) {
+            for (player_id, amount) in payouts {
+                *pot_payouts[pot].entry(*player_id).or_insert(0) += amount;
             }
-        };
+        }
+        let showdown = self.get_showdown(&pots, &pot_payouts);
+
+        if let Some(hand_history) = self.hand_history.as_mut() {
+            let pot_records = pots.into_iter()
+                .zip(pot_payouts)
+                .map(|(pot, payouts)| PotRecord::new(pot.get_amount(), pot.get_eligible_players().clone(), payouts.into_iter().collect()))
+                .collect();
+            let run_records = if boards.len() > 1 {
+                boards.into_iter()
+                    .zip(run_payouts)
+                    .map(|(board, payouts)| {
+                        // Each hand is read on its own run's board.
+                        let scores = showdown.as_ref().map(|showdown| showdown.score_on(&board)).unwrap_or_default();
+                        RunRecord::new(board, payouts.into_iter().map(|payouts| payouts.into_iter().collect()).collect(), scores)
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            hand_history.finish(self.community_cards.clone(), pot_records, run_records, showdown);
+            for history_sink in self.history_sinks.iter_mut() {
+                if let Err(error) = history_sink.record(hand_history) {
+                    self.history_errors.push(HistorySinkError::new(*hand_history.hand_number(), error));
+                }
+            }
+        }
+
+        self.remove_busted_players();
+    }
+
+
+    /// Decides who shows and who mucks at the end of the hand.
+    ///
+    /// # Parameters
+    ///
+    /// * `pots`: The main pot and side pots.
+    /// * `pot_payouts`: The chips each player won from each pot.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<ShowdownResult>` - Who showed and who mucked, or `None` if everyone else folded.
+    fn get_showdown(&self, pots: &[Pot], pot_payouts: &[HashMap<PlayerId, i32>]) -> Option<ShowdownResult> {
+        let hand_state = self.hand_state.as_ref().expect("Expected a hand to be in progress");
+        let mut players_in_hand = hand_state.get_players_in_hand();
+        if players_in_hand.len() < 2 {
+            return None;
+        }
+
+        // The last aggressor shows first, or the first player after the button if nobody bet on the last street.
+        if let Some(index) = hand_state.get_last_aggressor().and_then(|aggressor| players_in_hand.iter().position(|&p| p == aggressor)) {
+            players_in_hand.rotate_left(index);
+        }
+        let is_all_in = players_in_hand.iter().filter(|player_id| !hand_state.is_all_in(player_id)).count() <= 1;
+
+        let hands = players_in_hand.iter()
+            .map(|player_id| {
+                let hole_cards = self.player_id_to_player.get(player_id).expect(PLAYER_NOT_FOUND_ERROR).get_hole_cards();
+                let seven_cards = hole_cards.iter().chain(self.community_cards.iter()).cloned().collect();
+                let score = OnePlayerAllPossibleCards::new(seven_cards).get_highest_hand_score();
+                (*player_id, hole_cards, score)
+            })
+            .collect();
+        // Winning a pot nobody else was eligible for does not mean having to show.
+        let winners = pots.iter()
+            .zip(pot_payouts)
+            .filter(|(pot, _)| pot.get_eligible_players().len() > 1)
+            .flat_map(|(_, payouts)| payouts.iter())
+            .filter(|(_, amount)| **amount > 0)
+            .map(|(player_id, _)| *player_id)
+            .collect();
+
+        Some(ShowdownResult::new(hand_state.get_last_action_street(), is_all_in, hands, &winners,
+                                 |player_id| self.mucks_losing_hands(player_id)))
     }
 
-    fn handle_all_in(&mut self, player_id: &PlayerId, prev_contribution: i32) {
-        let player: &Player = self.player_id_to_player.get(player_id).expect(PLAYER_NOT_FOUND_ERROR);
-        let all_in_amount: i32 = player.get_money() + self.bets.get(player_id).unwrap_or(&0);
 
-        // self.add_to_all_bets_up_to_highest(player_id, all_in_amount);
-        self.make_player_bet(*player_id, all_in_amount, prev_contribution);
+    /// Pays out the pots to the players still in the round.
+    ///
+    /// # Parameters
+    ///
+    /// * `boards`: The board of each run. There is only one unless the board was run more than once.
+    ///
+    /// # Notes
+    ///
+    /// The `PotManager` splits the chips into a main pot and side pots. Each pot is split evenly between every player
+    /// tied for the best hand among those eligible for it, and any odd chips are handed out one at a time in the order
+    /// given by `odd_chip_rule`. If the board was run more than once, each pot is first split into one portion per run,
+    /// and each portion goes to the best hands on that run's board.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<HashMap<PlayerId, i32>>>` - For each run, the chips each player won from each pot, in the order of
+    ///   `PotManager::get_pots`.
+    fn determine_winner(&mut self, boards: &[Vec<Card>]) -> Vec<Vec<HashMap<PlayerId, i32>>> {
+        let players_in_hand = self.hand_state.as_ref().expect("Expected a hand to be in progress").get_players_in_hand();
+
+        if players_in_hand.len() == 1 {
+
+            let money_earned = self.pot_manager.get_total();
+            let player_id = players_in_hand[0];
+            let player: &mut Player = self.player_id_to_player.get_mut(&player_id).expect(PLAYER_NOT_FOUND_ERROR);
 
-        let higher_bets: Vec<i32> = self.pots.keys().filter(|&&pot| pot > all_in_amount).cloned().collect();
+            player.increment_money(money_earned);
+            return vec![self.pot_manager.get_pots().iter().map(|pot| HashMap::from([(player_id, pot.get_amount())])).collect()];
+        }
 
-        let players_with_higher_bets: HashSet<PlayerId> = higher_bets
-            .iter()
-            .fold(HashSet::new(), |mut acc: HashSet<PlayerId>, bet| {
-                acc.extend(self.pots.get_mut(bet).expect("Expected to find bet in pot map").iter());
-                acc
-            });
+        let run_rankings: Vec<Vec<Vec<PlayerId>>> = boards.iter()
+            .map(|board| {
+                let player_to_seven_cards: HashMap<PlayerId, OnePlayerAllPossibleCards> = players_in_hand.iter()
+                    .fold(HashMap::<PlayerId, OnePlayerAllPossibleCards>::new(), |mut map, player_id| {
+                        let player: &Player = self.player_id_to_player.get(player_id).expect(PLAYER_NOT_FOUND_ERROR);
+
+                        let seven_cards_vec: Vec<Card> = player.get_hole_cards().iter()
+                            .chain(board.iter())
+                            .cloned()
+                            .collect::<Vec<_>>();
+                        let seven_cards: OnePlayerAllPossibleCards = OnePlayerAllPossibleCards::new(seven_cards_vec);
+
+                        map.insert(*player_id, seven_cards);
+                        map
+                    });
+
+                OnePlayerAllPossibleCards::rank_players(&player_to_seven_cards)
+            })
+            .collect();
+        let run_rankings: Vec<&[Vec<PlayerId>]> = run_rankings.iter().map(|rankings| rankings.as_slice()).collect();
+        let run_payouts = self.pot_manager.get_run_payouts(&run_rankings, &self.get_odd_chip_order());
+
+        for (player_id, money_earned) in run_payouts.iter().flatten().flatten() {
+            let player = self.player_id_to_player.get_mut(player_id).expect(PLAYER_NOT_FOUND_ERROR);
+            player.increment_money(*money_earned);
+        }
 
-        players_with_higher_bets
-            .iter()
-            .for_each(
-                |player: &PlayerId| {
-                    self.pots.get_mut(&all_in_amount).expect("Expected to find bet in pot map").insert(*player);
-            });
+        run_payouts
+    }
 
 
-        //
-        // self.
-        // self.bets.insert(*player_id, all_in_amount);
-        // self.curr_bet = max(self.curr_bet, all_in_amount);
+    /// Returns the order in which the winners of a split pot receive the odd chips, according to `odd_chip_rule`.
+    fn get_odd_chip_order(&self) -> Vec<PlayerId> {
+        match self.odd_chip_rule {
+            // The hand's seats start with the first seat left of the button.
+            OddChipRule::FirstLeftOfButton => self.hand_state.as_ref().map_or(Vec::new(), |hand_state| hand_state.get_seats().clone()),
+            OddChipRule::LowestPlayerId => {
+                let mut player_ids = self.table.get_players();
+                player_ids.sort();
+                player_ids
+            }
+        }
     }
 
 
-    fn print_cards(mut community_vec: VecDeque<String>, player: &Player) {
-        let mut player_vec = player.format_hole_cards();
-        player_vec.push_front(utils::get_dashes_for_longest_string(player_vec.clone()));
-        player_vec.push_front("Your Cards".to_string());
+    /// Asks a player's agent to make a decision for their turn.
+    ///
+    /// # Parameters
+    ///
+    /// * `agents`: A mutable reference to the `PlayerAgent`s that make each player's decisions.
+    /// * `player_id`: The player whose turn it is.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the player has no agent.
+    ///
+    /// # Notes
+    ///
+    /// This function shows the player their `PlayerView` and asks their agent for an `Action`, which is applied with `apply_action`. If the action is rejected, the agent is told why and asked again.
+    fn ask_player(&mut self, agents: &mut HashMap<PlayerId, Box<dyn PlayerAgent>>, player_id: PlayerId) {
+        let agent = agents.get_mut(&player_id)
+            .unwrap_or_else(|| panic!("Player {} has no agent", player_id));
 
-        community_vec.push_front("-".repeat(community_vec.iter().max_by_key(|x| { x.len() }).expect("Error here").len()));
-        community_vec.push_front("Community Cards:".to_string());
-        let cards_display_str = utils::format_next_to_each_other(vec!(player_vec, community_vec));
-        println!("{}", cards_display_str);
-        println!();
+        loop {
+            let view = self.view_for(player_id);
+            let action = agent.decide(&view);
+
+            match self.apply_action(player_id, action) {
+                Ok(_) => break,
+                Err(error) => agent.on_invalid_action(action, &error)
+            }
+        }
     }
 
 
-    fn print_pot_state(&mut self, player_id: PlayerId, curr_money: i32, curr_contribution: i32) {
-        // let pot_str = vec!["Pot".to_string(), utils::dashes(6), self.pot.to_string()];
-        dbg!(&self.pots);
-        // let pot_str = vec!["Pot".to_string(), utils::dashes(6), self.get_total_pot_size().to_string()];
-        let bet_str = vec!["Table's Current Bet".to_string(), utils::dashes(18), self.curr_bet.to_string()];
-        let money_in_pot_str = vec!["Your Contribution to the Pot".to_string(), utils::dashes(29), self.bets.get(&player_id).unwrap_or(&0).to_string()];
-        let curr_bet_contribution = vec!["Your Current Contribution to the Bet".to_string(), utils::dashes(37), curr_contribution.to_string()];
-        let money_str = vec!["Your Money".to_string(), utils::dashes(15), curr_money.to_string()];
+    /// Asks the agent of every player the game is waiting on how many times to run the board. A choice the game rejects
+    /// counts as running it once.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if a player has no agent, or if the game is not waiting on anyone.
+    fn ask_run_counts(&mut self, agents: &mut HashMap<PlayerId, Box<dyn PlayerAgent>>) {
+        let player_ids = self.get_players_to_choose_runs();
+        assert!(!player_ids.is_empty(), "Expected the hand to be waiting on a player");
 
-        let output = utils::format_next_to_each_other(vec![bet_str, money_in_pot_str, curr_bet_contribution, money_str]);
-        println!("{}", output);
-        println!();
+        for player_id in player_ids {
+            let agent = agents.get_mut(&player_id)
+                .unwrap_or_else(|| panic!("Player {} has no agent", player_id));
+            let runs = agent.choose_run_count(&self.view_for(player_id), self.max_runs);
+
+            if self.choose_run_count(player_id, runs).is_err() {
+                self.choose_run_count(player_id, 1).expect("Expected running the board once to be allowed");
+            }
+        }
     }
 
 
-    /// Rotates through the players in the current round, prompting each to make a decision.
+    /// Applies a player's action to the current hand.
     ///
-    /// # Arguments
+    /// # Parameters
     ///
-    /// * `prev_contributions_option` - A mutable reference to an `Option` that may contain a `HashMap` mapping `PlayerId`s to the amount they have contributed to the pot in the current round.
-    /// * `prev_player` - A mutable reference to an `Option` that may contain the `PlayerId` of the previous player.
-    /// * `is_dealing_hold_cards` - A `bool` indicating whether the function is being called while dealing hole cards.
+    /// * `player_id`: The player making the action.
+    /// * `action`: The `Action` they want to make. Bet and raise amounts are the total they will have bet on this street.
     ///
     /// # Returns
     ///
-    /// * `bool` - Returns `false` if there is only one player left in the round, otherwise returns `true`.
+    /// * `Ok(ActionOutcome)` - The action was made. The outcome says how many chips it put in.
+    /// * `Err(ActionError)` - The action is not allowed. The game state is unchanged.
     ///
-    /// # Panics
+    /// # Notes
     ///
-    /// This function will panic if there are no players in the current round, or if a player tries to raise by an amount less than the current bet.
+    /// Bet and raise amounts are "raise to" amounts: the total the player will have bet on this street, including what they had already bet. A raise must be at least the size of the last full bet or raise on this street, and never less than the big blind. A player may always bet, raise or call for less than the minimum if it puts them all in, but an all in that is smaller than a full raise does not let players who have already acted raise again.
     ///
-    /// # Behavior
+    /// Once the action closes a betting round, the next street is dealt. If it ends the hand, the pots are paid out.
+    pub fn apply_action(&mut self, player_id: PlayerId, action: Action) -> Result<ActionOutcome, ActionError> {
+        self.validate_action(player_id, action)?;
+
+        let contribution = self.get_street_contribution(&player_id);
+        let money = self.player_id_to_player.get(&player_id).expect(PLAYER_NOT_FOUND_ERROR).get_money();
+        let all_in_amount = contribution + money;
+        let current_bet = self.get_hand_state_mut().get_current_bet();
+
+        let new_contribution = match action {
+            Action::Fold => {
+                self.record_action(player_id, ActionKind::Fold, 0, false);
+                self.get_hand_state_mut().record_fold(player_id);
+                self.pot_manager.fold(player_id);
+                self.advance_hand();
+                return Ok(ActionOutcome::new(player_id, action, 0, false));
+            },
+            Action::Check => contribution,
+            Action::Call => current_bet.min(all_in_amount),
+            Action::Bet(amount) | Action::RaiseTo(amount) => amount,
+            Action::AllIn => all_in_amount
+        };
+
+        let is_all_in = new_contribution == all_in_amount;
+        let kind = if new_contribution == contribution {
+            ActionKind::Check
+        } else if new_contribution <= current_bet {
+            ActionKind::Call
+        } else if current_bet == 0 {
+            ActionKind::Bet(new_contribution)
+        } else {
+            ActionKind::RaiseTo(new_contribution)
+        };
+        self.record_action(player_id, kind, new_contribution - contribution, is_all_in);
+
+        self.make_player_bet(player_id, new_contribution);
+        self.get_hand_state_mut().record_bet(player_id, new_contribution, is_all_in);
+        self.advance_hand();
+
+        Ok(ActionOutcome::new(player_id, action, new_contribution - contribution, is_all_in))
+    }
+
+
+    /// Checks whether `player_id` is allowed to make `action` right now.
     ///
-    /// This function rotates through the players in the current round, prompting each to make a decision until a condition to break the loop is met. The conditions to break the loop are:
-    /// 1. There is only one player left in the round.
-    /// 2. The last player to raise has been asked and all players have been asked at least once since the last raise.
-    /// 3. All players have been asked at least once and the last player to be asked has matched the current bet.
+    /// # Returns
     ///
-    fn circle_players(&mut self, prev_contributions_option: &mut Option<HashMap<PlayerId, i32>>,  prev_player: &mut Option<PlayerId>, is_dealing_hold_cards: bool) -> bool{
+    /// * `Ok(())` - The action is allowed.
+    /// * `Err(ActionError)` - The reason the action is not allowed.
+    fn validate_action(&self, player_id: PlayerId, action: Action) -> Result<(), ActionError> {
+        let hand_state = match &self.hand_state {
+            Some(hand_state) if !hand_state.is_complete() => hand_state,
+            _ => return Err(ActionError::NoHandInProgress)
+        };
+
+        let player = self.player_id_to_player.get(&player_id).ok_or(ActionError::UnknownPlayer { player_id })?;
 
-        if self.turn_queue.len() == 0 {
-            return true;
+        if !hand_state.is_in_hand(&player_id) {
+            return Err(ActionError::PlayerFolded);
         }
 
-        self.bet_this_round.clear();
+        if hand_state.is_all_in(&player_id) {
+            return Err(ActionError::PlayerAllIn);
+        }
+
+        let player_to_act = hand_state.next_to_act();
+        if player_to_act != Some(player_id) {
+            return Err(ActionError::NotYourTurn { player_to_act });
+        }
 
-        let mut prev_contributions: HashMap<PlayerId, i32>;
+        let current_bet = hand_state.get_current_bet();
+        let contribution = hand_state.get_street_contribution(&player_id);
+        let all_in_amount = contribution + player.get_money();
+        let legal_actions = self.get_legal_actions(hand_state, player_id);
+
+        // Going all in is the same as calling, betting or raising with every chip the player has.
+        let action = match action {
+            Action::AllIn if all_in_amount <= current_bet => Action::Call,
+            Action::AllIn if current_bet == 0 => Action::Bet(all_in_amount),
+            Action::AllIn => Action::RaiseTo(all_in_amount),
+            action => action
+        };
 
-        match prev_contributions_option {
-            Some(prev_contributions_option) => {
-                prev_contributions = prev_contributions_option.clone();
+        match action {
+            Action::Fold => Ok(()),
+            Action::Check if !*legal_actions.can_check() => Err(ActionError::CannotCheckFacingBet { amount_to_call: *legal_actions.amount_to_call() }),
+            Action::Check => Ok(()),
+            Action::Call if *legal_actions.can_check() => Err(ActionError::NothingToCall),
+            Action::Call => Ok(()),
+            Action::Bet(_) if current_bet > 0 => Err(ActionError::CannotBetFacingBet { current_bet }),
+            Action::RaiseTo(_) if current_bet == 0 => Err(ActionError::NothingToRaise),
+            Action::Bet(amount) | Action::RaiseTo(amount) if amount > all_in_amount => Err(ActionError::NotEnoughChips { maximum: all_in_amount }),
+            Action::RaiseTo(_) if !hand_state.can_raise(&player_id) => Err(ActionError::BettingNotReopened),
+            Action::RaiseTo(_) if self.betting_structure.is_raise_capped(hand_state) => Err(ActionError::RaiseCapReached),
+            Action::Bet(amount) => match legal_actions.bet_range() {
+                Some(range) if amount < *range.min() => Err(ActionError::BetTooSmall { minimum: *range.min() }),
+                Some(range) if amount > *range.max() => Err(ActionError::BetTooLarge { maximum: *range.max() }),
+                _ => Ok(())
             },
-            None => {
-                prev_contributions = HashMap::<PlayerId, i32>::new();
-            }
+            Action::RaiseTo(amount) => match legal_actions.raise_range() {
+                Some(range) if amount < *range.min() => Err(ActionError::RaiseTooSmall { minimum: *range.min() }),
+                Some(range) if amount > *range.max() => Err(ActionError::RaiseTooLarge { maximum: *range.max() }),
+                Some(_) => Ok(()),
+                // The player cannot even cover the current bet, so any raise is too small.
+                None => Err(ActionError::RaiseTooSmall { minimum: hand_state.get_min_raise_to() })
+            },
+            Action::AllIn => unreachable!("All in was turned into a call, bet or raise above")
         }
+    }
 
 
-        let mut count = 0;
+    /// Works out what `player_id` is allowed to do under the game's `BettingStructure`.
+    fn get_legal_actions(&self, hand_state: &HandState, player_id: PlayerId) -> LegalActions {
+        let money = self.player_id_to_player.get(&player_id).expect(PLAYER_NOT_FOUND_ERROR).get_money();
+        self.betting_structure.get_legal_actions(hand_state, &player_id, money, self.pot_manager.get_total())
+    }
 
-        loop {
-            if count == 20 {break}
 
-            if self.players_in_round.len() == 1 {
-                return false;
-            }
+    fn get_street_contribution(&self, player_id: &PlayerId) -> i32 {
+        self.hand_state.as_ref().map_or(0, |hand_state| hand_state.get_street_contribution(player_id))
+    }
 
 
+    /// Builds what `player_id` is allowed to see of the table.
+    ///
+    /// # Parameters
+    ///
+    /// * `player_id`: The player the view is for.
+    ///
+    /// # Returns
+    ///
+    /// * `PlayerView` - The public state of the current or last hand plus the player's own hole cards. The seats start
+    ///   with the player and then follow the order of play.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `player_id` is not in the game.
+    ///
+    /// # Notes
+    ///
+    /// Other players' hole cards are only included once the hand is over, and only for the hands in the `HandHistory`'s
+    /// showdown. A player who was not dealt into the hand gets no hole cards, even if they still hold cards from an
+    /// earlier hand.
+    pub fn view_for(&self, player_id: PlayerId) -> PlayerView {
+        let player = self.player_id_to_player.get(&player_id).expect(PLAYER_NOT_FOUND_ERROR);
+        let hand_state = self.hand_state.as_ref();
 
-            self.ask_player(&mut prev_contributions);
+        let mut seats = self.seat_views();
+        if let Some(index) = seats.iter().position(|seat| *seat.player_id() == player_id) {
+            seats.rotate_left(index);
+        }
 
-            if self.turn_queue.len() == 0 {
-                return true;
-            }
+        let is_dealt_in = hand_state.is_some_and(|hand_state| hand_state.get_seats().contains(&player_id));
+        let legal_actions = match hand_state {
+            Some(hand_state) if self.next_to_act() == Some(player_id) => self.get_legal_actions(hand_state, player_id),
+            _ => LegalActions::none()
+        };
 
-            let player_id = self.turn_queue.front().expect(PLAYER_NOT_FOUND_ERROR).clone();
-            let player = self.player_id_to_player.get(&player_id).expect(PLAYER_NOT_FOUND_ERROR).clone();
+        PlayerView::new(
+            player_id,
+            player.get_name(),
+            player.get_money(),
+            if is_dealt_in { player.get_hole_cards() } else { Vec::new() },
+            self.get_street(),
+            self.table.get_button(),
+            self.next_to_act(),
+            self.community_cards.clone(),
+            hand_state.map_or(0, |hand_state| hand_state.get_current_bet()),
+            self.get_street_contribution(&player_id),
+            self.pot_manager.get_contribution(&player_id),
+            self.pot_manager.get_total(),
+            legal_actions,
+            seats
+        )
+    }
 
 
-            match prev_player {
-                Some(prev_player) => {
-                    if self.has_raised && player.get_player_id() == self.last_player_to_raise && self.bet_this_round.len() >= self.turn_queue.len() {
-                        break;
-                    }
+    /// Builds what someone watching the table is allowed to see. Nobody's hole cards are included until they are shown
+    /// at showdown. Use a `SpectatorDelay` to show it to spectators after a delay.
+    pub fn spectator_view(&self) -> SpectatorView {
+        SpectatorView::new(
+            self.game_id,
+            self.hand_number,
+            self.get_street(),
+            self.table.get_button(),
+            self.next_to_act(),
+            self.community_cards.clone(),
+            self.hand_state.as_ref().map_or(0, |hand_state| hand_state.get_current_bet()),
+            self.pot_manager.get_total(),
+            self.seat_views()
+        )
+    }
 
-                    if *prev_player == self.last_player_to_raise && prev_contributions.get(&player.get_player_id()).cloned().unwrap_or(0) == self.curr_bet && self.bet_this_round.len() >= self.turn_queue.len() {
-                        break;
-                    }
-                },
-                None => {
-                }
-            }
 
-            *prev_player = Some(player.get_player_id());
-            count += 1;
-        }
+    /// Returns the public information about the players dealt into the current or last hand, in the order they act
+    /// after the flop, or about every seated player if no hand has been dealt. Players who have left are skipped.
+    fn seat_views(&self) -> Vec<SeatView> {
+        let player_ids = match self.hand_state.as_ref() {
+            Some(hand_state) => hand_state.get_seats().clone(),
+            None => self.table.get_players()
+        };
+        // Hole cards are only shown once the hand is over.
+        let showdown = match self.hand_history.as_ref() {
+            Some(hand_history) if !self.is_hand_in_progress() => hand_history.showdown().as_ref(),
+            _ => None
+        };
 
-        true
+        player_ids.iter()
+            .filter_map(|player_id| {
+                let player = self.player_id_to_player.get(player_id)?;
+                // Players who busted this hand have already left the table, but their seat is in the history.
+                let seat = self.hand_history.as_ref()
+                    .and_then(|hand_history| hand_history.seats().iter().find(|seat| seat.player_id() == player_id))
+                    .map(|seat| *seat.seat())
+                    .or_else(|| self.table.get_seat(player_id))?;
+                let shown_cards = showdown
+                    .and_then(|showdown| showdown.get_shown_cards(player_id))
+                    .cloned();
+                let (has_folded, is_all_in) = self.hand_state.as_ref()
+                    .map_or((false, false), |hand_state| (hand_state.has_folded(player_id), hand_state.is_all_in(player_id)));
+
+                Some(SeatView::new(seat, *player_id, player.get_name(), player.get_money(), self.pot_manager.get_contribution(player_id),
+                                   has_folded, is_all_in, shown_cards))
+            })
+            .collect()
     }
 
 
@@ -818,7 +1355,7 @@ impl Game {
     /// # Parameters
     ///
     /// * `player_id`: The ID of the player who is placing the bet.
-    /// * `bet`: The amount of money the player is betting.
+    /// * `bet`: The total amount the player will have bet on this street.
     ///
     /// # Panics
     ///
@@ -826,86 +1363,824 @@ impl Game {
     ///
     /// # Notes
     ///
-    /// This function updates the player's money, the current bet, the last player to raise, the player's total bet, and the pot.
-    /// If the bet is greater than the current bet, the player becomes the last player to raise and the current bet is updated.
-    fn make_player_bet(&mut self, player_id: PlayerId, bet: i32, prev_contribution: i32) {
-        let difference = bet - prev_contribution;
-        let mut player = self.player_id_to_player.get_mut(&player_id).expect(PLAYER_NOT_FOUND_ERROR);
+    /// This function moves the difference between `bet` and what the player has already bet on this street from the player's money into the pot. The `HandState` is updated separately by the caller.
+    fn make_player_bet(&mut self, player_id: PlayerId, bet: i32) {
+        let difference = bet - self.get_street_contribution(&player_id);
+        let player = self.player_id_to_player.get_mut(&player_id).expect(PLAYER_NOT_FOUND_ERROR);
         player.set_money(player.get_money() - difference);
-        if bet > self.curr_bet {
-            self.curr_bet = bet;
-            self.last_player_to_raise = player.get_player_id();
+        self.pot_manager.add_contribution(player_id, difference);
+    }
+
+
+    fn get_total_player_money(&self) -> i32 {
+        self.player_id_to_player.values().map(|player| player.get_money()).sum()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::game::agent::ScriptedAgent;
+    use crate::game::deck::StackedDecks;
+    use crate::game::hand::Suit;
+    use crate::game::hand_history::MemorySink;
+    use crate::game::replay::Replayer;
+
+    use super::*;
+
+    /// Starts a hand between three players with 100 chips each and a big blind of 10. Player 1 posts the small blind,
+    /// player 2 posts the big blind, and player 3 is first to act.
+    fn game_in_progress() -> Game {
+        let mut game = Game::new(0, 10, 100);
+        for player_id in 1..=3 {
+            game.add_player(Player::new(player_id, format!("Player {}", player_id), 100));
         }
-        let player_id = player.get_player_id();
-        self.bets.insert(player_id, *self.bets.get(&player_id).unwrap_or(&0) + bet);
+        game.start_hand(false);
+        game
+    }
+
+    fn get_money(game: &Game, player_id: PlayerId) -> i32 {
+        game.player_id_to_player.get(&player_id).unwrap().get_money()
+    }
+
+    #[test]
+    fn test_apply_action_rejects_out_of_turn() {
+        let mut game = game_in_progress();
+
+        assert_eq!(game.apply_action(1, Action::Call), Err(ActionError::NotYourTurn { player_to_act: Some(3) }));
+        assert_eq!(game.apply_action(7, Action::Call), Err(ActionError::UnknownPlayer { player_id: 7 }));
+    }
+
+    #[test]
+    fn test_apply_action_rejects_illegal_bets() {
+        let mut game = game_in_progress();
+
+        assert_eq!(game.apply_action(3, Action::Check), Err(ActionError::CannotCheckFacingBet { amount_to_call: 10 }));
+        assert_eq!(game.apply_action(3, Action::Bet(20)), Err(ActionError::CannotBetFacingBet { current_bet: 10 }));
+        assert_eq!(game.apply_action(3, Action::RaiseTo(15)), Err(ActionError::RaiseTooSmall { minimum: 20 }));
+        assert_eq!(game.apply_action(3, Action::RaiseTo(101)), Err(ActionError::NotEnoughChips { maximum: 100 }));
+
+        // The rejected actions should not have changed whose turn it is.
+        let outcome = game.apply_action(3, Action::RaiseTo(30)).unwrap();
+        assert_eq!(*outcome.amount(), 30);
+        assert!(!*outcome.is_all_in());
+        assert_eq!(game.next_to_act(), Some(1));
+    }
+
+    #[test]
+    fn test_apply_action_rejects_folded_and_all_in_players() {
+        let mut game = game_in_progress();
+
+        assert!(*game.apply_action(3, Action::AllIn).unwrap().is_all_in());
+        game.apply_action(1, Action::Fold).unwrap();
+
+        assert_eq!(game.apply_action(1, Action::Call), Err(ActionError::PlayerFolded));
+        assert_eq!(game.apply_action(3, Action::Call), Err(ActionError::PlayerAllIn));
 
-        if bet != 0 {
-            self.add_to_all_bets_up_to_highest(&player_id, bet);
+        let outcome = game.apply_action(2, Action::Call).unwrap();
+        assert_eq!(*outcome.amount(), 90);
+        assert_eq!(game.pot_manager.get_total(), 205);
+    }
+
+    #[test]
+    fn test_short_call_puts_player_all_in() {
+        let mut game = game_in_progress();
+        game.player_id_to_player.get_mut(&1).unwrap().set_money(15);
+
+        game.apply_action(3, Action::RaiseTo(50)).unwrap();
+        let outcome = game.apply_action(1, Action::Call).unwrap();
+
+        assert_eq!(*outcome.amount(), 15);
+        assert!(*outcome.is_all_in());
+    }
+
+    #[test]
+    fn test_raise_must_be_at_least_last_raise() {
+        let mut game = game_in_progress();
+
+        game.apply_action(3, Action::RaiseTo(40)).unwrap();
+        assert_eq!(game.apply_action(1, Action::RaiseTo(60)), Err(ActionError::RaiseTooSmall { minimum: 70 }));
+        game.apply_action(1, Action::RaiseTo(70)).unwrap();
+    }
+
+    #[test]
+    fn test_calling_a_reraise_only_adds_the_difference() {
+        let mut game = game_in_progress();
+
+        game.apply_action(3, Action::RaiseTo(30)).unwrap();
+        game.apply_action(1, Action::RaiseTo(70)).unwrap();
+        game.apply_action(2, Action::Fold).unwrap();
+        let outcome = game.apply_action(3, Action::Call).unwrap();
+
+        // Player 3 already had 30 in, so calling 70 only costs them 40 more, not another 70.
+        assert_eq!(*outcome.amount(), 40);
+        assert_eq!(get_money(&game, 3), 30);
+        assert_eq!(game.pot_manager.get_contribution(&3), 70);
+        assert_eq!(game.pot_manager.get_total(), 150);
+    }
+
+    #[test]
+    fn test_short_all_in_only_lets_players_who_acted_call() {
+        let mut game = game_in_progress();
+        // Player 1 has 35 left behind their small blind of 5.
+        game.player_id_to_player.get_mut(&1).unwrap().set_money(35);
+
+        game.apply_action(3, Action::RaiseTo(30)).unwrap();
+        // Player 1 goes all in for 40, which is 10 more than the bet of 30 but short of a full raise to 50.
+        game.apply_action(1, Action::AllIn).unwrap();
+
+        // Player 2 has not acted yet, so they can still raise, but only by a full raise of 20 on top of the 40.
+        assert_eq!(game.apply_action(2, Action::RaiseTo(50)), Err(ActionError::RaiseTooSmall { minimum: 60 }));
+        game.apply_action(2, Action::Call).unwrap();
+
+        assert_eq!(game.next_to_act(), Some(3));
+        assert_eq!(game.apply_action(3, Action::RaiseTo(100)), Err(ActionError::BettingNotReopened));
+        assert_eq!(game.apply_action(3, Action::AllIn), Err(ActionError::BettingNotReopened));
+        game.apply_action(3, Action::Call).unwrap();
+
+        assert_eq!(game.get_street(), Some(Street::Flop));
+    }
+
+    #[test]
+    fn test_pot_limit_caps_raises_at_the_pot() {
+        let mut game = Game::new(0, 10, 200);
+        game.set_betting_structure(BettingStructure::PotLimit);
+        for player_id in 1..=3 {
+            game.add_player(Player::new(player_id, format!("Player {}", player_id), 200));
         }
+        game.start_hand(false);
 
+        // The pot is 15. Calling the 10 makes it 25, so player 3 can raise to at most 10 + 25.
+        assert_eq!(game.apply_action(3, Action::RaiseTo(36)), Err(ActionError::RaiseTooLarge { maximum: 35 }));
+        assert_eq!(game.apply_action(3, Action::AllIn), Err(ActionError::RaiseTooLarge { maximum: 35 }));
+        game.apply_action(3, Action::RaiseTo(35)).unwrap();
 
+        // Player 1 has 5 in and 30 to call, which makes the pot 80.
+        let view = game.view_for(1);
+        assert_eq!(*view.pot(), 50);
+        assert_eq!(view.legal_actions().raise_range().map(|range| *range.max()), Some(35 + 50 + 30));
     }
 
+    #[test]
+    fn test_fixed_limit_bet_sizes_and_cap() {
+        let mut game = Game::new(0, 10, 100);
+        game.set_betting_structure(BettingStructure::FixedLimit { small_bet: 10, big_bet: 20, raise_cap: 1 });
+        for player_id in 1..=3 {
+            game.add_player(Player::new(player_id, format!("Player {}", player_id), 100));
+        }
+        game.start_hand(false);
+
+        assert_eq!(game.apply_action(3, Action::RaiseTo(30)), Err(ActionError::RaiseTooLarge { maximum: 20 }));
+        game.apply_action(3, Action::RaiseTo(20)).unwrap();
+        assert_eq!(game.apply_action(1, Action::RaiseTo(30)), Err(ActionError::RaiseCapReached));
+        game.apply_action(1, Action::Call).unwrap();
+        game.apply_action(2, Action::Call).unwrap();
+
+        // The flop is still played with the small bet.
+        assert_eq!(game.apply_action(1, Action::Bet(20)), Err(ActionError::BetTooLarge { maximum: 10 }));
+        for player_id in 1..=3 {
+            game.apply_action(player_id, Action::Check).unwrap();
+        }
 
-    /// Adds the player's ID to all pots that are smaller or equal to the highest bet.
-    ///
-    /// # Arguments
-    ///
-    /// * `player_id` - The ID of the player who is placing the bet.
-    /// * `highest_bet` - The highest bet that the player has made.
-    ///
-    /// # Behavior
-    ///
-    /// This function iterates over the keys of the `pots` HashMap, which represent different pot sizes. For each pot size that is smaller or equal to the `highest_bet`, it adds the `player_id` to the set of players associated with that pot size.
-    ///
-    /// This function does not return any value.
-    fn add_to_all_bets_up_to_highest(&mut self, player_id: &PlayerId, highest_bet: i32) {
-        if !self.pots.contains_key(&highest_bet) {
-            self.pots.insert(highest_bet, HashSet::new());
-        }
-
-        // let mut remaining = highest_bet;
-        // for (bet, mut hash_set) in self.pots.iter_mut() {
-        //     if remaining == 0 { break }
-        //
-        //     hash_set.insert(*player_id);
-        //     remaining = remaining - bet;
-        // }
-        //
-        // if remaining != 0 {
-        //     self.pots.insert()
-        // }
-
-            // .fold(highest_bet, |remaining, (bet, mut hash_set): (_, &mut HashSet<PlayerId>) | {
-            //     if remaining > 0 {
-            //         hash_set.insert(*player_id);
-            //         remaining - bet
-            //     }
-            //     0
-            // });
-
-
-
-        // let bet = player_id, ())
-        self.pots.iter_mut()
-            .filter(|&(&key, _): &(&i32, _)| key <= highest_bet)
-            .for_each(|(_, mut hash_set): (_, &mut HashSet<PlayerId>) | {
-                hash_set.insert(*player_id);
-            });
-    }
-
-    // fn get_total_pot_size(&self) -> i32 {
-    //     let mut pot = 0;
-    //     for (value, subpot) in self.pots.iter() {
-    //         pot += *value * (subpot.len() as i32);
-    //     }
-    //     pot
-    // }
+        assert_eq!(game.get_street(), Some(Street::Turn));
+        assert_eq!(game.apply_action(1, Action::Bet(10)), Err(ActionError::BetTooSmall { minimum: 20 }));
+        game.apply_action(1, Action::Bet(20)).unwrap();
+    }
 
-    fn get_total_player_money(&self) -> i32 {
-        self.players.iter().map(|p| {
-            let player = self.player_id_to_player.get(p).expect(PLAYER_NOT_FOUND_ERROR);
-            player.get_money()
-        }).sum()
+    /// Creates a game with `num_players` players with 100 chips each and blinds of 5 and 10.
+    fn game_with_players(num_players: i32) -> Game {
+        let mut game = Game::new(0, 10, 100);
+        for player_id in 1..=num_players {
+            game.add_player(Player::new(player_id, format!("Player {}", player_id), 100));
+        }
+        game
+    }
+
+    #[test]
+    fn test_antes_do_not_count_towards_the_bet() {
+        let mut game = game_with_players(3);
+        let mut blinds = BlindStructure::new(5, 10);
+        blinds.set_ante(2);
+        blinds.set_big_blind_ante(3);
+        game.set_blind_structure(blinds);
+        game.start_hand(false);
+
+        assert_eq!(game.pot_manager.get_total(), 5 + 10 + 3 * 2 + 3);
+        assert_eq!(get_money(&game, 2), 100 - 10 - 2 - 3);
+        assert_eq!(game.get_hand_state().unwrap().get_current_bet(), 10);
+
+        let outcome = game.apply_action(3, Action::Call).unwrap();
+        assert_eq!(*outcome.amount(), 10);
+    }
+
+    #[test]
+    fn test_straddle_acts_last_preflop() {
+        let mut game = game_with_players(4);
+        let mut blinds = BlindStructure::new(5, 10);
+        blinds.set_straddle(Some(20));
+        game.set_blind_structure(blinds);
+        game.start_hand(false);
+
+        assert_eq!(game.next_to_act(), Some(4));
+        assert_eq!(game.apply_action(4, Action::RaiseTo(30)), Err(ActionError::RaiseTooSmall { minimum: 40 }));
+        game.apply_action(4, Action::Call).unwrap();
+        game.apply_action(1, Action::Call).unwrap();
+        game.apply_action(2, Action::Call).unwrap();
+
+        assert_eq!(game.next_to_act(), Some(3));
+        game.apply_action(3, Action::Check).unwrap();
+        assert_eq!(game.get_street(), Some(Street::Flop));
+    }
+
+    #[test]
+    fn test_dead_blinds_after_sitting_out() {
+        let mut game = game_with_players(4);
+        game.sit_out(2);
+
+        // The big blind moves past player 2 on the first hand.
+        for (small_blind, big_blind) in [(1, 3), (3, 4)] {
+            game.start_hand(false);
+            let hand_state = game.get_hand_state().unwrap();
+            assert!(!hand_state.get_seats().contains(&2));
+            assert_eq!(hand_state.get_street_contribution(&small_blind), 5);
+            assert_eq!(hand_state.get_street_contribution(&big_blind), 10);
+
+            while game.is_hand_in_progress() {
+                game.apply_action(game.next_to_act().unwrap(), Action::Fold).unwrap();
+            }
+        }
+
+        let missed_blinds = game.get_missed_blinds(&2);
+        assert!(missed_blinds.has_missed_small_blind() && missed_blinds.has_missed_big_blind());
+
+        game.sit_in(2);
+        game.start_hand(false);
+
+        // Player 2 posts the big blind live and the small blind dead.
+        assert!(game.get_missed_blinds(&2).is_empty());
+        assert_eq!(get_money(&game, 2), 85);
+        assert_eq!(game.get_hand_state().unwrap().get_street_contribution(&2), 10);
+
+        assert_eq!(game.next_to_act(), Some(2));
+        game.apply_action(2, Action::Check).unwrap();
+    }
+
+    #[test]
+    fn test_heads_up_button_posts_small_blind_and_acts_first() {
+        let mut game = game_with_players(2);
+        game.start_hand(false);
+
+        let hand_state = game.get_hand_state().unwrap();
+        assert_eq!(hand_state.get_street_contribution(&2), 5);
+        assert_eq!(hand_state.get_street_contribution(&1), 10);
+
+        assert_eq!(game.next_to_act(), Some(2));
+        game.apply_action(2, Action::Call).unwrap();
+        game.apply_action(1, Action::Check).unwrap();
+
+        // The big blind acts first after the flop.
+        assert_eq!(game.get_street(), Some(Street::Flop));
+        assert_eq!(game.next_to_act(), Some(1));
+    }
+
+    #[test]
+    fn test_button_moves_each_hand() {
+        let mut game = game_with_players(3);
+
+        for button in [2, 0, 1, 2] {
+            game.start_hand(false);
+            assert_eq!(game.get_table().get_button(), Some(button));
+
+            while game.is_hand_in_progress() {
+                game.apply_action(game.next_to_act().unwrap(), Action::Fold).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_busted_players_leave_the_table() {
+        let mut game = game_with_players(3);
+        game.player_id_to_player.get_mut(&3).unwrap().set_money(0);
+        game.start_hand(false);
+
+        assert_eq!(game.get_hand_state().unwrap().get_seats(), &vec![1, 2]);
+        game.apply_action(2, Action::Fold).unwrap();
+
+        assert_eq!(game.get_table().get_players(), vec![1, 2]);
+        assert_eq!(game.get_num_players(), 2);
+    }
+
+    #[test]
+    fn test_remove_player_folds_their_hand() {
+        let mut game = game_in_progress();
+
+        let player = game.remove_player(2).unwrap();
+        assert_eq!(player.get_money(), 90);
+        assert_eq!(game.get_table().get_players(), vec![1, 3]);
+
+        // Player 2's big blind stays in the pot.
+        game.apply_action(3, Action::Fold).unwrap();
+        assert!(!game.is_hand_in_progress());
+        assert_eq!(get_money(&game, 1), 110);
+    }
+
+    #[test]
+    fn test_uncalled_all_in_is_returned() {
+        let mut game = Game::new(0, 10, 100);
+        for (player_id, money) in [(1, 100), (2, 40), (3, 100)] {
+            game.add_player(Player::new(player_id, format!("Player {}", player_id), money));
+        }
+        game.start_hand(false);
+
+        game.apply_action(3, Action::RaiseTo(100)).unwrap();
+        game.apply_action(1, Action::Fold).unwrap();
+        game.apply_action(2, Action::Call).unwrap();
+
+        // Player 2 can only call 40, so the other 60 of player 3's all in goes back to them before the board is dealt.
+        let hand_history = game.get_hand_history().unwrap();
+        let returned = hand_history.actions().iter().find(|action| *action.kind() == ActionKind::UncalledBetReturned).unwrap();
+        assert_eq!((*returned.street(), *returned.player_id(), *returned.amount()), (Street::Preflop, 3, 60));
+        assert_eq!(hand_history.pots().len(), 1);
+        assert_eq!(*hand_history.pots()[0].amount(), 85);
+        assert_eq!(hand_history.get_payouts().values().sum::<i32>(), 85);
+        assert_eq!(get_money(&game, 1) + get_money(&game, 2) + get_money(&game, 3), 240);
+    }
+
+    #[test]
+    fn test_action_error_serializes() {
+        let json = serde_json::to_string(&ActionError::RaiseTooSmall { minimum: 20 }).unwrap();
+        assert_eq!(json, r#"{"error":"RaiseTooSmall","minimum":20}"#);
+    }
+
+    #[test]
+    fn test_streets_advance_when_betting_closes() {
+        let mut game = game_in_progress();
+
+        game.apply_action(3, Action::Call).unwrap();
+        game.apply_action(1, Action::Call).unwrap();
+        assert_eq!(game.get_street(), Some(Street::Preflop));
+
+        // The big blind has the option to raise, even though everyone has matched their bet.
+        assert_eq!(game.next_to_act(), Some(2));
+        game.apply_action(2, Action::Check).unwrap();
+
+        for (street, num_community_cards) in [(Street::Flop, 3), (Street::Turn, 4), (Street::River, 5)] {
+            assert_eq!(game.get_street(), Some(street));
+            assert_eq!(game.get_community_cards().len(), num_community_cards);
+
+            for player_id in 1..=3 {
+                assert_eq!(game.next_to_act(), Some(player_id));
+                game.apply_action(player_id, Action::Check).unwrap();
+            }
+        }
+
+        assert_eq!(game.get_street(), Some(Street::Showdown));
+        assert_eq!(game.next_to_act(), None);
+        assert!(!game.is_hand_in_progress());
+        assert_eq!(game.get_total_player_money(), 300);
+        assert_eq!(game.apply_action(1, Action::Check), Err(ActionError::NoHandInProgress));
+    }
+
+    #[test]
+    fn test_hand_ends_when_everyone_else_folds() {
+        let mut game = game_in_progress();
+
+        game.apply_action(3, Action::Fold).unwrap();
+        game.apply_action(1, Action::Fold).unwrap();
+
+        assert!(!game.is_hand_in_progress());
+        assert!(game.get_community_cards().is_empty());
+        assert_eq!(get_money(&game, 1), 95);
+        assert_eq!(get_money(&game, 2), 105);
+    }
+
+    #[test]
+    fn test_board_runs_out_when_everyone_is_all_in() {
+        let mut game = game_in_progress();
+
+        game.apply_action(3, Action::AllIn).unwrap();
+        game.apply_action(1, Action::AllIn).unwrap();
+        game.apply_action(2, Action::Call).unwrap();
+
+        assert_eq!(game.get_street(), Some(Street::Showdown));
+        assert_eq!(game.get_community_cards().len(), 5);
+        assert_eq!(game.get_total_player_money(), 300);
+    }
+
+    #[test]
+    fn test_stacked_deck_scripts_hole_cards_and_board() {
+        let hole_cards = [
+            vec![Card::new(Suit::Spades, 14), Card::new(Suit::Hearts, 14)],
+            vec![Card::new(Suit::Spades, 13), Card::new(Suit::Hearts, 13)],
+            vec![Card::new(Suit::Clubs, 2), Card::new(Suit::Diamonds, 7)]
+        ];
+        let board = vec![
+            Card::new(Suit::Diamonds, 14), Card::new(Suit::Clubs, 14), Card::new(Suit::Hearts, 5),
+            Card::new(Suit::Spades, 9), Card::new(Suit::Diamonds, 11)
+        ];
+
+        let mut game = Game::new(0, 10, 100);
+        for player_id in 1..=3 {
+            game.add_player(Player::new(player_id, format!("Player {}", player_id), 100));
+        }
+        let top_cards = hole_cards.iter().flatten().chain(board.iter()).cloned().collect();
+        game.set_deck_source(Box::new(StackedDecks::new(vec![Deck::stacked(top_cards)])));
+        game.start_hand(false);
+
+        for (player_id, cards) in (1..=3).zip(hole_cards.iter()) {
+            assert_eq!(&game.player_id_to_player.get(&player_id).unwrap().get_hole_cards(), cards);
+        }
+
+        game.apply_action(3, Action::Call).unwrap();
+        game.apply_action(1, Action::Call).unwrap();
+        game.apply_action(2, Action::Check).unwrap();
+        while let Some(player_id) = game.next_to_act() {
+            game.apply_action(player_id, Action::Check).unwrap();
+        }
+
+        assert_eq!(game.get_community_cards(), &board);
+        assert_eq!(get_money(&game, 1), 120);
+        assert_eq!(get_money(&game, 2), 90);
+        assert_eq!(get_money(&game, 3), 90);
+    }
+
+    #[test]
+    fn test_provably_fair_hand_can_be_verified() {
+        let mut game = Game::new(0, 10, 100);
+        for player_id in 1..=3 {
+            game.add_player(Player::new(player_id, format!("Player {}", player_id), 100));
+        }
+
+        assert_eq!(game.add_shuffle_entropy(1, String::from("abc")), Err(ShuffleError::NotProvablyFair));
+        game.set_provably_fair(true);
+        assert_eq!(game.add_shuffle_entropy(7, String::from("abc")), Err(ShuffleError::UnknownPlayer { player_id: 7 }));
+
+        let commitment = game.get_shuffle_commitment().unwrap().to_string();
+        game.add_shuffle_entropy(1, String::from("abc")).unwrap();
+        game.add_shuffle_entropy(2, String::from("def")).unwrap();
+        game.start_hand(false);
+
+        // The next hand has a new commitment, and the seed is not revealed until this hand is over.
+        assert_ne!(game.get_shuffle_commitment().unwrap(), commitment);
+        assert_eq!(game.get_shuffle_reveal(), None);
+
+        game.apply_action(3, Action::Fold).unwrap();
+        game.apply_action(1, Action::Fold).unwrap();
+
+        let reveal = game.get_shuffle_reveal().unwrap();
+        assert!(fair_shuffle::verify_shuffle(&commitment, &reveal));
+        assert_eq!(reveal.client_entropy().get(&2), Some(&String::from("def")));
+        assert_eq!(&reveal.deck()[0..2], game.player_id_to_player.get(&1).unwrap().get_hole_cards().as_slice());
+    }
+
+    #[test]
+    fn test_hand_history_records_the_hand() {
+        let mut game = game_with_players(3);
+        let sink = MemorySink::new();
+        game.add_history_sink(Box::new(sink.clone()));
+        game.set_blind_structure({
+            let mut blinds = BlindStructure::new(5, 10);
+            blinds.set_ante(1);
+            blinds
+        });
+
+        game.start_hand(false);
+        game.apply_action(3, Action::RaiseTo(30)).unwrap();
+        game.apply_action(1, Action::Fold).unwrap();
+        game.apply_action(2, Action::Call).unwrap();
+        game.apply_action(2, Action::Check).unwrap();
+        game.apply_action(3, Action::Bet(20)).unwrap();
+        game.apply_action(2, Action::Fold).unwrap();
+
+        let hand_histories = sink.get_hand_histories();
+        assert_eq!(hand_histories.len(), 1);
+        let hand_history = &hand_histories[0];
+
+        assert_eq!(*hand_history.hand_number(), 1);
+        assert_eq!(*hand_history.button(), 2);
+        assert_eq!(hand_history.seats().iter().map(|seat| *seat.stack()).collect::<Vec<i32>>(), vec![100, 100, 100]);
+        assert_eq!(hand_history.seats()[0].hole_cards().len(), 2);
+
+        let actions: Vec<(Street, PlayerId, ActionKind, i32)> = hand_history.actions().iter()
+            .map(|action| (*action.street(), *action.player_id(), *action.kind(), *action.amount()))
+            .collect();
+        assert_eq!(actions, vec![
+            (Street::Preflop, 1, ActionKind::SmallBlind, 5),
+            (Street::Preflop, 2, ActionKind::BigBlind, 10),
+            (Street::Preflop, 1, ActionKind::Ante, 1),
+            (Street::Preflop, 2, ActionKind::Ante, 1),
+            (Street::Preflop, 3, ActionKind::Ante, 1),
+            (Street::Preflop, 3, ActionKind::RaiseTo(30), 30),
+            (Street::Preflop, 1, ActionKind::Fold, 0),
+            (Street::Preflop, 2, ActionKind::Call, 20),
+            (Street::Flop, 2, ActionKind::Check, 0),
+            (Street::Flop, 3, ActionKind::Bet(20), 20),
+            (Street::Flop, 2, ActionKind::Fold, 0),
+            (Street::Flop, 3, ActionKind::UncalledBetReturned, 20)
+        ]);
+
+        assert_eq!(hand_history.board().len(), 3);
+        assert_eq!(hand_history.showdown(), &None);
+        assert_eq!(hand_history.pots().iter().map(|pot| *pot.amount()).sum::<i32>(), 68);
+        assert_eq!(hand_history.get_payouts(), BTreeMap::from([(3, 68)]));
+        assert_eq!(game.get_hand_history(), Some(hand_history));
+    }
+
+    /// A sink that always fails, like a JSON lines file on a full disk.
+    #[derive(Debug)]
+    struct FailingSink;
+
+    impl HandHistorySink for FailingSink {
+        fn record(&mut self, _hand_history: &HandHistory) -> std::io::Result<()> {
+            Err(std::io::Error::other("disk full"))
+        }
+    }
+
+    #[test]
+    fn test_sink_failures_are_kept_for_the_caller() {
+        let mut game = game_in_progress();
+        let sink = MemorySink::new();
+        game.add_history_sink(Box::new(FailingSink));
+        game.add_history_sink(Box::new(sink.clone()));
+
+        game.apply_action(3, Action::Fold).unwrap();
+        game.apply_action(1, Action::Fold).unwrap();
+
+        // The other sinks still get the hand, and each error is only taken once.
+        let errors = game.take_history_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].get_hand_number(), 1);
+        assert_eq!(errors[0].to_string(), "Could not record hand 1: disk full");
+        assert_eq!(sink.get_hand_histories().len(), 1);
+        assert!(game.take_history_errors().is_empty());
+    }
+
+    #[test]
+    fn test_hand_history_records_showdown() {
+        let mut game = game_in_progress();
+        game.apply_action(3, Action::Call).unwrap();
+        game.apply_action(1, Action::Call).unwrap();
+        while let Some(player_id) = game.next_to_act() {
+            game.apply_action(player_id, Action::Check).unwrap();
+        }
+
+        let hand_history = game.get_hand_history().unwrap();
+        assert_eq!(hand_history.board(), game.get_community_cards());
+        let showdown = hand_history.showdown().as_ref().unwrap();
+        assert_eq!(showdown.order().len(), 3);
+        assert_eq!(showdown.shown().len() + showdown.get_mucked().len(), 3);
+        for player_id in hand_history.get_payouts().keys() {
+            assert!(showdown.get_shown_cards(player_id).is_some());
+        }
+        assert_eq!(hand_history.get_payouts().values().sum::<i32>(), 30);
+    }
+
+    /// Starts a hand where player 1 has four Aces and player 2 has two pair, Aces and Kings, by the river. Player 3
+    /// is first to act and player 1 is first to act after the flop.
+    fn showdown_game() -> Game {
+        let cards = vec![
+            Card::new(Suit::Spades, 14), Card::new(Suit::Hearts, 14),
+            Card::new(Suit::Spades, 13), Card::new(Suit::Hearts, 13),
+            Card::new(Suit::Clubs, 2), Card::new(Suit::Diamonds, 7),
+            Card::new(Suit::Diamonds, 14), Card::new(Suit::Clubs, 14), Card::new(Suit::Hearts, 5),
+            Card::new(Suit::Spades, 9), Card::new(Suit::Diamonds, 11)
+        ];
+
+        let mut game = game_with_players(3);
+        game.set_deck_source(Box::new(StackedDecks::new(vec![Deck::stacked(cards)])));
+        game.start_hand(false);
+        game
+    }
+
+    /// Plays `game` to the river with player 3 folding to player 2's bet on the flop.
+    fn play_to_river(game: &mut Game) {
+        game.apply_action(3, Action::Call).unwrap();
+        game.apply_action(1, Action::Call).unwrap();
+        game.apply_action(2, Action::Check).unwrap();
+        game.apply_action(1, Action::Check).unwrap();
+        game.apply_action(2, Action::Bet(10)).unwrap();
+        game.apply_action(3, Action::Fold).unwrap();
+        game.apply_action(1, Action::Call).unwrap();
+        game.apply_action(1, Action::Check).unwrap();
+        game.apply_action(2, Action::Check).unwrap();
+        assert_eq!(game.get_street(), Some(Street::River));
+    }
+
+    #[test]
+    fn test_losing_hand_mucks_when_checked_down() {
+        let mut game = showdown_game();
+        play_to_river(&mut game);
+        game.apply_action(1, Action::Check).unwrap();
+        game.apply_action(2, Action::Check).unwrap();
+
+        // Nobody bet on the river, so player 1 shows first after the button and player 2 mucks their beaten hand.
+        let showdown = game.get_hand_history().unwrap().showdown().clone().unwrap();
+        assert_eq!(*showdown.street(), Street::River);
+        assert!(!showdown.is_all_in());
+        assert_eq!(showdown.order(), &vec![1, 2]);
+        assert_eq!(showdown.get_mucked(), vec![2]);
+        assert!(game.spectator_view().seats().iter().all(|seat| seat.shown_cards().is_some() == (*seat.player_id() == 1)));
+    }
+
+    #[test]
+    fn test_last_aggressor_shows_first() {
+        let mut game = showdown_game();
+        play_to_river(&mut game);
+        game.apply_action(1, Action::Check).unwrap();
+        game.apply_action(2, Action::Bet(10)).unwrap();
+        game.apply_action(1, Action::Call).unwrap();
+
+        // Player 2 bet the river, so they show first and player 1 has to show to win.
+        let showdown = game.get_hand_history().unwrap().showdown().clone().unwrap();
+        assert_eq!(showdown.order(), &vec![2, 1]);
+        assert!(showdown.get_mucked().is_empty());
+    }
+
+    #[test]
+    fn test_player_can_choose_to_show_losing_hands() {
+        let mut game = showdown_game();
+        game.set_shows_losing_hands(2, true);
+        assert!(!game.mucks_losing_hands(&2));
+        play_to_river(&mut game);
+        game.apply_action(1, Action::Check).unwrap();
+        game.apply_action(2, Action::Check).unwrap();
+
+        let showdown = game.get_hand_history().unwrap().showdown().clone().unwrap();
+        assert!(showdown.get_mucked().is_empty());
+    }
+
+    #[test]
+    fn test_all_in_hands_are_shown_face_up() {
+        let mut game = showdown_game();
+        game.apply_action(3, Action::Fold).unwrap();
+        game.apply_action(1, Action::RaiseTo(100)).unwrap();
+        game.apply_action(2, Action::Call).unwrap();
+
+        let showdown = game.get_hand_history().unwrap().showdown().clone().unwrap();
+        assert_eq!(*showdown.street(), Street::Preflop);
+        assert!(showdown.is_all_in());
+        assert_eq!(showdown.order(), &vec![1, 2]);
+        assert!(showdown.get_mucked().is_empty());
+    }
+
+    /// Starts a hand that can be run twice, where player 1 makes four Aces on the first board and player 2 makes four
+    /// Kings on the second.
+    fn run_twice_game() -> Game {
+        let cards = vec![
+            Card::new(Suit::Spades, 14), Card::new(Suit::Hearts, 14),
+            Card::new(Suit::Spades, 13), Card::new(Suit::Hearts, 13),
+            Card::new(Suit::Clubs, 2), Card::new(Suit::Diamonds, 7),
+            Card::new(Suit::Diamonds, 14), Card::new(Suit::Clubs, 14), Card::new(Suit::Hearts, 5),
+            Card::new(Suit::Spades, 9), Card::new(Suit::Diamonds, 11),
+            Card::new(Suit::Diamonds, 13), Card::new(Suit::Clubs, 13), Card::new(Suit::Clubs, 5),
+            Card::new(Suit::Diamonds, 9), Card::new(Suit::Clubs, 11)
+        ];
+
+        let mut game = game_with_players(3);
+        game.set_max_runs(2);
+        game.set_deck_source(Box::new(StackedDecks::new(vec![Deck::stacked(cards)])));
+        game.start_hand(false);
+        game.apply_action(3, Action::Fold).unwrap();
+        game.apply_action(1, Action::RaiseTo(100)).unwrap();
+        game.apply_action(2, Action::Call).unwrap();
+        game
+    }
+
+    #[test]
+    fn test_board_is_run_twice_when_everyone_agrees() {
+        let mut game = run_twice_game();
+        assert!(game.is_hand_in_progress());
+        assert_eq!(game.get_players_to_choose_runs(), vec![1, 2]);
+
+        assert_eq!(game.choose_run_count(3, 2), Err(RunoutError::NotInHand));
+        assert_eq!(game.choose_run_count(1, 3), Err(RunoutError::InvalidRunCount { maximum: 2 }));
+        game.choose_run_count(1, 2).unwrap();
+        assert_eq!(game.get_players_to_choose_runs(), vec![2]);
+        game.choose_run_count(2, 2).unwrap();
+        assert!(!game.is_hand_in_progress());
+
+        // Each run is worth half of the 200 chip pot.
+        let hand_history = game.get_hand_history().unwrap();
+        let runs = hand_history.runs();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].board(), hand_history.board());
+        assert_eq!(runs[0].payouts(), &vec![BTreeMap::from([(1, 100)])]);
+        assert_eq!(runs[1].board()[0], Card::new(Suit::Diamonds, 13));
+        assert_eq!(runs[1].payouts(), &vec![BTreeMap::from([(2, 100)])]);
+        // The shown hands are read on each run's own board.
+        assert_eq!(hand_history.showdown().as_ref().unwrap().shown()[0].score(), &runs[0].scores()[&1]);
+        assert!(runs[0].scores()[&1] > runs[0].scores()[&2]);
+        assert!(runs[1].scores()[&2] > runs[1].scores()[&1]);
+        assert_eq!(hand_history.pots()[0].payouts(), &BTreeMap::from([(1, 100), (2, 100)]));
+        assert_eq!(get_money(&game, 2), 100);
+
+        assert_eq!(Replayer::new(hand_history.clone()).unwrap().finish().as_ref(), Ok(hand_history));
+    }
+
+    #[test]
+    fn test_board_is_run_once_unless_everyone_agrees() {
+        let mut game = run_twice_game();
+        game.choose_run_count(2, 1).unwrap();
+        assert!(game.is_hand_in_progress());
+        game.choose_run_count(1, 2).unwrap();
+
+        let hand_history = game.get_hand_history().unwrap();
+        assert!(hand_history.runs().is_empty());
+        assert_eq!(hand_history.pots()[0].payouts(), &BTreeMap::from([(1, 200)]));
+        assert_eq!(game.choose_run_count(1, 2), Err(RunoutError::NoHandInProgress));
+    }
+
+    #[test]
+    fn test_view_for_only_shows_own_hole_cards() {
+        let mut game = game_in_progress();
+        let view = game.view_for(2);
+
+        assert_eq!(view.hole_cards(), &game.player_id_to_player.get(&2).unwrap().get_hole_cards());
+        assert_eq!(view.seats().iter().map(|seat| *seat.player_id()).collect::<Vec<PlayerId>>(), vec![2, 3, 1]);
+        assert!(view.seats().iter().all(|seat| seat.shown_cards().is_none()));
+        assert_eq!(*view.next_to_act(), Some(3));
+        assert_eq!(view.legal_actions(), &LegalActions::none());
+        assert_eq!(*game.view_for(3).legal_actions().amount_to_call(), 10);
+
+        // Player 1 folds, and is not dealt into the next hand after sitting out, so they get no hole cards.
+        game.apply_action(3, Action::Fold).unwrap();
+        game.apply_action(1, Action::Fold).unwrap();
+        game.sit_out(1);
+        game.start_hand(false);
+        assert!(game.view_for(1).hole_cards().is_empty());
+        assert!(!game.view_for(1).seats().iter().any(|seat| *seat.player_id() == 1));
+    }
+
+    #[test]
+    fn test_views_show_hands_at_showdown() {
+        let mut game = game_in_progress();
+        game.apply_action(3, Action::Call).unwrap();
+        game.apply_action(1, Action::Fold).unwrap();
+        game.apply_action(2, Action::Check).unwrap();
+
+        let spectator_view = game.spectator_view();
+        assert_eq!(*spectator_view.street(), Some(Street::Flop));
+        assert_eq!(*spectator_view.pot(), 25);
+        assert!(spectator_view.seats().iter().all(|seat| seat.shown_cards().is_none()));
+        assert!(spectator_view.seats()[0].has_folded());
+
+        while let Some(player_id) = game.next_to_act() {
+            game.apply_action(player_id, Action::Check).unwrap();
+        }
+
+        // The hands shown at showdown are shown to everyone. Player 1 folded, so theirs stay hidden.
+        let showdown = game.get_hand_history().unwrap().showdown().clone().unwrap();
+        assert_eq!(showdown.order().len(), 2);
+        for view_seats in [game.spectator_view().seats().clone(), game.view_for(1).seats().clone()] {
+            for seat in view_seats.iter() {
+                assert_eq!(seat.shown_cards().as_ref(), showdown.get_shown_cards(seat.player_id()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_play_one_round_with_scripted_agents() {
+        let mut game = Game::new(0, 10, 100);
+        for player_id in 1..=3 {
+            game.add_player(Player::new(player_id, format!("Player {}", player_id), 100));
+        }
+
+        let mut agents = HashMap::<PlayerId, Box<dyn PlayerAgent>>::new();
+        agents.insert(1, Box::new(ScriptedAgent::new(vec![Action::Call, Action::Bet(20), Action::Fold])));
+        agents.insert(2, Box::new(ScriptedAgent::new(vec![Action::Check, Action::Call])));
+        agents.insert(3, Box::new(ScriptedAgent::new(vec![Action::RaiseTo(10), Action::Call, Action::Fold])));
+
+        game.play_one_round(&mut agents, false);
+
+        // Player 3's preflop raise is too small, so their agent is asked again and calls instead. Player 3 folds to
+        // the bet on the flop, and player 1 folds to player 2 on the turn.
+        assert!(!game.is_hand_in_progress());
+        assert_eq!(game.get_community_cards().len(), 4);
+        assert_eq!(get_money(&game, 1), 70);
+        assert_eq!(get_money(&game, 2), 140);
+        assert_eq!(get_money(&game, 3), 90);
+    }
+
+    #[test]
+    fn test_start_game_plays_until_one_player_is_left() {
+        let cards = vec![
+            Card::new(Suit::Spades, 14), Card::new(Suit::Hearts, 14),
+            Card::new(Suit::Clubs, 7), Card::new(Suit::Diamonds, 2),
+            Card::new(Suit::Spades, 13), Card::new(Suit::Diamonds, 12), Card::new(Suit::Clubs, 9),
+            Card::new(Suit::Hearts, 4), Card::new(Suit::Spades, 3)
+        ];
+
+        let mut game = game_with_players(2);
+        game.set_deck_source(Box::new(StackedDecks::new(vec![Deck::stacked(cards)])));
+        let mut agents = HashMap::<PlayerId, Box<dyn PlayerAgent>>::new();
+        agents.insert(1, Box::new(ScriptedAgent::new(vec![Action::AllIn])));
+        agents.insert(2, Box::new(ScriptedAgent::new(vec![Action::AllIn])));
+
+        // Whoever is dealt the aces wins everything in the first hand, so no more actions are needed.
+        game.start_game(&mut agents, None, false);
+        assert_eq!(game.get_table().get_players().len(), 1);
+        assert_eq!(get_money(&game, game.get_table().get_players()[0]), 200);
     }
 }
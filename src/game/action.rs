@@ -0,0 +1,136 @@
+use getset::Getters;
+use serde::{Deserialize, Serialize};
+
+use crate::game::player::PlayerId;
+
+
+/// Represents a decision a player can make on their turn.
+///
+/// Amounts are the total the player will have bet on the current street once the action is made, not the amount
+/// added on top of what they have already bet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", content = "amount")]
+pub enum Action {
+    Fold,
+    Check,
+    /// Matches the current bet, or puts the player all in if they cannot match it.
+    Call,
+    /// Opens the betting on this street so the player has bet the given total.
+    Bet(i32),
+    /// Raises the current bet so the player has bet the given total on this street. This is "raise to", not
+    /// "raise by": raising a bet of 20 to 50 is `RaiseTo(50)`.
+    RaiseTo(i32),
+    AllIn
+}
+
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Action::Fold => write!(f, "fold"),
+            Action::Check => write!(f, "check"),
+            Action::Call => write!(f, "call"),
+            Action::Bet(amount) => write!(f, "bet {}", amount),
+            Action::RaiseTo(amount) => write!(f, "raise to {}", amount),
+            Action::AllIn => write!(f, "all in")
+        }
+    }
+}
+
+
+/// The reasons `Game::apply_action` can reject an `Action`.
+///
+/// These are serializable so the server can send them back to the client that made the action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "error")]
+pub enum ActionError {
+    /// There is no hand being played.
+    NoHandInProgress,
+    /// The player is not seated at this game.
+    UnknownPlayer { player_id: PlayerId },
+    /// It is another player's turn.
+    NotYourTurn { player_to_act: Option<PlayerId> },
+    /// The player has already folded this hand.
+    PlayerFolded,
+    /// The player is all in and cannot act again this hand.
+    PlayerAllIn,
+    /// The player tried to check while facing a bet.
+    CannotCheckFacingBet { amount_to_call: i32 },
+    /// The player tried to call when there was nothing to call.
+    NothingToCall,
+    /// The player tried to open the betting when there is already a bet. They must raise instead.
+    CannotBetFacingBet { current_bet: i32 },
+    /// The player tried to raise when there was no bet. They must bet instead.
+    NothingToRaise,
+    /// The bet is smaller than the minimum bet.
+    BetTooSmall { minimum: i32 },
+    /// The raise is smaller than the minimum raise.
+    RaiseTooSmall { minimum: i32 },
+    /// The bet is bigger than the betting structure allows.
+    BetTooLarge { maximum: i32 },
+    /// The raise is bigger than the betting structure allows.
+    RaiseTooLarge { maximum: i32 },
+    /// The street has already been raised as many times as a fixed-limit game allows.
+    RaiseCapReached,
+    /// The player does not have enough chips. `maximum` is the most they can have bet on this street.
+    NotEnoughChips { maximum: i32 },
+    /// The player has already acted and the bet has only been raised by an all in that was too small to be a full
+    /// raise, so they can only call or fold.
+    BettingNotReopened
+}
+
+
+impl std::fmt::Display for ActionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ActionError::NoHandInProgress => write!(f, "There is no hand in progress"),
+            ActionError::UnknownPlayer { player_id } => write!(f, "Player {} is not in this game", player_id),
+            ActionError::NotYourTurn { player_to_act: Some(player_id) } => write!(f, "It is player {}'s turn", player_id),
+            ActionError::NotYourTurn { player_to_act: None } => write!(f, "It is nobody's turn"),
+            ActionError::PlayerFolded => write!(f, "You have already folded"),
+            ActionError::PlayerAllIn => write!(f, "You are already all in"),
+            ActionError::CannotCheckFacingBet { amount_to_call } => write!(f, "You cannot check, there is {} to call", amount_to_call),
+            ActionError::NothingToCall => write!(f, "There is nothing to call"),
+            ActionError::CannotBetFacingBet { current_bet } => write!(f, "There is already a bet of {}, raise instead", current_bet),
+            ActionError::NothingToRaise => write!(f, "There is no bet to raise, bet instead"),
+            ActionError::BetTooSmall { minimum } => write!(f, "Bet must be at least {}", minimum),
+            ActionError::RaiseTooSmall { minimum } => write!(f, "Raise must be to at least {}", minimum),
+            ActionError::BetTooLarge { maximum } => write!(f, "Bet can be at most {}", maximum),
+            ActionError::RaiseTooLarge { maximum } => write!(f, "Raise can be to at most {}", maximum),
+            ActionError::RaiseCapReached => write!(f, "The betting has been capped, you can only call or fold"),
+            ActionError::NotEnoughChips { maximum } => write!(f, "You can bet at most {}", maximum),
+            ActionError::BettingNotReopened => write!(f, "The betting has not been reopened, you can only call or fold")
+        }
+    }
+}
+
+impl std::error::Error for ActionError {}
+
+
+/// What happened when `Game::apply_action` accepted an `Action`.
+///
+/// # Fields
+///
+/// * `player_id`: The player who acted.
+/// * `action`: The action that was made.
+/// * `amount`: The number of chips the player put in with this action.
+/// * `is_all_in`: Whether the player has no chips left after this action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct ActionOutcome {
+    player_id: PlayerId,
+    action: Action,
+    amount: i32,
+    is_all_in: bool
+}
+
+impl ActionOutcome {
+    pub(crate) fn new(player_id: PlayerId, action: Action, amount: i32, is_all_in: bool) -> ActionOutcome {
+        ActionOutcome {
+            player_id,
+            action,
+            amount,
+            is_all_in
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use std::cmp::max;
+use std::collections::VecDeque;
+use std::io;
+use std::io::Write;
+
+use crate::game::action::{Action, ActionError};
+use crate::game::view::PlayerView;
+use crate::utils;
+
+
+/// Something that makes the decisions for one player, such as a person at a terminal, a bot, a test script or a
+/// client connected over the network.
+///
+/// `Game::start_game` asks a player's agent for an `Action` every time it is that player's turn.
+pub trait PlayerAgent: Send {
+    /// Decides what the player does on their turn.
+    ///
+    /// # Parameters
+    ///
+    /// * `view`: What the player can see of the table. It only contains their own hole cards.
+    fn decide(&mut self, view: &PlayerView) -> Action;
+
+    /// Called when the game rejects the `Action` returned by `decide`. The game will call `decide` again afterwards.
+    ///
+    /// # Parameters
+    ///
+    /// * `action`: The action that was rejected.
+    /// * `error`: Why the action was rejected.
+    fn on_invalid_action(&mut self, _action: Action, _error: &ActionError) {}
+
+    /// Decides how many times the player agrees to run the board when everyone still in the hand is all in. By
+    /// default the player only runs it once.
+    ///
+    /// # Parameters
+    ///
+    /// * `view`: What the player can see of the table.
+    /// * `max_runs`: The most times the game allows the board to be run.
+    fn choose_run_count(&mut self, _view: &PlayerView, _max_runs: u32) -> u32 {
+        1
+    }
+}
+
+
+/// A `PlayerAgent` that shows the table in the terminal and reads the player's decision from stdin.
+#[derive(Debug, Default)]
+pub struct TerminalAgent;
+
+impl TerminalAgent {
+    pub fn new() -> TerminalAgent {
+        TerminalAgent
+    }
+
+    fn format_community_cards(view: &PlayerView) -> VecDeque<String> {
+        let mut output = VecDeque::<String>::new();
+        if !view.community_cards().is_empty() {
+            for card in view.community_cards() {
+                output.push_back(card.to_string());
+            }
+        } else {
+            output.push_back("Community cards have not been dealt.".to_string());
+        }
+
+        output
+    }
+
+    /// Prints the players still in the hand in the order they will play their turns, with an arrow pointing to the
+    /// player whose turn it is.
+    fn print_turn_state(view: &PlayerView) {
+        let width = view.player_id().to_string().len() / 2 + "Player: ".len();
+
+        let mut output = VecDeque::<VecDeque<String>>::new();
+
+        view.seats().iter().filter(|seat| seat.is_in_hand()).enumerate()
+            .for_each(|(i, seat)| {
+                let mut this_output = VecDeque::<String>::new();
+
+                if i == 0 {
+                    this_output.push_back(" ".repeat(width) + "|");
+                    this_output.push_back(" ".repeat(width) + "|");
+                    this_output.push_back(" ".repeat(width) + "v");
+                } else {
+                    for _ in 0..3 {
+                        this_output.push_back("".to_string());
+                    }
+                }
+
+                this_output.push_back("Player: ".to_string() + &seat.player_id().to_string());
+                this_output.push_back("Contribution: ".to_string() + &seat.contribution().to_string());
+                this_output.push_back("Money: ".to_string() + &seat.money().to_string());
+
+                output.push_back(this_output);
+            });
+
+        let output_str = utils::format_next_to_each_other(output);
+
+        let turn_state = " Turn State ";
+
+        let num_dashes_total = output_str.split('\n').map(|str| str.len()).max().expect("Error in calculating dashes");
+
+        let dashes = "-".repeat((max((num_dashes_total as i32) - (turn_state.len() as i32), 0) / 2) as usize);
+
+        println!();
+        println!("{}", dashes.clone() + turn_state + &dashes);
+        println!("{}", output_str);
+    }
+
+    fn print_cards(view: &PlayerView) {
+        let mut player_vec = view.hole_cards().iter().map(|card| card.to_string()).collect::<VecDeque<String>>();
+        player_vec.push_front(utils::get_dashes_for_longest_string(player_vec.clone()));
+        player_vec.push_front("Your Cards".to_string());
+
+        let mut community_vec = Self::format_community_cards(view);
+        community_vec.push_front(utils::get_dashes_for_longest_string(community_vec.clone()));
+        community_vec.push_front("Community Cards:".to_string());
+        let cards_display_str = utils::format_next_to_each_other(vec!(player_vec, community_vec));
+        println!("{}", cards_display_str);
+        println!();
+    }
+
+    fn print_pot_state(view: &PlayerView) {
+        let bet_str = vec!["Table's Current Bet".to_string(), utils::dashes(18), view.current_bet().to_string()];
+        let money_in_pot_str = vec!["Your Contribution to the Pot".to_string(), utils::dashes(29), view.hand_contribution().to_string()];
+        let curr_bet_contribution = vec!["Your Current Contribution to the Bet".to_string(), utils::dashes(37), view.street_contribution().to_string()];
+        let money_str = vec!["Your Money".to_string(), utils::dashes(15), view.money().to_string()];
+
+        let output = utils::format_next_to_each_other(vec![bet_str, money_in_pot_str, curr_bet_contribution, money_str]);
+        println!("{}", output);
+        println!();
+    }
+
+    fn read_line() -> String {
+        io::stdout().flush().unwrap();
+        let mut input = String::new();
+        io::stdin().read_line(&mut input).unwrap();
+        input.trim().to_string()
+    }
+}
+
+impl PlayerAgent for TerminalAgent {
+    fn decide(&mut self, view: &PlayerView) -> Action {
+        Self::print_turn_state(view);
+        println!();
+        Self::print_cards(view);
+        Self::print_pot_state(view);
+
+        loop {
+            match view.get_amount_to_call() {
+                0 => print!("{}, Would you like to fold, bet, go all in, or check? ", view.name()),
+                _ => print!("{}, Would you like to fold, raise, go all in, or call? ", view.name())
+            }
+
+            match Self::read_line().as_str() {
+                "fold" => return Action::Fold,
+                "check" => return Action::Check,
+                "call" => return Action::Call,
+                "all in" => return Action::AllIn,
+                "bet" | "raise" => {
+                    let legal_actions = view.legal_actions();
+                    match (view.current_bet(), legal_actions.bet_range(), legal_actions.raise_range()) {
+                        (0, Some(range), _) => print!("Bet how much? ({} to {}) ", range.min(), range.max()),
+                        (_, _, Some(range)) => print!("Raise to how much? ({} to {}) ", range.min(), range.max()),
+                        _ => {
+                            println!("You cannot raise, you can only call or fold.");
+                            continue;
+                        }
+                    }
+
+                    match Self::read_line().parse::<i32>() {
+                        Ok(amount) if *view.current_bet() == 0 => return Action::Bet(amount),
+                        Ok(amount) => return Action::RaiseTo(amount),
+                        Err(_) => println!("Please enter a whole number of chips.")
+                    }
+                },
+                _ => println!("Please enter fold, check, call, bet, raise, or all in.")
+            }
+        }
+    }
+
+    fn on_invalid_action(&mut self, _action: Action, error: &ActionError) {
+        println!("{}", error);
+    }
+
+    fn choose_run_count(&mut self, view: &PlayerView, max_runs: u32) -> u32 {
+        Self::print_cards(view);
+
+        loop {
+            print!("{}, everyone is all in. How many times do you want to run it? (1 to {}) ", view.name(), max_runs);
+
+            match Self::read_line().parse::<u32>() {
+                Ok(runs) if (1..=max_runs).contains(&runs) => return runs,
+                _ => println!("Please enter a whole number from 1 to {}.", max_runs)
+            }
+        }
+    }
+}
+
+
+/// A `PlayerAgent` that plays a fixed list of actions in order, for tests and simulations.
+///
+/// # Panics
+///
+/// `decide` will panic if it is asked for more actions than were scripted.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptedAgent {
+    actions: VecDeque<Action>
+}
+
+impl ScriptedAgent {
+    pub fn new(actions: Vec<Action>) -> ScriptedAgent {
+        ScriptedAgent {
+            actions: VecDeque::from(actions)
+        }
+    }
+
+    /// Returns the number of scripted actions that have not been played yet.
+    pub fn get_num_remaining(&self) -> usize {
+        self.actions.len()
+    }
+}
+
+impl PlayerAgent for ScriptedAgent {
+    fn decide(&mut self, view: &PlayerView) -> Action {
+        self.actions.pop_front()
+            .unwrap_or_else(|| panic!("Player {} ran out of scripted actions", view.player_id()))
+    }
+}
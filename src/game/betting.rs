@@ -0,0 +1,226 @@
+use getset::Getters;
+use serde::{Deserialize, Serialize};
+
+use crate::game::hand_state::{HandState, Street};
+use crate::game::player::PlayerId;
+
+
+/// The rules that limit how much a player can bet or raise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BettingStructure {
+    /// A player can bet or raise any amount up to their whole stack.
+    NoLimit,
+    /// A player can bet or raise at most the size of the pot. A raise can be at most the size of the pot after the
+    /// player has called.
+    PotLimit,
+    /// Every bet and raise is a fixed size: `small_bet` preflop and on the flop, and `big_bet` on the turn and river.
+    /// At most `raise_cap` raises can follow the bet that opens the betting on each street.
+    FixedLimit { small_bet: i32, big_bet: i32, raise_cap: u32 }
+}
+
+impl BettingStructure {
+    /// Returns the smallest bet on `street` for a game with a big blind of `big_blind`.
+    pub fn get_min_bet(&self, street: Street, big_blind: i32) -> i32 {
+        match self {
+            BettingStructure::NoLimit | BettingStructure::PotLimit => big_blind,
+            BettingStructure::FixedLimit { small_bet, big_bet, .. } => match street {
+                Street::Preflop | Street::Flop => *small_bet,
+                Street::Turn | Street::River | Street::Showdown => *big_bet
+            }
+        }
+    }
+
+    /// Returns whether the raise cap has been reached on the current street, so nobody can raise again.
+    pub fn is_raise_capped(&self, hand_state: &HandState) -> bool {
+        match self {
+            BettingStructure::FixedLimit { raise_cap, .. } => hand_state.get_num_raises() >= *raise_cap,
+            BettingStructure::NoLimit | BettingStructure::PotLimit => false
+        }
+    }
+
+    /// Works out what `player_id` is allowed to do on their turn.
+    ///
+    /// # Parameters
+    ///
+    /// * `hand_state`: The state of the hand being played.
+    /// * `player_id`: The player whose turn it is.
+    /// * `money`: The chips the player has left in front of them.
+    /// * `pot`: The total number of chips put in this hand, including the bets on this street.
+    ///
+    /// # Returns
+    ///
+    /// * `LegalActions` - The actions the player can make. Bet and raise ranges are "raise to" amounts.
+    ///
+    /// # Notes
+    ///
+    /// The smallest bet or raise never goes below the player's all in amount, since a player can always go all in
+    /// for less than the minimum.
+    pub(crate) fn get_legal_actions(&self, hand_state: &HandState, player_id: &PlayerId, money: i32, pot: i32) -> LegalActions {
+        let current_bet = hand_state.get_current_bet();
+        let contribution = hand_state.get_street_contribution(player_id);
+        let all_in_amount = contribution + money;
+        let amount_to_call = (current_bet - contribution).clamp(0, money);
+
+        let (min, max) = if current_bet == 0 {
+            let max = match self {
+                BettingStructure::NoLimit => all_in_amount,
+                BettingStructure::PotLimit => pot,
+                BettingStructure::FixedLimit { .. } => hand_state.get_min_bet()
+            };
+            (hand_state.get_min_bet(), max)
+        } else {
+            let max = match self {
+                BettingStructure::NoLimit => all_in_amount,
+                // The player may call and then raise by the size of the pot after their call.
+                BettingStructure::PotLimit => current_bet + pot + (current_bet - contribution),
+                BettingStructure::FixedLimit { .. } => hand_state.get_min_raise_to()
+            };
+            (hand_state.get_min_raise_to(), max)
+        };
+
+        let range = BetRange::new(min.min(all_in_amount), max.max(min).min(all_in_amount));
+
+        let bet_range = if current_bet == 0 && money > 0 { Some(range) } else { None };
+        let raise_range = if current_bet > 0 && all_in_amount > current_bet && hand_state.can_raise(player_id) && !self.is_raise_capped(hand_state) {
+            Some(range)
+        } else {
+            None
+        };
+
+        LegalActions {
+            can_check: amount_to_call == 0,
+            amount_to_call,
+            bet_range,
+            raise_range
+        }
+    }
+}
+
+
+/// The smallest and largest total a player can bet or raise to, inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct BetRange {
+    min: i32,
+    max: i32
+}
+
+impl BetRange {
+    pub(crate) fn new(min: i32, max: i32) -> BetRange {
+        BetRange {
+            min,
+            max
+        }
+    }
+
+    pub fn contains(&self, amount: i32) -> bool {
+        self.min <= amount && amount <= self.max
+    }
+}
+
+
+/// What a player is allowed to do on their turn.
+///
+/// # Fields
+///
+/// * `can_check`: Whether the player can check.
+/// * `amount_to_call`: The chips the player has to put in to call. This is less than the bet if calling puts them all in.
+/// * `bet_range`: The totals the player can bet, or `None` if they cannot bet.
+/// * `raise_range`: The totals the player can raise to, or `None` if they cannot raise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct LegalActions {
+    can_check: bool,
+    amount_to_call: i32,
+    bet_range: Option<BetRange>,
+    raise_range: Option<BetRange>
+}
+
+impl LegalActions {
+    /// Returns the legal actions of a player whose turn it is not, who cannot do anything.
+    pub(crate) fn none() -> LegalActions {
+        LegalActions {
+            can_check: false,
+            amount_to_call: 0,
+            bet_range: None,
+            raise_range: None
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Three players on the flop with a pot of 30 and a big blind of 10.
+    fn flop(structure: BettingStructure) -> HandState {
+        let mut hand_state = HandState::new(vec![1, 2, 3], 0, 10);
+        hand_state.next_street(structure.get_min_bet(Street::Flop, 10));
+        hand_state
+    }
+
+    #[test]
+    fn test_no_limit_ranges() {
+        let mut hand_state = flop(BettingStructure::NoLimit);
+
+        let legal_actions = BettingStructure::NoLimit.get_legal_actions(&hand_state, &1, 200, 30);
+        assert!(legal_actions.can_check);
+        assert_eq!(legal_actions.bet_range, Some(BetRange::new(10, 200)));
+        assert_eq!(legal_actions.raise_range, None);
+
+        hand_state.record_bet(1, 25, false);
+        let legal_actions = BettingStructure::NoLimit.get_legal_actions(&hand_state, &2, 200, 55);
+        assert_eq!(legal_actions.amount_to_call, 25);
+        assert_eq!(legal_actions.bet_range, None);
+        assert_eq!(legal_actions.raise_range, Some(BetRange::new(50, 200)));
+    }
+
+    #[test]
+    fn test_pot_limit_call_then_pot() {
+        let mut hand_state = flop(BettingStructure::PotLimit);
+
+        let legal_actions = BettingStructure::PotLimit.get_legal_actions(&hand_state, &1, 200, 30);
+        assert_eq!(legal_actions.bet_range, Some(BetRange::new(10, 30)));
+
+        // After a bet of 20 into 30 the pot is 50. Calling makes it 70, so the most player 2 can raise to is 20 + 70.
+        hand_state.record_bet(1, 20, false);
+        let legal_actions = BettingStructure::PotLimit.get_legal_actions(&hand_state, &2, 200, 50);
+        assert_eq!(legal_actions.raise_range, Some(BetRange::new(40, 90)));
+
+        // The range never goes past the player's stack.
+        let legal_actions = BettingStructure::PotLimit.get_legal_actions(&hand_state, &2, 60, 50);
+        assert_eq!(legal_actions.raise_range, Some(BetRange::new(40, 60)));
+    }
+
+    #[test]
+    fn test_fixed_limit_sizes_and_cap() {
+        let structure = BettingStructure::FixedLimit { small_bet: 10, big_bet: 20, raise_cap: 2 };
+        assert_eq!(structure.get_min_bet(Street::Flop, 10), 10);
+        assert_eq!(structure.get_min_bet(Street::Turn, 10), 20);
+
+        let mut hand_state = flop(structure);
+        assert_eq!(structure.get_legal_actions(&hand_state, &1, 200, 30).bet_range, Some(BetRange::new(10, 10)));
+
+        hand_state.record_bet(1, 10, false);
+        assert_eq!(structure.get_legal_actions(&hand_state, &2, 200, 40).raise_range, Some(BetRange::new(20, 20)));
+
+        hand_state.record_bet(2, 20, false);
+        hand_state.record_bet(3, 30, false);
+        assert_eq!(hand_state.get_num_raises(), 2);
+        assert_eq!(structure.get_legal_actions(&hand_state, &1, 200, 90).raise_range, None);
+    }
+
+    #[test]
+    fn test_short_stack_can_only_go_all_in() {
+        let mut hand_state = flop(BettingStructure::NoLimit);
+        hand_state.record_bet(1, 50, false);
+
+        let legal_actions = BettingStructure::NoLimit.get_legal_actions(&hand_state, &2, 70, 80);
+        assert_eq!(legal_actions.raise_range, Some(BetRange::new(70, 70)));
+
+        let legal_actions = BettingStructure::NoLimit.get_legal_actions(&hand_state, &2, 40, 80);
+        assert_eq!(legal_actions.amount_to_call, 40);
+        assert_eq!(legal_actions.raise_range, None);
+    }
+}
@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+
+
+/// The forced bets that are posted before the cards are dealt.
+///
+/// # Fields
+///
+/// * `small_blind`: The amount the first player after the button posts.
+/// * `big_blind`: The amount the second player after the button posts. This is also the smallest bet in no-limit and
+///   pot-limit games.
+/// * `ante`: The amount every player dealt in puts in the pot. Antes are dead money: they do not count towards the
+///   player's bet.
+/// * `big_blind_ante`: An ante that the big blind pays on behalf of the whole table. It is dead money like `ante`.
+/// * `straddle`: If set, the player after the big blind posts this amount as a live blind and acts last preflop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlindStructure {
+    small_blind: i32,
+    big_blind: i32,
+    ante: i32,
+    big_blind_ante: i32,
+    straddle: Option<i32>
+}
+
+impl BlindStructure {
+    /// Creates a blind structure with the given blinds and no antes or straddle.
+    pub fn new(small_blind: i32, big_blind: i32) -> BlindStructure {
+        BlindStructure {
+            small_blind,
+            big_blind,
+            ante: 0,
+            big_blind_ante: 0,
+            straddle: None
+        }
+    }
+
+    pub fn get_small_blind(&self) -> i32 {
+        self.small_blind
+    }
+
+    pub fn get_big_blind(&self) -> i32 {
+        self.big_blind
+    }
+
+    pub fn get_ante(&self) -> i32 {
+        self.ante
+    }
+
+    pub fn set_ante(&mut self, ante: i32) {
+        self.ante = ante;
+    }
+
+    pub fn get_big_blind_ante(&self) -> i32 {
+        self.big_blind_ante
+    }
+
+    pub fn set_big_blind_ante(&mut self, big_blind_ante: i32) {
+        self.big_blind_ante = big_blind_ante;
+    }
+
+    pub fn get_straddle(&self) -> Option<i32> {
+        self.straddle
+    }
+
+    pub fn set_straddle(&mut self, straddle: Option<i32>) {
+        self.straddle = straddle;
+    }
+}
+
+
+/// The blinds a player missed while sitting out. They have to post them when they sit back in.
+///
+/// # Fields
+///
+/// * `small_blind`: Whether the player missed the small blind. It is posted dead, so it does not count towards their bet.
+/// * `big_blind`: Whether the player missed the big blind. It is posted live, so it counts towards their bet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct MissedBlinds {
+    small_blind: bool,
+    big_blind: bool
+}
+
+impl MissedBlinds {
+    pub fn has_missed_small_blind(&self) -> bool {
+        self.small_blind
+    }
+
+    pub fn has_missed_big_blind(&self) -> bool {
+        self.big_blind
+    }
+
+    pub(crate) fn miss_small_blind(&mut self) {
+        self.small_blind = true;
+    }
+
+    pub(crate) fn miss_big_blind(&mut self) {
+        self.big_blind = true;
+    }
+
+    /// Returns whether the player owes any blinds.
+    pub fn is_empty(&self) -> bool {
+        !self.small_blind && !self.big_blind
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::{HashSet, VecDeque};
+use std::fmt::Debug;
+
+use rand::rngs::OsRng;
+use rand::seq::SliceRandom;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+use crate::game::hand::Card;
+
+
+/// A deck of cards in the order they will be dealt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deck {
+    cards: VecDeque<Card>
+}
+
+impl Deck {
+    /// Creates a full deck in a fixed order, sorted by suit and then by value.
+    pub fn new() -> Deck {
+        let mut cards: Vec<Card> = Card::new_full_deck().into_iter().collect();
+        cards.sort();
+
+        Deck {
+            cards: cards.into()
+        }
+    }
+
+    /// Creates a full deck shuffled with `rng`. The same RNG state always gives the same order.
+    pub fn shuffled<R: RngCore + ?Sized>(rng: &mut R) -> Deck {
+        let mut cards: Vec<Card> = Deck::new().cards.into();
+        cards.shuffle(rng);
+
+        Deck {
+            cards: cards.into()
+        }
+    }
+
+    /// Creates a full deck that deals `top_cards` first, in the order given, followed by the rest of the deck in the
+    /// order of `Deck::new`.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `top_cards` has the same card more than once.
+    pub fn stacked(top_cards: Vec<Card>) -> Deck {
+        let stacked: HashSet<Card> = top_cards.iter().cloned().collect();
+        assert_eq!(stacked.len(), top_cards.len(), "A stacked deck cannot have the same card twice");
+
+        let mut cards: VecDeque<Card> = top_cards.into();
+        cards.extend(Deck::new().cards.into_iter().filter(|card| !stacked.contains(card)));
+
+        Deck {
+            cards
+        }
+    }
+
+    /// Removes the top card of the deck, or returns `None` if the deck is empty.
+    pub fn deal(&mut self) -> Option<Card> {
+        self.cards.pop_front()
+    }
+
+    /// Returns the cards left in the deck, in the order they will be dealt.
+    pub fn get_cards(&self) -> &VecDeque<Card> {
+        &self.cards
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+}
+
+impl Default for Deck {
+    fn default() -> Self {
+        Deck::new()
+    }
+}
+
+
+/// Gives `Game` a new deck for each hand.
+pub trait DeckSource: Debug + Send {
+    fn next_deck(&mut self) -> Deck;
+}
+
+
+/// A `DeckSource` that shuffles a full deck with its RNG for each hand.
+#[derive(Debug)]
+pub struct ShuffledDecks<R> {
+    rng: R
+}
+
+impl<R: RngCore> ShuffledDecks<R> {
+    pub fn new(rng: R) -> ShuffledDecks<R> {
+        ShuffledDecks {
+            rng
+        }
+    }
+}
+
+impl ShuffledDecks<OsRng> {
+    /// Shuffles with the operating system's random number generator. This is what `Game` uses unless it is given
+    /// another source.
+    pub fn from_os_rng() -> ShuffledDecks<OsRng> {
+        ShuffledDecks::new(OsRng)
+    }
+}
+
+impl ShuffledDecks<ChaCha20Rng> {
+    /// Shuffles with a ChaCha RNG seeded with `seed`, so the same seed always deals the same hands.
+    pub fn from_seed(seed: u64) -> ShuffledDecks<ChaCha20Rng> {
+        ShuffledDecks::new(ChaCha20Rng::seed_from_u64(seed))
+    }
+}
+
+impl<R: RngCore + Debug + Send> DeckSource for ShuffledDecks<R> {
+    fn next_deck(&mut self) -> Deck {
+        Deck::shuffled(&mut self.rng)
+    }
+}
+
+
+/// A `DeckSource` that deals a list of decks in order, one per hand, so tests can script exact hole cards and boards.
+#[derive(Debug)]
+pub struct StackedDecks {
+    decks: VecDeque<Deck>
+}
+
+impl StackedDecks {
+    pub fn new(decks: Vec<Deck>) -> StackedDecks {
+        StackedDecks {
+            decks: decks.into()
+        }
+    }
+}
+
+impl DeckSource for StackedDecks {
+    /// # Panics
+    ///
+    /// This function will panic if every deck has been used.
+    fn next_deck(&mut self) -> Deck {
+        self.decks.pop_front().expect("Ran out of stacked decks")
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::hand::Suit;
+
+    #[test]
+    fn test_same_seed_gives_same_order() {
+        let mut first = ShuffledDecks::from_seed(42);
+        let mut second = ShuffledDecks::from_seed(42);
+
+        let deck = first.next_deck();
+        assert_eq!(deck, second.next_deck());
+        assert_eq!(deck.len(), 52);
+        assert_ne!(deck, Deck::new());
+
+        // Each hand gets a different shuffle.
+        assert_ne!(first.next_deck(), deck);
+    }
+
+    #[test]
+    fn test_stacked_deck_deals_top_cards_first() {
+        let top_cards = vec![Card::new(Suit::Spades, 14), Card::new(Suit::Hearts, 14), Card::new(Suit::Clubs, 2)];
+        let mut deck = Deck::stacked(top_cards.clone());
+
+        assert_eq!(deck.len(), 52);
+        for card in top_cards.iter() {
+            assert_eq!(deck.deal(), Some(*card));
+        }
+
+        let rest: HashSet<Card> = deck.get_cards().iter().cloned().collect();
+        assert_eq!(rest.len(), 49);
+        assert!(top_cards.iter().all(|card| !rest.contains(card)));
+    }
+
+    #[test]
+    #[should_panic(expected = "A stacked deck cannot have the same card twice")]
+    fn test_stacked_deck_rejects_duplicates() {
+        Deck::stacked(vec![Card::new(Suit::Spades, 14), Card::new(Suit::Spades, 14)]);
+    }
+}
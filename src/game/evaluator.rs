@@ -0,0 +1,336 @@
+use crate::game::hand::{Card, HandScore, HandType, Suit};
+
+/// The number of distinct card values (2 through Ace).
+const NUM_VALUES: usize = 13;
+
+/// A rank mask with a bit set for the Ace, 2, 3, 4 and 5 (the wheel straight).
+const WHEEL_MASK: u16 = 0b1_0000_0000_1111;
+
+/// Maps every 13-bit rank mask to the value of the highest straight it contains, or 0 if it contains none.
+///
+/// Bit `n` of a rank mask represents the card value `n + 2`, so bit 0 is a 2 and bit 12 is an Ace.
+/// The table is built at compile time, so looking up a straight is a single array access.
+const STRAIGHT_TABLE: [u8; 1 << NUM_VALUES] = build_straight_table();
+
+const fn build_straight_table() -> [u8; 1 << NUM_VALUES] {
+    let mut table = [0u8; 1 << NUM_VALUES];
+    let mut mask: usize = 0;
+
+    while mask < (1 << NUM_VALUES) {
+        // Look for five consecutive bits, starting from the Ace-high straight.
+        let mut top = NUM_VALUES - 1;
+        while top >= 4 {
+            let straight = 0b1_1111 << (top - 4);
+            if mask & straight == straight {
+                table[mask] = (top + 2) as u8;
+                break;
+            }
+            top -= 1;
+        }
+
+        if table[mask] == 0 && mask & WHEEL_MASK as usize == WHEEL_MASK as usize {
+            table[mask] = 5;
+        }
+
+        mask += 1;
+    }
+
+    table
+}
+
+
+/// Returns the index used for `suit` in the per-suit rank masks.
+fn suit_index(suit: Suit) -> usize {
+    match suit {
+        Suit::Hearts => 0,
+        Suit::Diamonds => 1,
+        Suit::Clubs => 2,
+        Suit::Spades => 3
+    }
+}
+
+
+/// Returns the value of the highest card in `mask`, which must not be empty.
+fn highest_value(mask: u16) -> i32 {
+    (u16::BITS - 1 - mask.leading_zeros()) as i32 + 2
+}
+
+
+/// Returns `mask` without the card `value`.
+fn without(mask: u16, value: i32) -> u16 {
+    mask & !(1 << (value - 2))
+}
+
+
+/// Builds the score of a hand from the values of its groups followed by its highest `num_kickers` values in `kickers`.
+///
+/// The ranks are gathered in a fixed-size array, as no hand has more than five of them, so the only allocation is the
+/// one owned by the returned `HandScore`.
+fn score(hand_type: HandType, values: &[i32], mut kickers: u16, num_kickers: usize) -> HandScore {
+    let mut ranks = [0i32; 5];
+    ranks[..values.len()].copy_from_slice(values);
+
+    let mut len = values.len();
+    while kickers != 0 && len < values.len() + num_kickers {
+        ranks[len] = highest_value(kickers);
+        kickers = without(kickers, ranks[len]);
+        len += 1;
+    }
+
+    HandScore::new(hand_type, ranks[..len].to_vec())
+}
+
+
+/// Evaluates the best five card poker hand that can be made from `cards`.
+///
+/// # Parameters
+///
+/// * `cards`: A slice of 5, 6 or 7 distinct `Card`s.
+///
+/// # Returns
+///
+/// * `HandScore` - The score of the best hand. Scores of different hands can be compared directly.
+///
+/// # Panics
+///
+/// This function will panic if `cards` does not contain between 5 and 7 cards.
+///
+/// # Notes
+///
+/// Rather than building every five card `Hand`, this function folds the cards into one rank mask per suit and a count
+/// per value. Flushes and straights are then found with the precomputed `STRAIGHT_TABLE`, and pairs, trips and quads
+/// with a single pass over the counts into one rank mask per group size, so the cost does not depend on the number of
+/// five card combinations. Nothing is allocated apart from the ranks of the returned `HandScore`.
+pub fn evaluate(cards: &[Card]) -> HandScore {
+    if cards.len() < 5 || cards.len() > 7 {
+        panic!("A hand can only be evaluated from 5 to 7 cards. Cards seen: {}", cards.len());
+    }
+
+    let mut suit_masks = [0u16; 4];
+    let mut counts = [0u8; NUM_VALUES];
+
+    for card in cards {
+        let bit = (card.get_value() - 2) as usize;
+        suit_masks[suit_index(card.get_suit())] |= 1 << bit;
+        counts[bit] += 1;
+    }
+
+    let rank_mask = suit_masks.iter().fold(0u16, |acc, mask| acc | mask);
+    let flush_mask = suit_masks.iter().find(|mask| mask.count_ones() >= 5).cloned();
+
+    if let Some(flush_mask) = flush_mask {
+        let straight_high = STRAIGHT_TABLE[flush_mask as usize] as i32;
+        if straight_high == 14 {
+            return score(HandType::RoyalFlush, &[straight_high], 0, 0);
+        }
+        if straight_high != 0 {
+            return score(HandType::StraightFlush, &[straight_high], 0, 0);
+        }
+    }
+
+    // One rank mask per group size, so the highest quads, trips or pair is the highest bit of its mask.
+    let mut quad_mask = 0u16;
+    let mut trip_mask = 0u16;
+    let mut pair_mask = 0u16;
+    for (bit, count) in counts.iter().enumerate() {
+        match count {
+            4 => quad_mask |= 1 << bit,
+            3 => trip_mask |= 1 << bit,
+            2 => pair_mask |= 1 << bit,
+            _ => {}
+        }
+    }
+
+    if quad_mask != 0 {
+        let quad = highest_value(quad_mask);
+        return score(HandType::FourOfAKind, &[quad], without(rank_mask, quad), 1);
+    }
+
+    if trip_mask != 0 {
+        // The pair of a full house can come from a second set of trips.
+        let trip = highest_value(trip_mask);
+        let pair_mask = without(trip_mask, trip) | pair_mask;
+        if pair_mask != 0 {
+            return score(HandType::FullHouse, &[trip, highest_value(pair_mask)], 0, 0);
+        }
+    }
+
+    if let Some(flush_mask) = flush_mask {
+        return score(HandType::Flush, &[], flush_mask, 5);
+    }
+
+    let straight_high = STRAIGHT_TABLE[rank_mask as usize] as i32;
+    if straight_high != 0 {
+        return score(HandType::Straight, &[straight_high], 0, 0);
+    }
+
+    if trip_mask != 0 {
+        let trip = highest_value(trip_mask);
+        return score(HandType::ThreeOfAKind, &[trip], without(rank_mask, trip), 2);
+    }
+
+    if pair_mask.count_ones() >= 2 {
+        let high_pair = highest_value(pair_mask);
+        let low_pair = highest_value(without(pair_mask, high_pair));
+        return score(HandType::TwoPair, &[high_pair, low_pair], without(without(rank_mask, high_pair), low_pair), 1);
+    }
+
+    if pair_mask != 0 {
+        let pair = highest_value(pair_mask);
+        return score(HandType::Pair, &[pair], without(rank_mask, pair), 3);
+    }
+
+    score(HandType::HighCard, &[], rank_mask, 5)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use combinations::Combinations;
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::game::hand::OnePlayerAllPossibleCards;
+
+    fn cards(values: &[(Suit, i32)]) -> Vec<Card> {
+        values.iter().map(|&(suit, value)| Card::new(suit, value)).collect()
+    }
+
+    #[test]
+    fn test_straight_table() {
+        assert_eq!(STRAIGHT_TABLE[0b1_1111_0000_0000], 14);
+        assert_eq!(STRAIGHT_TABLE[0b0_0000_0001_1111], 6);
+        assert_eq!(STRAIGHT_TABLE[WHEEL_MASK as usize], 5);
+        assert_eq!(STRAIGHT_TABLE[0b1_0000_0001_1111], 6);
+        assert_eq!(STRAIGHT_TABLE[0b1_1110_0000_0001], 0);
+    }
+
+    #[test]
+    fn test_evaluate_six_and_seven_cards() {
+        let six = cards(&[(Suit::Hearts, 9), (Suit::Hearts, 10), (Suit::Hearts, 11), (Suit::Hearts, 12), (Suit::Hearts, 13), (Suit::Clubs, 9)]);
+        assert_eq!(evaluate(&six).get_hand_type(), HandType::StraightFlush);
+
+        let seven = cards(&[(Suit::Hearts, 2), (Suit::Clubs, 2), (Suit::Spades, 2), (Suit::Hearts, 5), (Suit::Clubs, 5), (Suit::Spades, 5), (Suit::Diamonds, 14)]);
+        let score = evaluate(&seven);
+        assert_eq!(score.get_hand_type(), HandType::FullHouse);
+        assert_eq!(score.get_ranks(), &vec![5, 2]);
+    }
+
+    #[test]
+    fn test_evaluate_matches_seven_card_combinations() {
+        let seven_card_hands = vec![
+            cards(&[(Suit::Clubs, 4), (Suit::Spades, 11), (Suit::Clubs, 12), (Suit::Diamonds, 10), (Suit::Hearts, 8), (Suit::Spades, 9), (Suit::Diamonds, 3)]),
+            cards(&[(Suit::Clubs, 3), (Suit::Spades, 3), (Suit::Diamonds, 3), (Suit::Hearts, 6), (Suit::Spades, 6), (Suit::Diamonds, 2), (Suit::Clubs, 8)]),
+            cards(&[(Suit::Clubs, 2), (Suit::Clubs, 5), (Suit::Clubs, 7), (Suit::Clubs, 9), (Suit::Clubs, 11), (Suit::Spades, 13), (Suit::Diamonds, 4)]),
+            cards(&[(Suit::Clubs, 9), (Suit::Spades, 9), (Suit::Diamonds, 9), (Suit::Hearts, 9), (Suit::Clubs, 11), (Suit::Spades, 3), (Suit::Diamonds, 2)]),
+            cards(&[(Suit::Hearts, 14), (Suit::Clubs, 2), (Suit::Hearts, 3), (Suit::Diamonds, 4), (Suit::Spades, 5), (Suit::Spades, 13), (Suit::Diamonds, 13)]),
+            cards(&[(Suit::Hearts, 14), (Suit::Clubs, 14), (Suit::Hearts, 13), (Suit::Diamonds, 13), (Suit::Spades, 12), (Suit::Clubs, 12), (Suit::Diamonds, 2)]),
+        ];
+
+        for seven_cards in seven_card_hands {
+            let expected = Combinations::new(seven_cards.clone(), 5)
+                .map(|five_cards| reference_score(&five_cards))
+                .max()
+                .expect("Expected at least one combination");
+
+            assert_eq!(evaluate(&seven_cards), expected);
+            assert_eq!(OnePlayerAllPossibleCards::new(seven_cards).get_highest_hand_score(), expected);
+        }
+    }
+
+    /// A slow but obviously correct scorer for exactly five cards, used to check `evaluate`.
+    ///
+    /// The values are sorted by how often they appear and then by value, which is the tie-break order for every
+    /// hand type except the wheel.
+    fn reference_score(five_cards: &[Card]) -> HandScore {
+        let mut counts = HashMap::<i32, usize>::new();
+        for card in five_cards {
+            *counts.entry(card.get_value()).or_insert(0) += 1;
+        }
+
+        let mut grouped = counts.into_iter().collect::<Vec<(i32, usize)>>();
+        grouped.sort_by(|(value1, count1), (value2, count2)| count2.cmp(count1).then(value2.cmp(value1)));
+        let ranks = grouped.iter().map(|(value, _)| *value).collect::<Vec<i32>>();
+        let shape = grouped.iter().map(|(_, count)| *count).collect::<Vec<usize>>();
+
+        let is_flush = five_cards.iter().map(|card| card.get_suit()).collect::<HashSet<Suit>>().len() == 1;
+        let straight_high = match ranks.as_slice() {
+            [14, 5, 4, 3, 2] => Some(5),
+            [high, .., low] if ranks.len() == 5 && high - low == 4 => Some(*high),
+            _ => None
+        };
+
+        match (shape.as_slice(), is_flush, straight_high) {
+            (_, true, Some(14)) => HandScore::new(HandType::RoyalFlush, vec![14]),
+            (_, true, Some(high)) => HandScore::new(HandType::StraightFlush, vec![high]),
+            ([4, 1], _, _) => HandScore::new(HandType::FourOfAKind, ranks),
+            ([3, 2], _, _) => HandScore::new(HandType::FullHouse, ranks),
+            (_, true, None) => HandScore::new(HandType::Flush, ranks),
+            (_, false, Some(high)) => HandScore::new(HandType::Straight, vec![high]),
+            ([3, 1, 1], _, _) => HandScore::new(HandType::ThreeOfAKind, ranks),
+            ([2, 2, 1], _, _) => HandScore::new(HandType::TwoPair, ranks),
+            ([2, 1, 1, 1], _, _) => HandScore::new(HandType::Pair, ranks),
+            _ => HandScore::new(HandType::HighCard, ranks)
+        }
+    }
+
+    /// A random set of 6 or 7 distinct cards. Every set of 5 is checked by `test_evaluate_all_five_card_hands`.
+    fn distinct_cards() -> impl Strategy<Value = Vec<Card>> {
+        let deck = Card::new_full_deck().into_iter().collect::<Vec<Card>>();
+        (6..=7usize).prop_flat_map(move |num_cards| prop::sample::subsequence(deck.clone(), num_cards))
+            .prop_shuffle()
+    }
+
+    proptest! {
+        #[test]
+        fn test_evaluate_matches_best_five_card_score(cards in distinct_cards()) {
+            let expected = Combinations::new(cards.clone(), 5)
+                .map(|five_cards| reference_score(&five_cards))
+                .max()
+                .expect("Expected at least one combination");
+
+            prop_assert_eq!(evaluate(&cards), expected);
+        }
+    }
+
+    /// Checks every one of the 2,598,960 five card hands against `reference_score`, and that they fall into the
+    /// standard 7,462 equivalence classes with the expected number of classes per hand type.
+    ///
+    /// This is slow in debug builds, so run it with `cargo test --release -- --ignored`.
+    #[test]
+    #[ignore]
+    fn test_evaluate_all_five_card_hands() {
+        let deck = Card::new_full_deck().into_iter().collect::<Vec<Card>>();
+        let mut num_hands = 0;
+        let mut classes = HashSet::<HandScore>::new();
+
+        for five_cards in Combinations::new(deck, 5) {
+            let score = evaluate(&five_cards);
+            assert_eq!(score, reference_score(&five_cards), "Mismatch for {:?}", five_cards);
+            classes.insert(score);
+            num_hands += 1;
+        }
+
+        assert_eq!(num_hands, 2_598_960);
+        assert_eq!(classes.len(), 7_462);
+
+        let expected_classes_per_type = vec![
+            (HandType::RoyalFlush, 1),
+            (HandType::StraightFlush, 9),
+            (HandType::FourOfAKind, 156),
+            (HandType::FullHouse, 156),
+            (HandType::Flush, 1_277),
+            (HandType::Straight, 10),
+            (HandType::ThreeOfAKind, 858),
+            (HandType::TwoPair, 858),
+            (HandType::Pair, 2_860),
+            (HandType::HighCard, 1_277),
+        ];
+        for (hand_type, expected) in expected_classes_per_type {
+            let num_classes = classes.iter().filter(|score| score.get_hand_type() == hand_type).count();
+            assert_eq!(num_classes, expected, "Wrong number of classes for {}", hand_type);
+        }
+    }
+}
@@ -0,0 +1,310 @@
+use std::collections::BTreeMap;
+
+use getset::Getters;
+use rand::rngs::OsRng;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::game::deck::Deck;
+use crate::game::hand::{Card, Suit};
+use crate::game::player::PlayerId;
+
+
+/// The most bytes of entropy a player can add to a shuffle.
+pub const MAX_ENTROPY_LEN: usize = 256;
+
+
+/// A commit-reveal shuffle that lets players check that the server did not pick the order of the deck.
+///
+/// # Fields
+///
+/// * `server_seed`: The server's secret seed. It is only revealed after the hand.
+/// * `commitment`: The hex SHA-256 hash of `server_seed`, which is sent to the players before the hand.
+/// * `client_entropy`: The entropy each player added before the hand.
+///
+/// # Notes
+///
+/// The server cannot change its seed after committing to it, and it does not know the players' entropy when it
+/// commits, so it cannot choose the order of the deck. The shuffle is fully specified below, so a client in any
+/// language can repeat it:
+///
+/// 1. The shuffle key is the SHA-256 hash of the 32 byte server seed followed by, for each player in order of
+///    `PlayerId`, the player's id as 4 little-endian bytes, the length of their entropy in bytes as 4 little-endian
+///    bytes and the UTF-8 bytes of the entropy itself.
+/// 2. The random words are the ChaCha20 keystream for that key with a nonce of zero and a block counter starting at
+///    zero, as in RFC 8439, read 4 bytes at a time as little-endian `u32`s.
+/// 3. The deck starts in the order Hearts, Diamonds, Clubs, Spades, and within each suit from 2 up to the Ace, so it
+///    starts 2 of Hearts, 3 of Hearts, ..., Ace of Spades.
+/// 4. For `i` from 51 down to 1, a uniform index `j` from 0 to `i` is drawn and the cards at `i` and `j` are swapped.
+///    To draw `j`, let `n = i + 1`. Words of at least `2^32 - (2^32 mod n)` are thrown away, and `j` is the first
+///    word below that limit modulo `n`.
+/// 5. The card at index 0 is dealt first.
+#[derive(Debug, Clone)]
+pub struct FairShuffle {
+    server_seed: [u8; 32],
+    commitment: String,
+    client_entropy: BTreeMap<PlayerId, String>
+}
+
+impl FairShuffle {
+    /// Creates a shuffle with a server seed from the operating system's random number generator.
+    pub fn new() -> FairShuffle {
+        let mut server_seed = [0u8; 32];
+        OsRng.fill_bytes(&mut server_seed);
+        FairShuffle::from_server_seed(server_seed)
+    }
+
+    pub fn from_server_seed(server_seed: [u8; 32]) -> FairShuffle {
+        FairShuffle {
+            server_seed,
+            commitment: hex::encode(Sha256::digest(server_seed)),
+            client_entropy: BTreeMap::new()
+        }
+    }
+
+    /// Returns the commitment to send to the players before the hand.
+    pub fn get_commitment(&self) -> &str {
+        &self.commitment
+    }
+
+    pub fn get_client_entropy(&self) -> &BTreeMap<PlayerId, String> {
+        &self.client_entropy
+    }
+
+    /// Adds `entropy` from `player_id` to the shuffle. It replaces any entropy the player added before.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), ShuffleError>` - An error if the entropy is longer than `MAX_ENTROPY_LEN` bytes.
+    pub fn add_entropy(&mut self, player_id: PlayerId, entropy: String) -> Result<(), ShuffleError> {
+        if entropy.len() > MAX_ENTROPY_LEN {
+            return Err(ShuffleError::EntropyTooLong { maximum: MAX_ENTROPY_LEN });
+        }
+
+        self.client_entropy.insert(player_id, entropy);
+        Ok(())
+    }
+
+    /// Shuffles a full deck with the server seed and the players' entropy.
+    pub fn deck(&self) -> Deck {
+        shuffle(&self.server_seed, &self.client_entropy)
+    }
+
+    /// Reveals the server seed so the players can check the shuffle with `verify_shuffle`.
+    pub fn reveal(&self) -> ShuffleReveal {
+        ShuffleReveal {
+            server_seed: hex::encode(self.server_seed),
+            client_entropy: self.client_entropy.clone(),
+            deck: self.deck().get_cards().iter().cloned().collect()
+        }
+    }
+}
+
+impl Default for FairShuffle {
+    fn default() -> Self {
+        FairShuffle::new()
+    }
+}
+
+
+/// What the server sends the players after a provably fair hand.
+///
+/// # Fields
+///
+/// * `server_seed`: The hex server seed the server committed to before the hand.
+/// * `client_entropy`: The entropy each player added.
+/// * `deck`: The order the deck was dealt in.
+#[derive(Debug, Clone, PartialEq, Eq, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct ShuffleReveal {
+    server_seed: String,
+    client_entropy: BTreeMap<PlayerId, String>,
+    deck: Vec<Card>
+}
+
+
+/// The reasons entropy cannot be added to a shuffle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "error")]
+pub enum ShuffleError {
+    /// The game is not using provably fair shuffles.
+    NotProvablyFair,
+    /// The player is not seated at this game.
+    UnknownPlayer { player_id: PlayerId },
+    /// The entropy is longer than the server accepts.
+    EntropyTooLong { maximum: usize }
+}
+
+impl std::fmt::Display for ShuffleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ShuffleError::NotProvablyFair => write!(f, "This game does not use provably fair shuffles"),
+            ShuffleError::UnknownPlayer { player_id } => write!(f, "Player {} is not in this game", player_id),
+            ShuffleError::EntropyTooLong { maximum } => write!(f, "Entropy can be at most {} bytes", maximum)
+        }
+    }
+}
+
+impl std::error::Error for ShuffleError {}
+
+
+/// Checks that a revealed shuffle matches the commitment the server sent before the hand.
+///
+/// # Parameters
+///
+/// * `commitment`: The commitment the player received before the hand.
+/// * `reveal`: The `ShuffleReveal` the player received after the hand.
+///
+/// # Returns
+///
+/// * `bool` - Whether the server seed hashes to `commitment` and the seed and entropy shuffle the deck into the
+///   revealed order. A player should also check that their own entropy is in `reveal`.
+pub fn verify_shuffle(commitment: &str, reveal: &ShuffleReveal) -> bool {
+    let server_seed: [u8; 32] = match hex::decode(&reveal.server_seed).ok().and_then(|seed| seed.try_into().ok()) {
+        Some(server_seed) => server_seed,
+        None => return false
+    };
+
+    if hex::encode(Sha256::digest(server_seed)) != commitment.to_lowercase() {
+        return false;
+    }
+
+    shuffle(&server_seed, &reveal.client_entropy).get_cards().iter().eq(reveal.deck.iter())
+}
+
+
+fn shuffle(server_seed: &[u8; 32], client_entropy: &BTreeMap<PlayerId, String>) -> Deck {
+    let mut hasher = Sha256::new();
+    hasher.update(server_seed);
+    for (player_id, entropy) in client_entropy {
+        hasher.update(player_id.to_le_bytes());
+        hasher.update((entropy.len() as u32).to_le_bytes());
+        hasher.update(entropy.as_bytes());
+    }
+
+    let key: [u8; 32] = hasher.finalize().into();
+    let mut rng = ChaCha20Rng::from_seed(key);
+
+    let mut cards = starting_order();
+    for i in (1..cards.len()).rev() {
+        let j = uniform_index(&mut rng, i as u32 + 1);
+        cards.swap(i, j as usize);
+    }
+
+    Deck::stacked(cards)
+}
+
+
+/// Returns the order the deck is in before it is shuffled, which is step 3 of the shuffle described on `FairShuffle`.
+fn starting_order() -> Vec<Card> {
+    [Suit::Hearts, Suit::Diamonds, Suit::Clubs, Suit::Spades].into_iter()
+        .flat_map(|suit| (2..=14).map(move |value| Card::new(suit, value)))
+        .collect()
+}
+
+
+/// Draws an index from 0 to `n - 1` with the rejection sampling rule in step 4 of the shuffle described on
+/// `FairShuffle`, so that every index is equally likely.
+fn uniform_index(rng: &mut ChaCha20Rng, n: u32) -> u32 {
+    let limit = (1u64 << 32) - (1u64 << 32) % n as u64;
+    loop {
+        let word = rng.next_u32();
+        if (word as u64) < limit {
+            return word % n;
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes a deck as two characters per card, such as `Th` for the 10 of Hearts, so it can be compared with a
+    /// fixed vector.
+    fn short_deck(cards: &[Card]) -> String {
+        cards.iter()
+            .map(|card| {
+                let value = "23456789TJQKA".chars().nth(card.get_value() as usize - 2).unwrap();
+                let suit = match card.get_suit() {
+                    Suit::Hearts => 'h',
+                    Suit::Diamonds => 'd',
+                    Suit::Clubs => 'c',
+                    Suit::Spades => 's'
+                };
+                format!("{}{}", value, suit)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_random_words_are_the_rfc_8439_keystream() {
+        // The first keystream block for an all zero key and nonce, from RFC 8439 appendix A.1.
+        let mut rng = ChaCha20Rng::from_seed([0; 32]);
+        let words: Vec<u32> = (0..4).map(|_| rng.next_u32()).collect();
+        assert_eq!(words, vec![0xade0b876, 0x903df1a0, 0xe56a5d40, 0x28bd8653]);
+    }
+
+    /// The shuffled deck was worked out independently from the steps described on `FairShuffle`, so this fails if a
+    /// change to the code or to `rand` changes the order clients would get.
+    #[test]
+    fn test_shuffle_matches_fixed_vector() {
+        assert_eq!(short_deck(&starting_order()), concat!(
+            "2h3h4h5h6h7h8h9hThJhQhKhAh2d3d4d5d6d7d8d9dTdJdQdKdAd",
+            "2c3c4c5c6c7c8c9cTcJcQcKcAc2s3s4s5s6s7s8s9sTsJsQsKsAs"
+        ));
+
+        let mut fair_shuffle = FairShuffle::from_server_seed([7; 32]);
+        fair_shuffle.add_entropy(1, String::from("player one")).unwrap();
+        fair_shuffle.add_entropy(2, String::from("player two")).unwrap();
+        assert_eq!(short_deck(fair_shuffle.reveal().deck()), concat!(
+            "5h8c9s5c8sTd4dKsKd3cAs8hTc7s7cThQs2hAhQcTs6d7h4s5sJh",
+            "Jc4h9d3h2cAc2s5dKc9c6s6c7dJsAd8dQd3s3d9h2d4cKhQhJd6h"
+        ));
+    }
+
+    #[test]
+    fn test_reveal_verifies_against_commitment() {
+        let mut fair_shuffle = FairShuffle::from_server_seed([7; 32]);
+        fair_shuffle.add_entropy(1, String::from("player one")).unwrap();
+        fair_shuffle.add_entropy(2, String::from("player two")).unwrap();
+        let commitment = fair_shuffle.get_commitment().to_string();
+
+        let reveal = fair_shuffle.reveal();
+        assert_eq!(reveal.deck().len(), 52);
+        assert!(verify_shuffle(&commitment, &reveal));
+
+        // A different seed does not match the commitment.
+        assert!(!verify_shuffle(&commitment, &FairShuffle::from_server_seed([8; 32]).reveal()));
+    }
+
+    #[test]
+    fn test_tampering_fails_verification() {
+        let mut fair_shuffle = FairShuffle::from_server_seed([7; 32]);
+        fair_shuffle.add_entropy(1, String::from("player one")).unwrap();
+        let commitment = fair_shuffle.get_commitment().to_string();
+        let reveal = fair_shuffle.reveal();
+
+        let mut swapped_cards = reveal.clone();
+        swapped_cards.deck.swap(0, 1);
+        assert!(!verify_shuffle(&commitment, &swapped_cards));
+
+        let mut dropped_entropy = reveal.clone();
+        dropped_entropy.client_entropy.clear();
+        assert!(!verify_shuffle(&commitment, &dropped_entropy));
+    }
+
+    #[test]
+    fn test_entropy_changes_the_deck() {
+        let mut fair_shuffle = FairShuffle::from_server_seed([7; 32]);
+        let without_entropy = fair_shuffle.deck();
+
+        fair_shuffle.add_entropy(1, String::from("player one")).unwrap();
+        assert_ne!(fair_shuffle.deck(), without_entropy);
+
+        let too_long = "x".repeat(MAX_ENTROPY_LEN + 1);
+        assert_eq!(fair_shuffle.add_entropy(1, too_long), Err(ShuffleError::EntropyTooLong { maximum: MAX_ENTROPY_LEN }));
+    }
+}
@@ -1,12 +1,12 @@
 use std::collections::HashMap;
 use std::collections::HashSet;
-use std::iter::zip;
 
-use combinations::Combinations;
+use serde::{Deserialize, Serialize};
 
+use crate::game::evaluator;
 use crate::game::player::PlayerId;
 
-#[derive(Hash, Eq, PartialEq, Ord, PartialOrd, Clone, Debug, Copy)]
+#[derive(Hash, Eq, PartialEq, Ord, PartialOrd, Clone, Debug, Copy, Serialize, Deserialize)]
 pub enum Suit {
     Hearts,
     Diamonds,
@@ -34,7 +34,7 @@ impl std::fmt::Display for Suit {
  * Represents a playing card.
  * Ace is 14, King is 13, Queen is 12, Jack is 11, and 2-10 are their respective values.
  */
-#[derive(Hash, Eq, PartialEq, Clone, Ord, PartialOrd, Debug, Copy)]
+#[derive(Hash, Eq, PartialEq, Clone, Ord, PartialOrd, Debug, Copy, Serialize, Deserialize)]
 pub struct Card {
     suit: Suit,
     value: i32
@@ -48,6 +48,14 @@ impl Card {
         }
     }
 
+    pub fn get_suit(&self) -> Suit {
+        self.suit
+    }
+
+    pub fn get_value(&self) -> i32 {
+        self.value
+    }
+
     pub fn new_full_deck() -> HashSet<Card> {
         let mut deck = HashSet::<Card>::new();
         for suit in vec![Suit::Hearts, Suit::Diamonds, Suit::Clubs, Suit::Spades] {
@@ -81,7 +89,7 @@ impl std::fmt::Display for Card {
 /**
  * Represents a hand type in poker.
  */
-#[derive(Eq, Hash, PartialEq, Clone, Debug, Copy)]
+#[derive(Eq, Hash, PartialEq, Clone, Debug, Copy, Serialize, Deserialize)]
 pub enum HandType {
     HighCard,
     Pair,
@@ -146,19 +154,22 @@ impl PartialOrd for HandType {
 /**
  * Represents a score for a hand in poker.
  *
- * This works similar to a decimal system. Handtype takes priority over score.
- * The score is used to compare hands of the same type.
+ * A score is the `HandType` followed by the values that break ties between two hands of that type, most important
+ * first. For example a pair of kings with A-9-4 kickers has the ranks `[13, 14, 9, 4]`, and a full house of threes
+ * over sixes has the ranks `[3, 6]`. An Ace-low straight (the wheel) is ranked by its five, so it is below a six-high
+ * straight. Two hands that split the pot have equal scores.
  */
-#[derive(Eq, Hash, PartialEq)]
+#[derive(Eq, Hash, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct HandScore {
     hand_type: HandType,
-    score: i32
+    ranks: Vec<i32>
 }
 
 
 impl std::fmt::Display for HandScore {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{} with a score of {}", self.hand_type, self.score)
+        let ranks = self.ranks.iter().map(|rank| rank.to_string()).collect::<Vec<String>>().join(", ");
+        write!(f, "{} ({})", self.hand_type, ranks)
     }
 }
 
@@ -167,15 +178,12 @@ impl std::fmt::Display for HandScore {
  * Implements the `Ord` trait for the `HandScore` struct.
  *
  * This allows instances of the `HandScore` struct to be compared using the `<`, `>`, `<=`, and `>=` operators.
+ * The hand type is compared first, then the ranks in order.
  */
 impl Ord for HandScore {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        let c = self.hand_type.cmp(&other.hand_type);
-        if c == std::cmp::Ordering::Equal {
-            self.score.cmp(&other.score)
-        } else {
-            c
-        }
+        self.hand_type.cmp(&other.hand_type)
+            .then_with(|| self.ranks.cmp(&other.ranks))
     }
 }
 
@@ -187,12 +195,20 @@ impl PartialOrd for HandScore {
 }
 
 impl HandScore {
+    pub(crate) fn new(hand_type: HandType, ranks: Vec<i32>) -> HandScore {
+        HandScore {
+            hand_type,
+            ranks
+        }
+    }
+
     pub fn get_hand_type(&self) -> HandType {
         self.hand_type
     }
 
-    pub fn get_score(&self) -> i32 {
-        self.score
+    /// Returns the values used to break ties between hands of the same type, most important first.
+    pub fn get_ranks(&self) -> &Vec<i32> {
+        &self.ranks
     }
 }
 
@@ -232,368 +248,9 @@ impl Hand {
         &self.cards
     }
 
-    /** Checks
-      * hello
-     */
-    pub fn check_high_card(&self) -> Option<(HashSet<Vec<Card>>, i32, Vec<Card>)> {
-        let cards = self.cards.iter().cloned().collect::<Vec<Card>>();
-        let highest_card = cards.iter().max_by(|card1, card2| card1.value.cmp(&card2.value)).unwrap();
-        let transformed_set: HashSet<Vec<Card>> = self.cards.iter().map(|card| vec![card.clone()]).collect();
-
-        Some((transformed_set, highest_card.value, vec![highest_card.clone()]))
-    }
-
-    pub fn check_pair(&self) -> Option<(HashSet::<Vec<Card>>, i32, Vec<Card>)> {
-        let all_pairs = Combinations::new(self.cards.iter().cloned().collect(), 2);
-        let mut pairs = HashSet::<Vec<Card>>::new();
-        for pair in all_pairs {
-            if pair[0].value == pair[1].value {
-                pairs.insert(pair);
-            }
-        }
-
-        let highest_pair: (i32, Vec<Card>) = pairs.iter().map(|pair| (pair[0].value, pair.clone()))
-            .max_by(|(value1, _pair1), (value2, _pair2)| value1.cmp(value2))?;
-
-        match pairs.len() {
-            0 => None,
-            _ => Some((pairs, highest_pair.0, highest_pair.1))
-        }
-    }
-
-    pub fn check_two_pair(&self) -> Option<(HashSet::<Vec<Card>>, i32, Vec<Card>)> {
-        let all_combinations = Combinations::new(self.cards.iter().cloned().collect(), 2);
-        let all_combinations_vec = all_combinations.collect::<Vec<Vec<Card>>>();
-        let mut two_pairs = HashSet::<Vec<Card>>::new();
-        for combination in all_combinations_vec.iter() {
-            if combination[0].value == combination[1].value {
-                for combination2 in all_combinations_vec.clone() {
-                    if combination2[0].value == combination2[1].value && combination2[0].value != combination[0].value {
-                        let mut two_pair = Vec::<Card>::new();
-                        two_pair.extend(combination.clone());
-                        two_pair.extend(combination2);
-                        two_pairs.insert(two_pair);
-                    }
-                }
-            }
-        }
-
-        match two_pairs.len() {
-            0 => None,
-            _ => {
-                let highest_two_pair = two_pairs.iter().max_by(|pair1, pair2| {
-                    let pair1_values = pair1.iter().map(|card| card.value).collect::<Vec<i32>>();
-                    let pair2_values = pair2.iter().map(|card| card.value).collect::<Vec<i32>>();
-                    pair1_values.iter().max().unwrap().cmp(pair2_values.iter().max().unwrap())
-                }).unwrap();
-
-                let highest_value: i32 = highest_two_pair.iter().map(|card| card.value).sum();
-
-
-                Some((two_pairs.clone(), highest_value, highest_two_pair.clone()))
-            }
-        }
-    }
-
-
-    pub fn check_three_of_a_kind(&self) -> Option<(HashSet::<Vec<Card>>, i32, Vec<Card>)> {
-        let all_combinations = Combinations::new(self.cards.iter().cloned().collect(), 3);
-        let mut three_of_a_kinds = HashSet::<Vec<Card>>::new();
-        for combination in all_combinations {
-            if combination[0].value == combination[1].value && combination[1].value == combination[2].value {
-                three_of_a_kinds.insert(combination);
-            }
-        }
-
-        match three_of_a_kinds.len() {
-            0 => None,
-            _ => {
-                let highest_three_of_a_kind = three_of_a_kinds.iter().max_by(|pair1, pair2| {
-                    let pair1_values = pair1.iter().map(|card| card.value).collect::<Vec<i32>>();
-                    let pair2_values = pair2.iter().map(|card| card.value).collect::<Vec<i32>>();
-                    pair1_values.iter().max().unwrap().cmp(pair2_values.iter().max().unwrap())
-                }).unwrap();
-
-                let highest_value: i32 = highest_three_of_a_kind.iter().map(|card| card.value).sum();
-
-                Some((three_of_a_kinds.clone(), highest_value, highest_three_of_a_kind.clone()))
-            }
-        }
-    }
-
-
-    pub fn check_four_of_a_kind(&self) -> Option<(HashSet::<Vec<Card>>, i32, Vec<Card>)> {
-        let all_combinations = Combinations::new(self.cards.iter().cloned().collect(), 4);
-        let four_of_a_kinds: HashSet<Vec<Card>> = all_combinations.into_iter()
-            .filter(|combination| {
-                combination[0].value == combination[1].value &&
-                    combination[1].value == combination[2].value &&
-                    combination[2].value == combination[3].value
-            })
-            .collect::<HashSet<Vec<Card>>>();
-
-        if four_of_a_kinds.len() == 0 {
-            return None
-        }
-
-        let highest_four_of_a_kind: &Vec<Card> = four_of_a_kinds.iter()
-            // .map(|vec| *vec)
-            .max_by_key(|vector| vector.get(0).expect("Error with indexing 0").value)
-            .expect("Error calculating max");
-
-        let highest_four_of_a_kind_value = highest_four_of_a_kind.iter().map(|card| card.value).sum();
-
-        Some((four_of_a_kinds.clone(), highest_four_of_a_kind_value, (*highest_four_of_a_kind).clone()))
-
-        // let mut four_of_a_kinds = HashSet::<Vec<Card>>::new();
-        // for combination in all_combinations {
-        //   if combination[0].value == combination[1].value && combination[1].value == combination[2].value && combination[2].value == combination[3].value {
-        //     four_of_a_kinds.insert(combination);
-        //   }
-        // }
-        //
-        // match four_of_a_kinds.len() {
-        //   0 => None,
-        //   _ => {
-        //     let highest_four_of_a_kind = four_of_a_kinds.iter().max_by(|pair1, pair2| {
-        //       let pair1_values = pair1.iter().map(|card| card.value).collect::<Vec<i32>>();
-        //       let pair2_values = pair2.iter().map(|card| card.value).collect::<Vec<i32>>();
-        //       pair1_values.iter().max().unwrap().cmp(pair2_values.iter().max().unwrap())
-        //     }).unwrap();
-        //
-        //     let highest_value = highest_four_of_a_kind.iter().map(|card| card.value).sum();
-        //
-        //     Some((four_of_a_kinds.clone(), highest_value, highest_four_of_a_kind.clone()))
-        //   }
-        // }
-    }
-
-
-    pub fn check_full_house(&self) -> Option<(HashSet::<Vec<Card>>, i32, Vec<Card>)> {
-        let all_triplets = Combinations::new(self.cards.iter().cloned().collect(), 3);
-        let mut full_houses = HashSet::<Vec<Card>>::new();
-
-        for triplet in all_triplets {
-            let pair = (&self.cards.clone() - &HashSet::<Card>::from_iter(triplet.clone())).iter().cloned().collect::<Vec<Card>>();
-            if triplet[0].value == triplet[1].value && triplet[1].value == triplet[2].value {
-                if pair[0].value == pair[1].value {
-                    let mut full_house = Vec::<Card>::new();
-                    full_house.extend(triplet);
-                    full_house.extend(pair);
-                    full_houses.insert(full_house);
-                }
-            }
-        }
-
-        match full_houses.len() {
-            0 => None,
-            _ => {
-                let highest_full_house = full_houses.iter().max_by(|pair1, pair2| {
-                    let pair1_values = pair1.iter().map(|card| card.value).collect::<Vec<i32>>();
-                    let pair2_values = pair2.iter().map(|card| card.value).collect::<Vec<i32>>();
-                    pair1_values.iter().max().unwrap().cmp(pair2_values.iter().max().unwrap())
-                }).unwrap();
-
-                let highest_value = highest_full_house.iter().map(|card| card.value).sum();
-
-                Some((full_houses.clone(), highest_value, highest_full_house.clone()))
-            }
-        }
-    }
-
-
-    pub fn check_flush(&self) -> bool {
-        let mut suits = HashSet::<Suit>::new();
-        for card in &self.cards {
-            suits.insert(card.suit.clone());
-        }
-
-        suits.len() == 1
-    }
-
-
-    pub fn check_royal(&self) -> bool {
-        let mut values = Vec::<i32>::new();
-        for card in &self.cards {
-            values.push(card.value);
-        }
-        values.sort();
-        values == vec![10, 11, 12, 13, 14]
-    }
-
-
-    pub fn check_straight(&self) -> bool {
-        let mut values = Vec::<i32>::new();
-        for card in &self.cards {
-            values.push(card.value);
-        }
-        values.sort();
-        // check for ace, 1, 2, 3, 4
-        if values == vec![2, 3, 4, 5, 14] {
-            return true;
-        }
-        for i in 0..values.len() - 1 {
-            if values[i + 1] - values[i] != 1 {
-                return false;
-            }
-        }
-
-        true
-    }
-
-
-    pub fn check_straight_flush(&self) -> bool {
-        self.check_straight() && self.check_flush()
-    }
-
-
-    pub fn check_royal_flush(&self) -> bool {
-        self.check_royal() && self.check_flush()
-    }
-
-
-    fn calculate_score_for_boolean(&self) -> i32 {
-        let mut score = 0;
-        let values = self.cards.iter().map(|card| card.value);
-        let mut values = values.collect::<Vec<i32>>();
-        values.sort();
-        for (i, value) in values.iter().enumerate() {
-            score += value * (i + 1) as i32 * 14  as i32;
-        }
-        score
-    }
-
-
-    fn calculate_score_for_straight(&self) -> i32 {
-        let mut score = 0;
-        let values = self.cards.iter().map(|card| card.value);
-        let mut values = values.collect::<Vec<i32>>();
-        values.sort();
-        if values == vec![2, 3, 4, 5, 14] {
-            values = vec![1, 2, 3, 4, 5];
-        }
-        for (i, value) in values.iter().enumerate() {
-            score += value * (i + 1) as i32 * 14  as i32;
-        }
-        score
-    }
-
-
-    fn calculate_score_for_full_house(&self) -> i32 {
-        let mut score = 0;
-        let values_map = self.cards.iter().map(|card| card.value).fold(HashMap::<i32, i32>::new(), |mut acc, value| {
-            *acc.entry(value).or_insert(0) += 1;
-            acc
-        });
-
-        for (key, value) in values_map {
-            if value == 3 {
-                score += key * 14;
-            } else {
-                score += key;
-            }
-        }
-
-        score
-    }
-
-
+    /// Returns the score of this hand.
     pub fn check_hand(&self) -> HandScore {
-        let is_flush = self.check_flush();
-        let is_royal = self.check_royal();
-        let is_straight = self.check_straight();
-
-        let high_card_output = self.check_high_card();
-        let pairs_output= self.check_pair();
-        let two_pairs_output = self.check_two_pair();
-        let triplets_output = self.check_three_of_a_kind();
-        let four_of_a_kind_output = self.check_four_of_a_kind();
-        let full_houses_output = self.check_full_house();
-
-        let outputs = vec![four_of_a_kind_output, full_houses_output, triplets_output, two_pairs_output, pairs_output, high_card_output];
-        let hand_types_ranked = vec![HandType::FourOfAKind, HandType::FullHouse, HandType::ThreeOfAKind, HandType::TwoPair, HandType::Pair, HandType::HighCard];
-        let hand_type_multiples = HashMap::<HandType, i32>::from_iter(vec![
-            (HandType::HighCard, 1),
-            (HandType::Pair, 6188),
-            (HandType::TwoPair, 3848),
-            (HandType::ThreeOfAKind, 624),
-            (HandType::Straight, 1976),
-            (HandType::Flush, 9880),
-            (HandType::FullHouse, 9828),
-            (HandType::FourOfAKind, 10764),
-            (HandType::StraightFlush, 676),
-            (HandType::RoyalFlush, 9100)
-        ]);
-
-        let mut output_hand_type: HandType= HandType::HighCard;
-        let mut score: i32 = 0;
-
-        match is_flush {
-            true => {
-                match is_royal {
-                    true => {
-                        score = self.calculate_score_for_boolean();
-                        output_hand_type = HandType::RoyalFlush;
-                    },
-                    false => {
-                        match is_straight {
-                            true => {
-                                score = self.calculate_score_for_straight();
-                                output_hand_type = HandType::StraightFlush;
-                            },
-                            false => {
-                                score = self.calculate_score_for_boolean();
-                                output_hand_type = HandType::Flush;
-                            }
-                        }
-                    }
-                }
-            },
-            false => {
-                match is_straight {
-                    true => {
-                        score = self.calculate_score_for_boolean();
-                        output_hand_type = HandType::Straight;
-                    }
-                    false => {
-                        for (output, hand_type) in zip(outputs, hand_types_ranked) {
-                            match output {
-                                Some((_, highest_value, highest_hand)) => {
-                                    let remaining_cards = &self.cards - &HashSet::<Card>::from_iter(highest_hand.clone());
-                                    let remaining_values = remaining_cards.iter().map(|card| card.value);
-                                    let mut remaining_values = remaining_values.collect::<Vec<i32>>();
-                                    remaining_values.sort();
-
-                                    if hand_type == HandType::FullHouse {
-                                        score = self.calculate_score_for_full_house();
-                                        output_hand_type = hand_type;
-                                        break;
-                                    }
-
-                                    let mut sum_of_remaining = 0;
-
-                                    for (i, value) in remaining_values.iter().enumerate() {
-                                        sum_of_remaining += value * (i + 1) as i32 * 14  as i32;
-                                    }
-
-                                    output_hand_type = hand_type.clone();
-                                    score = sum_of_remaining + highest_value * hand_type_multiples[&hand_type];
-                                    break;
-                                },
-                                None => {
-                                    continue;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
-        HandScore {
-            hand_type: output_hand_type,
-            score
-        }
+        evaluator::evaluate(&self.cards.iter().cloned().collect::<Vec<Card>>())
     }
 }
 
@@ -636,29 +293,49 @@ impl OnePlayerAllPossibleCards {
         &self.cards
     }
 
+    /// Returns the score of the best five card hand that can be made from these cards.
+    ///
+    /// This uses the lookup table based `evaluator` instead of scoring every five card `Hand`.
     pub fn get_highest_hand_score(&self) -> HandScore {
-        let all_combinations = Combinations::new(self.cards.clone(), 5);
-        let mut highest_hand_score = HandScore {
-            hand_type: HandType::HighCard,
-            score: 0
-        };
-        for combination in all_combinations {
-            let hand = Hand::new(HashSet::<Card>::from_iter(combination.clone()));
-            let hand_score = hand.check_hand();
-            if hand_score > highest_hand_score {
-                highest_hand_score = hand_score;
+        evaluator::evaluate(&self.cards)
+    }
+
+    /// Groups the players by the strength of their best hand.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<PlayerId>>` - The groups from best hand to worst. Players in the same group have hands of equal value
+    ///   and are sorted by `PlayerId`.
+    pub fn rank_players(hands: &HashMap<PlayerId, OnePlayerAllPossibleCards>) -> Vec<Vec<PlayerId>> {
+        let mut scores: Vec<(HandScore, PlayerId)> = hands.iter()
+            .map(|(&player_id, seven_cards)| (seven_cards.get_highest_hand_score(), player_id))
+            .collect();
+        scores.sort_by(|(score1, player1), (score2, player2)| score2.cmp(score1).then(player1.cmp(player2)));
+
+        let mut rankings = Vec::<Vec<PlayerId>>::new();
+        let mut prev_score: Option<HandScore> = None;
+
+        for (score, player_id) in scores {
+            match (&prev_score, rankings.last_mut()) {
+                (Some(prev), Some(group)) if *prev == score => group.push(player_id),
+                _ => rankings.push(vec![player_id])
             }
+            prev_score = Some(score);
         }
 
-        highest_hand_score
+        rankings
     }
 
-    pub fn get_winner(hands: &HashMap<PlayerId, OnePlayerAllPossibleCards>) -> PlayerId {
-
-        *hands.iter()
-            .max_by_key(|&(_, ref seven_cards)| seven_cards.get_highest_hand_score())
-            .unwrap()
-            .0
+    /// Returns every player tied for the best hand, sorted by `PlayerId`.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `hands` is empty.
+    pub fn get_winners(hands: &HashMap<PlayerId, OnePlayerAllPossibleCards>) -> Vec<PlayerId> {
+        Self::rank_players(hands)
+            .into_iter()
+            .next()
+            .expect("Expected at least one hand to compare")
     }
 }
 
@@ -907,8 +584,141 @@ mod tests {
         assert!(two_pair > pair);
         assert!(pair > high_card);
     }
-}
 
+    /// Scores a hand written as `(suit, value)` pairs.
+    fn score(cards: &[(Suit, i32)]) -> HandScore {
+        let cards = cards.iter().map(|&(suit, value)| Card::new(suit, value)).collect::<Vec<Card>>();
+        evaluator::evaluate(&cards)
+    }
+
+    #[test]
+    fn test_compare_high_card_kickers() {
+        let ace_king_nine = score(&[(Suit::Hearts, 14), (Suit::Clubs, 13), (Suit::Spades, 9), (Suit::Diamonds, 5), (Suit::Hearts, 3)]);
+        let ace_king_eight = score(&[(Suit::Clubs, 14), (Suit::Hearts, 13), (Suit::Diamonds, 8), (Suit::Spades, 7), (Suit::Clubs, 6)]);
+        let ace_king_nine_four = score(&[(Suit::Spades, 14), (Suit::Diamonds, 13), (Suit::Clubs, 9), (Suit::Hearts, 5), (Suit::Diamonds, 4)]);
+
+        assert!(ace_king_nine > ace_king_eight);
+        assert!(ace_king_nine_four > ace_king_nine);
+        assert_eq!(ace_king_nine.get_ranks(), &vec![14, 13, 9, 5, 3]);
+    }
+
+    #[test]
+    fn test_compare_pair_kickers() {
+        let kings_ace_kicker = score(&[(Suit::Hearts, 13), (Suit::Clubs, 13), (Suit::Spades, 14), (Suit::Diamonds, 5), (Suit::Hearts, 3)]);
+        let kings_queen_kicker = score(&[(Suit::Spades, 13), (Suit::Diamonds, 13), (Suit::Clubs, 12), (Suit::Hearts, 11), (Suit::Clubs, 10)]);
+        let queens_ace_kicker = score(&[(Suit::Spades, 12), (Suit::Diamonds, 12), (Suit::Clubs, 14), (Suit::Hearts, 13), (Suit::Clubs, 11)]);
+
+        assert!(kings_ace_kicker > kings_queen_kicker);
+        assert!(kings_queen_kicker > queens_ace_kicker);
+        assert_eq!(kings_ace_kicker.get_ranks(), &vec![13, 14, 5, 3]);
+    }
+
+    #[test]
+    fn test_compare_two_pair_kickers() {
+        let aces_and_twos = score(&[(Suit::Hearts, 14), (Suit::Clubs, 14), (Suit::Spades, 2), (Suit::Diamonds, 2), (Suit::Hearts, 3)]);
+        let kings_and_queens = score(&[(Suit::Spades, 13), (Suit::Diamonds, 13), (Suit::Clubs, 12), (Suit::Hearts, 12), (Suit::Clubs, 14)]);
+        let aces_and_twos_king = score(&[(Suit::Spades, 14), (Suit::Diamonds, 14), (Suit::Clubs, 2), (Suit::Hearts, 2), (Suit::Clubs, 13)]);
+
+        assert!(aces_and_twos > kings_and_queens);
+        assert!(aces_and_twos_king > aces_and_twos);
+        assert_eq!(aces_and_twos.get_ranks(), &vec![14, 2, 3]);
+    }
+
+    #[test]
+    fn test_compare_three_of_a_kind_and_full_house() {
+        let threes_ace_king = score(&[(Suit::Hearts, 3), (Suit::Clubs, 3), (Suit::Spades, 3), (Suit::Diamonds, 14), (Suit::Hearts, 13)]);
+        let threes_ace_queen = score(&[(Suit::Hearts, 3), (Suit::Clubs, 3), (Suit::Diamonds, 3), (Suit::Spades, 14), (Suit::Hearts, 12)]);
+        assert!(threes_ace_king > threes_ace_queen);
+
+        let threes_full_of_aces = score(&[(Suit::Hearts, 3), (Suit::Clubs, 3), (Suit::Spades, 3), (Suit::Diamonds, 14), (Suit::Hearts, 14)]);
+        let fours_full_of_twos = score(&[(Suit::Hearts, 4), (Suit::Clubs, 4), (Suit::Spades, 4), (Suit::Diamonds, 2), (Suit::Hearts, 2)]);
+        let fours_full_of_threes = score(&[(Suit::Hearts, 4), (Suit::Clubs, 4), (Suit::Spades, 4), (Suit::Diamonds, 3), (Suit::Clubs, 3)]);
+
+        assert!(fours_full_of_twos > threes_full_of_aces);
+        assert!(fours_full_of_threes > fours_full_of_twos);
+    }
+
+    #[test]
+    fn test_compare_four_of_a_kind_kickers() {
+        let nines_king_kicker = score(&[(Suit::Hearts, 9), (Suit::Clubs, 9), (Suit::Spades, 9), (Suit::Diamonds, 9), (Suit::Hearts, 13)]);
+        let nines_two_kicker = score(&[(Suit::Hearts, 9), (Suit::Clubs, 9), (Suit::Spades, 9), (Suit::Diamonds, 9), (Suit::Hearts, 2)]);
+        let tens_two_kicker = score(&[(Suit::Hearts, 10), (Suit::Clubs, 10), (Suit::Spades, 10), (Suit::Diamonds, 10), (Suit::Hearts, 2)]);
+
+        assert!(nines_king_kicker > nines_two_kicker);
+        assert!(tens_two_kicker > nines_king_kicker);
+    }
+
+    #[test]
+    fn test_compare_flushes() {
+        let ace_high_flush = score(&[(Suit::Hearts, 14), (Suit::Hearts, 9), (Suit::Hearts, 7), (Suit::Hearts, 4), (Suit::Hearts, 2)]);
+        let ace_high_flush_better_kicker = score(&[(Suit::Clubs, 14), (Suit::Clubs, 9), (Suit::Clubs, 7), (Suit::Clubs, 5), (Suit::Clubs, 2)]);
+        let king_high_flush = score(&[(Suit::Spades, 13), (Suit::Spades, 12), (Suit::Spades, 11), (Suit::Spades, 9), (Suit::Spades, 8)]);
+
+        assert!(ace_high_flush_better_kicker > ace_high_flush);
+        assert!(ace_high_flush > king_high_flush);
+    }
+
+    #[test]
+    fn test_compare_straights_and_the_wheel() {
+        let wheel = score(&[(Suit::Hearts, 14), (Suit::Clubs, 2), (Suit::Spades, 3), (Suit::Diamonds, 4), (Suit::Hearts, 5)]);
+        let six_high = score(&[(Suit::Hearts, 6), (Suit::Clubs, 2), (Suit::Spades, 3), (Suit::Diamonds, 4), (Suit::Hearts, 5)]);
+        let broadway = score(&[(Suit::Hearts, 14), (Suit::Clubs, 13), (Suit::Spades, 12), (Suit::Diamonds, 11), (Suit::Hearts, 10)]);
+        let three_of_a_kind = score(&[(Suit::Hearts, 14), (Suit::Clubs, 14), (Suit::Spades, 14), (Suit::Diamonds, 13), (Suit::Hearts, 12)]);
+
+        assert_eq!(wheel.get_hand_type(), HandType::Straight);
+        assert_eq!(wheel.get_ranks(), &vec![5]);
+        assert!(six_high > wheel);
+        assert!(broadway > six_high);
+        assert!(wheel > three_of_a_kind);
+
+        let steel_wheel = score(&[(Suit::Clubs, 14), (Suit::Clubs, 2), (Suit::Clubs, 3), (Suit::Clubs, 4), (Suit::Clubs, 5)]);
+        let six_high_straight_flush = score(&[(Suit::Clubs, 6), (Suit::Clubs, 2), (Suit::Clubs, 3), (Suit::Clubs, 4), (Suit::Clubs, 5)]);
+        let royal_flush = score(&[(Suit::Clubs, 14), (Suit::Clubs, 13), (Suit::Clubs, 12), (Suit::Clubs, 11), (Suit::Clubs, 10)]);
+
+        assert_eq!(steel_wheel.get_hand_type(), HandType::StraightFlush);
+        assert!(six_high_straight_flush > steel_wheel);
+        assert!(royal_flush > six_high_straight_flush);
+    }
+
+    #[test]
+    fn test_equal_hands_compare_equal() {
+        let pair_hearts = score(&[(Suit::Hearts, 8), (Suit::Clubs, 8), (Suit::Spades, 14), (Suit::Diamonds, 5), (Suit::Hearts, 3)]);
+        let pair_spades = score(&[(Suit::Spades, 8), (Suit::Diamonds, 8), (Suit::Clubs, 14), (Suit::Hearts, 5), (Suit::Spades, 3)]);
+        assert_eq!(pair_hearts.cmp(&pair_spades), std::cmp::Ordering::Equal);
 
+        let straight_1 = score(&[(Suit::Hearts, 9), (Suit::Clubs, 10), (Suit::Spades, 11), (Suit::Diamonds, 12), (Suit::Hearts, 13)]);
+        let straight_2 = score(&[(Suit::Spades, 9), (Suit::Diamonds, 10), (Suit::Clubs, 11), (Suit::Hearts, 12), (Suit::Spades, 13)]);
+        assert_eq!(straight_1, straight_2);
 
+        // The sixth and seventh cards must not break a tie when they do not play.
+        let board = [(Suit::Hearts, 14), (Suit::Hearts, 13), (Suit::Hearts, 12), (Suit::Hearts, 11), (Suit::Hearts, 10)];
+        let mut with_deuces = board.to_vec();
+        with_deuces.extend([(Suit::Clubs, 2), (Suit::Diamonds, 2)]);
+        let mut with_nines = board.to_vec();
+        with_nines.extend([(Suit::Clubs, 9), (Suit::Diamonds, 9)]);
+        assert_eq!(score(&with_deuces), score(&with_nines));
+    }
 
+    #[test]
+    fn test_get_winners_returns_all_tied_players() {
+        let board = [
+            Card::new(Suit::Hearts, 14),
+            Card::new(Suit::Clubs, 13),
+            Card::new(Suit::Spades, 12),
+            Card::new(Suit::Diamonds, 11),
+            Card::new(Suit::Hearts, 2),
+        ];
+        let with_hole_cards = |hole_cards: Vec<Card>| {
+            OnePlayerAllPossibleCards::new(board.iter().cloned().chain(hole_cards).collect())
+        };
+
+        let mut hands = HashMap::<PlayerId, OnePlayerAllPossibleCards>::new();
+        hands.insert(3, with_hole_cards(vec![Card::new(Suit::Clubs, 10), Card::new(Suit::Diamonds, 3)]));
+        hands.insert(1, with_hole_cards(vec![Card::new(Suit::Spades, 10), Card::new(Suit::Hearts, 5)]));
+        hands.insert(2, with_hole_cards(vec![Card::new(Suit::Diamonds, 14), Card::new(Suit::Clubs, 14)]));
+        assert_eq!(OnePlayerAllPossibleCards::get_winners(&hands), vec![1, 3]);
+
+        hands.remove(&1);
+        assert_eq!(OnePlayerAllPossibleCards::get_winners(&hands), vec![3]);
+    }
+}
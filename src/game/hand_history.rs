@@ -0,0 +1,426 @@
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use getset::Getters;
+use serde::{Deserialize, Serialize};
+
+use crate::game::OddChipRule;
+use crate::game::betting::BettingStructure;
+use crate::game::blinds::BlindStructure;
+use crate::game::deck::Deck;
+use crate::game::hand::{Card, HandScore};
+use crate::game::hand_state::Street;
+use crate::game::player::PlayerId;
+use crate::game::showdown::ShowdownResult;
+
+pub mod pokerstars;
+
+
+/// A record of everything that happened in one hand.
+///
+/// # Fields
+///
+/// * `game_id`: The game the hand was played in.
+/// * `hand_number`: The number of the hand in the game, starting from 1.
+/// * `start_time`: When the hand started, in seconds since the Unix epoch.
+/// * `num_seats`: The number of seats at the table.
+/// * `blind_structure`: The blinds, antes and straddle of the hand.
+/// * `betting_structure`: The betting structure of the hand.
+/// * `odd_chip_rule`: Who received the odd chips of split pots.
+/// * `button`: The seat with the dealer button.
+/// * `seats`: The players dealt in, in the order they act after the flop.
+/// * `actions`: Every blind, ante and action, in the order they happened.
+/// * `board`: The community cards. If the board was run more than once, this is the first run's board.
+/// * `pots`: The main pot and side pots at the end of the hand, and who won them.
+/// * `runs`: Each board and what it won when the board was run more than once. It is empty if the board was run once.
+/// * `showdown`: Who showed and who mucked at showdown, or `None` if everyone else folded.
+#[derive(Debug, Clone, PartialEq, Eq, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct HandHistory {
+    game_id: u128,
+    hand_number: u64,
+    start_time: u64,
+    num_seats: usize,
+    blind_structure: BlindStructure,
+    betting_structure: BettingStructure,
+    odd_chip_rule: OddChipRule,
+    button: usize,
+    seats: Vec<SeatRecord>,
+    actions: Vec<ActionRecord>,
+    board: Vec<Card>,
+    pots: Vec<PotRecord>,
+    // Hands recorded before the board could be run more than once have no runs.
+    #[serde(default)]
+    runs: Vec<RunRecord>,
+    showdown: Option<ShowdownResult>
+}
+
+impl HandHistory {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(game_id: u128, hand_number: u64, num_seats: usize, blind_structure: BlindStructure,
+                      betting_structure: BettingStructure, odd_chip_rule: OddChipRule, button: usize,
+                      seats: Vec<SeatRecord>) -> HandHistory {
+        HandHistory {
+            game_id,
+            hand_number,
+            start_time: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs()),
+            num_seats,
+            blind_structure,
+            betting_structure,
+            odd_chip_rule,
+            button,
+            seats,
+            actions: Vec::new(),
+            board: Vec::new(),
+            pots: Vec::new(),
+            runs: Vec::new(),
+            showdown: None
+        }
+    }
+
+    /// Returns the chips each player won from all the pots.
+    pub fn get_payouts(&self) -> BTreeMap<PlayerId, i32> {
+        let mut payouts = BTreeMap::<PlayerId, i32>::new();
+        for (player_id, amount) in self.pots.iter().flat_map(|pot| pot.payouts.iter()) {
+            *payouts.entry(*player_id).or_insert(0) += amount;
+        }
+
+        payouts
+    }
+
+    /// Returns the deck the hand was dealt from. It deals each player's hole cards in the order of `seats`, then the
+    /// board, then the rest of each later run's board, then the cards nobody saw in the order of `Deck::new`. `Game`
+    /// does not burn cards, so a hand dealt from this deck gets the same cards.
+    pub fn get_deck(&self) -> Deck {
+        let run_cards = self.runs.iter()
+            .skip(1)
+            .flat_map(|run| run.board.iter().filter(|card| !self.board.contains(card)));
+        let dealt_cards = self.seats.iter()
+            .flat_map(|seat| seat.hole_cards.iter())
+            .chain(self.board.iter())
+            .chain(run_cards)
+            .cloned()
+            .collect();
+
+        Deck::stacked(dealt_cards)
+    }
+
+    pub(crate) fn set_start_time(&mut self, start_time: u64) {
+        self.start_time = start_time;
+    }
+
+    pub(crate) fn record_action(&mut self, action: ActionRecord) {
+        self.actions.push(action);
+    }
+
+    /// Records how the hand ended.
+    pub(crate) fn finish(&mut self, board: Vec<Card>, pots: Vec<PotRecord>, runs: Vec<RunRecord>, showdown: Option<ShowdownResult>) {
+        self.board = board;
+        self.pots = pots;
+        self.runs = runs;
+        self.showdown = showdown;
+    }
+}
+
+
+/// A player dealt into a hand.
+///
+/// # Fields
+///
+/// * `seat`: The seat the player sat in.
+/// * `player_id`: The player.
+/// * `name`: The player's name.
+/// * `stack`: The chips the player had before the blinds and antes.
+/// * `hole_cards`: The player's hole cards.
+#[derive(Debug, Clone, PartialEq, Eq, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct SeatRecord {
+    seat: usize,
+    player_id: PlayerId,
+    name: String,
+    stack: i32,
+    hole_cards: Vec<Card>
+}
+
+impl SeatRecord {
+    pub(crate) fn new(seat: usize, player_id: PlayerId, name: String, stack: i32, hole_cards: Vec<Card>) -> SeatRecord {
+        SeatRecord {
+            seat,
+            player_id,
+            name,
+            stack,
+            hole_cards
+        }
+    }
+}
+
+
+/// What a player did in an `ActionRecord`. Bet and raise amounts are the total the player bet on the street, like
+/// `Action`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", content = "amount")]
+pub enum ActionKind {
+    SmallBlind,
+    BigBlind,
+    Straddle,
+    /// A small blind a returning player owed, posted as dead money.
+    DeadSmallBlind,
+    Ante,
+    BigBlindAnte,
+    Fold,
+    Check,
+    Call,
+    Bet(i32),
+    RaiseTo(i32),
+    /// The part of a bet that nobody called, given back to the player who made it once the betting on the street was
+    /// over. Its amount is taken back out of the pot.
+    UncalledBetReturned
+}
+
+
+/// One blind, ante or action in a hand.
+///
+/// # Fields
+///
+/// * `street`: The street the action was made on.
+/// * `player_id`: The player who made it.
+/// * `kind`: What the player did.
+/// * `amount`: The chips the action put in the pot, or took out of it for `ActionKind::UncalledBetReturned`.
+/// * `is_all_in`: Whether the action put the player all in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct ActionRecord {
+    street: Street,
+    player_id: PlayerId,
+    kind: ActionKind,
+    amount: i32,
+    is_all_in: bool
+}
+
+impl ActionRecord {
+    pub(crate) fn new(street: Street, player_id: PlayerId, kind: ActionKind, amount: i32, is_all_in: bool) -> ActionRecord {
+        ActionRecord {
+            street,
+            player_id,
+            kind,
+            amount,
+            is_all_in
+        }
+    }
+}
+
+
+/// The main pot or a side pot at the end of a hand.
+///
+/// # Fields
+///
+/// * `amount`: The chips in the pot.
+/// * `eligible_players`: The players who could win the pot, sorted by `PlayerId`.
+/// * `payouts`: The chips each winner of the pot received.
+#[derive(Debug, Clone, PartialEq, Eq, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct PotRecord {
+    amount: i32,
+    eligible_players: Vec<PlayerId>,
+    payouts: BTreeMap<PlayerId, i32>
+}
+
+impl PotRecord {
+    pub(crate) fn new(amount: i32, eligible_players: Vec<PlayerId>, payouts: BTreeMap<PlayerId, i32>) -> PotRecord {
+        PotRecord {
+            amount,
+            eligible_players,
+            payouts
+        }
+    }
+}
+
+
+/// One run of the board when it was run more than once.
+///
+/// # Fields
+///
+/// * `board`: The five community cards of this run, including the ones dealt before the players agreed to run it
+///   more than once.
+/// * `payouts`: The chips each winner received from this run's portion of each pot, in the order of the hand's pots.
+/// * `scores`: The hand each player who showed made on this run's board. Mucked hands are not scored.
+#[derive(Debug, Clone, PartialEq, Eq, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct RunRecord {
+    board: Vec<Card>,
+    payouts: Vec<BTreeMap<PlayerId, i32>>,
+    scores: BTreeMap<PlayerId, HandScore>
+}
+
+impl RunRecord {
+    pub(crate) fn new(board: Vec<Card>, payouts: Vec<BTreeMap<PlayerId, i32>>, scores: BTreeMap<PlayerId, HandScore>) -> RunRecord {
+        RunRecord {
+            board,
+            payouts,
+            scores
+        }
+    }
+}
+
+
+/// A hand shown at showdown.
+///
+/// # Fields
+///
+/// * `player_id`: The player who showed.
+/// * `hole_cards`: The cards they showed.
+/// * `score`: The hand they made on the first run of the board. When the board was run more than once, the hand they
+///   made on each run is in that run's `RunRecord`.
+#[derive(Debug, Clone, PartialEq, Eq, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct ShowdownRecord {
+    player_id: PlayerId,
+    hole_cards: Vec<Card>,
+    score: HandScore
+}
+
+impl ShowdownRecord {
+    pub(crate) fn new(player_id: PlayerId, hole_cards: Vec<Card>, score: HandScore) -> ShowdownRecord {
+        ShowdownRecord {
+            player_id,
+            hole_cards,
+            score
+        }
+    }
+}
+
+
+/// Somewhere `Game` sends the `HandHistory` of each hand when it ends.
+pub trait HandHistorySink: Debug + Send {
+    fn record(&mut self, hand_history: &HandHistory) -> std::io::Result<()>;
+}
+
+
+/// A `HandHistory` that a `HandHistorySink` could not record.
+///
+/// # Fields
+///
+/// * `hand_number`: The number of the hand that was not recorded.
+/// * `error`: Why the sink failed.
+#[derive(Debug)]
+pub struct HistorySinkError {
+    hand_number: u64,
+    error: std::io::Error
+}
+
+impl HistorySinkError {
+    pub(crate) fn new(hand_number: u64, error: std::io::Error) -> HistorySinkError {
+        HistorySinkError {
+            hand_number,
+            error
+        }
+    }
+
+    pub fn get_hand_number(&self) -> u64 {
+        self.hand_number
+    }
+
+    pub fn get_error(&self) -> &std::io::Error {
+        &self.error
+    }
+}
+
+impl std::fmt::Display for HistorySinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Could not record hand {}: {}", self.hand_number, self.error)
+    }
+}
+
+impl std::error::Error for HistorySinkError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+
+/// A `HandHistorySink` that keeps the histories in memory. Clones share the same histories, so a clone can be given
+/// to `Game` and the original used to read them.
+#[derive(Debug, Clone, Default)]
+pub struct MemorySink {
+    hand_histories: Arc<Mutex<Vec<HandHistory>>>
+}
+
+impl MemorySink {
+    pub fn new() -> MemorySink {
+        MemorySink::default()
+    }
+
+    pub fn get_hand_histories(&self) -> Vec<HandHistory> {
+        self.hand_histories.lock().expect("Hand history lock was poisoned").clone()
+    }
+}
+
+impl HandHistorySink for MemorySink {
+    fn record(&mut self, hand_history: &HandHistory) -> std::io::Result<()> {
+        self.hand_histories.lock().expect("Hand history lock was poisoned").push(hand_history.clone());
+        Ok(())
+    }
+}
+
+
+/// A `HandHistorySink` that writes each history as one line of JSON.
+#[derive(Debug)]
+pub struct JsonLinesSink<W> {
+    writer: W
+}
+
+impl<W: Write> JsonLinesSink<W> {
+    pub fn new(writer: W) -> JsonLinesSink<W> {
+        JsonLinesSink {
+            writer
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl JsonLinesSink<File> {
+    /// Opens `path` to append histories to, creating it if it does not exist.
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<JsonLinesSink<File>> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(JsonLinesSink::new(file))
+    }
+}
+
+impl<W: Write + Debug + Send> HandHistorySink for JsonLinesSink<W> {
+    fn record(&mut self, hand_history: &HandHistory) -> std::io::Result<()> {
+        serde_json::to_writer(&mut self.writer, hand_history)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_lines_sink_writes_one_history_per_line() {
+        let seats = vec![SeatRecord::new(0, 1, String::from("Player 1"), 100, Vec::new())];
+        let mut hand_history = HandHistory::new(0, 1, 10, BlindStructure::new(5, 10), BettingStructure::NoLimit,
+                                                OddChipRule::FirstLeftOfButton, 0, seats);
+        hand_history.record_action(ActionRecord::new(Street::Preflop, 1, ActionKind::RaiseTo(30), 30, false));
+
+        let mut sink = JsonLinesSink::new(Vec::<u8>::new());
+        sink.record(&hand_history).unwrap();
+        sink.record(&hand_history).unwrap();
+
+        let output = String::from_utf8(sink.into_inner()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(serde_json::from_str::<HandHistory>(lines[0]).unwrap(), hand_history);
+    }
+}
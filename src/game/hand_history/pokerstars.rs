@@ -0,0 +1,1074 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Debug;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use crate::game::OddChipRule;
+use crate::game::betting::BettingStructure;
+use crate::game::blinds::BlindStructure;
+use crate::game::hand::{Card, HandScore, HandType, OnePlayerAllPossibleCards, Suit};
+use crate::game::hand_history::{ActionKind, ActionRecord, HandHistory, HandHistorySink, PotRecord, RunRecord, SeatRecord};
+use crate::game::hand_state::Street;
+use crate::game::player::PlayerId;
+use crate::game::pot_manager::PotManager;
+use crate::game::showdown::ShowdownResult;
+
+
+/// The raise cap given to fixed-limit hands read by `parse_hands`, since the text format does not include it. This is
+/// the usual cap of a bet and three raises.
+pub const FIXED_LIMIT_RAISE_CAP: u32 = 3;
+
+const HAND_SEPARATOR: &str = "\n\n\n";
+
+const RUN_NAMES: [&str; 10] = ["FIRST", "SECOND", "THIRD", "FOURTH", "FIFTH", "SIXTH", "SEVENTH", "EIGHTH", "NINTH", "TENTH"];
+
+
+/// Converts a `HandHistory` into the PokerStars text hand history format.
+///
+/// # Notes
+///
+/// Every player's hole cards are written as "Dealt to" lines, since the server saw them all. Amounts are written in
+/// chips with no currency. Hands mucked at showdown are written as "mucks hand" and listed as mucked in
+/// the summary.
+///
+/// Blinds and antes that PokerStars does not have are written as "posts straddle", "posts dead small blind" and
+/// "posts the big blind ante".
+pub fn write_hand(hand_history: &HandHistory) -> String {
+    let names: HashMap<PlayerId, &str> = hand_history.seats().iter()
+        .map(|seat| (*seat.player_id(), seat.name().as_str()))
+        .collect();
+    let name = |player_id: &PlayerId| names.get(player_id).cloned().unwrap_or("Unknown player");
+    let board = hand_history.board();
+    let mut lines = Vec::<String>::new();
+
+    lines.push(format!("PokerStars Hand #{}: {} ({}) - {} UTC", hand_history.hand_number(),
+                       game_type(hand_history.betting_structure()),
+                       stakes(hand_history.betting_structure(), hand_history.blind_structure()),
+                       format_time(*hand_history.start_time())));
+    lines.push(format!("Table '{}' {}-max Seat #{} is the button", hand_history.game_id(), hand_history.num_seats(),
+                       hand_history.button() + 1));
+
+    let mut seats: Vec<&SeatRecord> = hand_history.seats().iter().collect();
+    seats.sort_by_key(|seat| *seat.seat());
+    for seat in seats.iter() {
+        lines.push(format!("Seat {}: {} ({} in chips)", seat.seat() + 1, seat.name(), seat.stack()));
+    }
+
+    let num_posts = hand_history.actions().iter().take_while(|action| is_post(*action.kind())).count();
+    let (posts, actions) = hand_history.actions().split_at(num_posts);
+    let mut street_bets = HashMap::<PlayerId, i32>::new();
+
+    for post in posts {
+        lines.push(write_action(post, name(post.player_id()), &mut street_bets));
+    }
+
+    lines.push(String::from("*** HOLE CARDS ***"));
+    for seat in hand_history.seats() {
+        lines.push(format!("Dealt to {} [{}]", seat.name(), write_cards(seat.hole_cards())));
+    }
+
+    let mut street = Street::Preflop;
+    for action in actions {
+        while street < *action.street() {
+            street = street.next();
+            street_bets.clear();
+            lines.extend(write_street(street, board, None));
+        }
+        lines.push(write_action(action, name(action.player_id()), &mut street_bets));
+    }
+
+    // The rest of the board is dealt without any betting when everyone is all in. If it was run more than once, the
+    // cards every run shares are dealt first, then the rest of each run.
+    let runs = hand_history.runs();
+    let num_shared = (0..board.len())
+        .take_while(|&i| runs.iter().all(|run| run.board().get(i) == Some(&board[i])))
+        .count();
+    while street < Street::River && num_shared >= num_cards_by(street.next()) {
+        street = street.next();
+        lines.extend(write_street(street, board, None));
+    }
+    for (i, run) in runs.iter().enumerate() {
+        let mut run_street = street;
+        while run_street < Street::River {
+            run_street = run_street.next();
+            lines.extend(write_street(run_street, run.board(), Some(i)));
+        }
+    }
+
+    // Each run has its own showdown, where the hands are described on that run's board.
+    let pots = hand_history.pots();
+    let write_showdown = |lines: &mut Vec<String>, board: &[Card], payouts: Vec<&BTreeMap<PlayerId, i32>>| {
+        if let Some(showdown) = hand_history.showdown() {
+            for player_id in showdown.order() {
+                match showdown.shown().iter().find(|shown| shown.player_id() == player_id) {
+                    Some(shown) => lines.push(format!("{}: shows [{}] ({})", name(player_id), write_cards(shown.hole_cards()),
+                                                      describe(&score(shown.hole_cards(), board)))),
+                    None => lines.push(format!("{}: mucks hand", name(player_id)))
+                }
+            }
+        }
+        for (i, payouts) in payouts.iter().enumerate().rev() {
+            for (player_id, amount) in payouts.iter() {
+                lines.push(format!("{} collected {} from {}", name(player_id), amount, pot_label(i, pots.len())));
+            }
+        }
+    };
+    if runs.is_empty() {
+        if hand_history.showdown().is_some() {
+            lines.push(String::from("*** SHOW DOWN ***"));
+        }
+        write_showdown(&mut lines, board, pots.iter().map(|pot| pot.payouts()).collect());
+    }
+    for (i, run) in runs.iter().enumerate() {
+        lines.push(format!("*** {} SHOW DOWN ***", run_name(i)));
+        write_showdown(&mut lines, run.board(), run.payouts().iter().collect());
+    }
+
+    lines.push(String::from("*** SUMMARY ***"));
+    let mut total_pot = format!("Total pot {}", pots.iter().map(|pot| pot.amount()).sum::<i32>());
+    if pots.len() > 1 {
+        for (i, pot) in pots.iter().enumerate() {
+            let label = pot_label(i, pots.len());
+            total_pot += &format!(" {}{} {}.", label[..1].to_uppercase(), &label[1..], pot.amount());
+        }
+    }
+    lines.push(total_pot + " | Rake 0");
+    if runs.is_empty() && !board.is_empty() {
+        lines.push(format!("Board [{}]", write_cards(board)));
+    }
+    if !runs.is_empty() {
+        lines.push(match runs.len() {
+            2 => String::from("Hand was run twice"),
+            3 => String::from("Hand was run three times"),
+            num_runs => format!("Hand was run {} times", num_runs)
+        });
+    }
+    for (i, run) in runs.iter().enumerate() {
+        lines.push(format!("{} Board [{}]", run_name(i), write_cards(run.board())));
+    }
+
+    let small_blind = posts.iter().find(|post| *post.kind() == ActionKind::SmallBlind).map(|post| *post.player_id());
+    let big_blind = posts.iter().find(|post| *post.kind() == ActionKind::BigBlind).map(|post| *post.player_id());
+    let mut winnings = BTreeMap::<PlayerId, i32>::new();
+    for (player_id, amount) in pots.iter().flat_map(|pot| pot.payouts().iter()) {
+        *winnings.entry(*player_id).or_insert(0) += amount;
+    }
+
+    for seat in seats {
+        let player_id = seat.player_id();
+        let mut line = format!("Seat {}: {}", seat.seat() + 1, seat.name());
+        if seat.seat() == hand_history.button() {
+            line += " (button)";
+        }
+        if small_blind == Some(*player_id) {
+            line += " (small blind)";
+        }
+        if big_blind == Some(*player_id) {
+            line += " (big blind)";
+        }
+
+        let fold = hand_history.actions().iter().find(|action| action.player_id() == player_id && *action.kind() == ActionKind::Fold);
+        let showdown = hand_history.showdown().as_ref();
+        let shown = showdown.and_then(|showdown| showdown.shown().iter().find(|shown| shown.player_id() == player_id));
+        let mucked = showdown.is_some_and(|showdown| showdown.get_mucked().contains(player_id));
+        let won = winnings.get(player_id).cloned().unwrap_or(0);
+
+        line += &match (fold, shown) {
+            (None, None) if mucked => format!(" mucked [{}]", write_cards(seat.hole_cards())),
+            (Some(fold), _) if *fold.street() == Street::Preflop => String::from(" folded before Flop"),
+            (Some(fold), _) => format!(" folded on the {}", fold.street()),
+            (None, Some(shown)) if won > 0 => format!(" showed [{}] and won ({}) with {}", write_cards(shown.hole_cards()), won, describe(shown.score())),
+            (None, Some(shown)) => format!(" showed [{}] and lost with {}", write_cards(shown.hole_cards()), describe(shown.score())),
+            (None, None) => format!(" collected ({})", won)
+        };
+        lines.push(line);
+    }
+
+    lines.join("\n")
+}
+
+
+/// Converts several hands into one PokerStars hand history file, with the hands separated by blank lines.
+pub fn write_hands(hand_histories: &[HandHistory]) -> String {
+    hand_histories.iter().map(write_hand).collect::<Vec<String>>().join(HAND_SEPARATOR)
+}
+
+
+/// Reads the hands in a PokerStars hand history file written by `write_hand`.
+///
+/// # Returns
+///
+/// * `Ok(Vec<HandHistory>)` - The hands in the file, in order.
+/// * `Err(ParseError)` - The first line that could not be read.
+///
+/// # Notes
+///
+/// The text format does not have `PlayerId`s, so each player is given the number of their seat, counting from 1. It
+/// does not have the odd chip rule either, so the hands are given the default `OddChipRule::FirstLeftOfButton`.
+/// Pots, payouts and showdown scores are worked out again from the actions and cards. The summary section is not read.
+pub fn parse_hands(text: &str) -> Result<Vec<HandHistory>, ParseError> {
+    let mut hand_histories = Vec::<HandHistory>::new();
+    let mut hand_lines = Vec::<(usize, &str)>::new();
+
+    for (i, line) in text.trim_start_matches('\u{feff}').lines().enumerate() {
+        let line = line.trim_end();
+        if line.starts_with("PokerStars Hand #") && !hand_lines.is_empty() {
+            hand_histories.push(parse_hand_lines(&hand_lines)?);
+            hand_lines.clear();
+        }
+        if !line.is_empty() {
+            hand_lines.push((i + 1, line));
+        }
+    }
+
+    if !hand_lines.is_empty() {
+        hand_histories.push(parse_hand_lines(&hand_lines)?);
+    }
+
+    Ok(hand_histories)
+}
+
+
+/// A line of a PokerStars hand history that could not be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    line_number: usize,
+    message: String
+}
+
+impl ParseError {
+    fn new(line_number: usize, message: impl Into<String>) -> ParseError {
+        ParseError {
+            line_number,
+            message: message.into()
+        }
+    }
+
+    /// Returns the number of the line that could not be read, counting from 1.
+    pub fn get_line_number(&self) -> usize {
+        self.line_number
+    }
+
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Line {}: {}", self.line_number, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+
+/// A `HandHistorySink` that writes each hand in the PokerStars text format, so it can be imported into trackers.
+#[derive(Debug)]
+pub struct PokerStarsSink<W> {
+    writer: W
+}
+
+impl<W: Write> PokerStarsSink<W> {
+    pub fn new(writer: W) -> PokerStarsSink<W> {
+        PokerStarsSink {
+            writer
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl PokerStarsSink<File> {
+    /// Opens `path` to append hands to, creating it if it does not exist.
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<PokerStarsSink<File>> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(PokerStarsSink::new(file))
+    }
+}
+
+impl<W: Write + Debug + Send> HandHistorySink for PokerStarsSink<W> {
+    fn record(&mut self, hand_history: &HandHistory) -> std::io::Result<()> {
+        write!(self.writer, "{}{}", write_hand(hand_history), HAND_SEPARATOR)?;
+        self.writer.flush()
+    }
+}
+
+
+fn game_type(betting_structure: &BettingStructure) -> &'static str {
+    match betting_structure {
+        BettingStructure::NoLimit => "Hold'em No Limit",
+        BettingStructure::PotLimit => "Hold'em Pot Limit",
+        BettingStructure::FixedLimit { .. } => "Hold'em Limit"
+    }
+}
+
+
+/// Fixed-limit games are named by their bet sizes rather than their blinds.
+fn stakes(betting_structure: &BettingStructure, blind_structure: &BlindStructure) -> String {
+    match betting_structure {
+        BettingStructure::FixedLimit { small_bet, big_bet, .. } => format!("{}/{}", small_bet, big_bet),
+        _ => format!("{}/{}", blind_structure.get_small_blind(), blind_structure.get_big_blind())
+    }
+}
+
+
+fn is_post(kind: ActionKind) -> bool {
+    matches!(kind, ActionKind::SmallBlind | ActionKind::BigBlind | ActionKind::Straddle | ActionKind::DeadSmallBlind
+        | ActionKind::Ante | ActionKind::BigBlindAnte)
+}
+
+
+/// Returns whether the chips put in by `kind` count towards the player's bet on the street.
+fn is_live(kind: ActionKind) -> bool {
+    !matches!(kind, ActionKind::DeadSmallBlind | ActionKind::Ante | ActionKind::BigBlindAnte | ActionKind::UncalledBetReturned)
+}
+
+
+/// Writes the line for a blind, ante or action by the player called `name`. `street_bets` holds what each player has
+/// bet on the street so far.
+fn write_action(action: &ActionRecord, name: &str, street_bets: &mut HashMap<PlayerId, i32>) -> String {
+    let amount = *action.amount();
+    let current_bet = street_bets.values().cloned().max().unwrap_or(0);
+    if is_live(*action.kind()) {
+        *street_bets.entry(*action.player_id()).or_insert(0) += amount;
+    }
+
+    let text = match action.kind() {
+        ActionKind::SmallBlind => format!("posts small blind {}", amount),
+        ActionKind::BigBlind => format!("posts big blind {}", amount),
+        ActionKind::Straddle => format!("posts straddle {}", amount),
+        ActionKind::DeadSmallBlind => format!("posts dead small blind {}", amount),
+        ActionKind::Ante => format!("posts the ante {}", amount),
+        ActionKind::BigBlindAnte => format!("posts the big blind ante {}", amount),
+        ActionKind::Fold => String::from("folds"),
+        ActionKind::Check => String::from("checks"),
+        ActionKind::Call => format!("calls {}", amount),
+        ActionKind::Bet(total) => format!("bets {}", total),
+        ActionKind::RaiseTo(total) => format!("raises {} to {}", total - current_bet, total),
+        ActionKind::UncalledBetReturned => return format!("Uncalled bet ({}) returned to {}", amount, name)
+    };
+
+    if *action.is_all_in() {
+        format!("{}: {} and is all-in", name, text)
+    } else {
+        format!("{}: {}", name, text)
+    }
+}
+
+
+/// Reads what a player did from the text after their name.
+///
+/// # Returns
+///
+/// * `Option<(ActionKind, i32, bool)>` - The action, the chips it put in and whether it put the player all in, or
+///   `None` if the text is not an action. `street_bet` is what the player had already bet on the street.
+fn parse_action(text: &str, street_bet: i32) -> Option<(ActionKind, i32, bool)> {
+    let (text, is_all_in) = match text.strip_suffix(" and is all-in") {
+        Some(text) => (text, true),
+        None => (text, false)
+    };
+
+    let posts = [
+        ("posts small blind ", ActionKind::SmallBlind),
+        ("posts big blind ", ActionKind::BigBlind),
+        ("posts straddle ", ActionKind::Straddle),
+        ("posts dead small blind ", ActionKind::DeadSmallBlind),
+        ("posts the ante ", ActionKind::Ante),
+        ("posts the big blind ante ", ActionKind::BigBlindAnte),
+        ("calls ", ActionKind::Call)
+    ];
+    for (prefix, kind) in posts {
+        if let Some(amount) = text.strip_prefix(prefix) {
+            return Some((kind, amount.parse().ok()?, is_all_in));
+        }
+    }
+
+    if text == "folds" {
+        return Some((ActionKind::Fold, 0, is_all_in));
+    }
+    if text == "checks" {
+        return Some((ActionKind::Check, 0, is_all_in));
+    }
+    if let Some(total) = text.strip_prefix("bets ") {
+        let total = total.parse().ok()?;
+        return Some((ActionKind::Bet(total), total - street_bet, is_all_in));
+    }
+    if let Some(raise) = text.strip_prefix("raises ") {
+        let total: i32 = raise.split_once(" to ")?.1.parse().ok()?;
+        return Some((ActionKind::RaiseTo(total), total - street_bet, is_all_in));
+    }
+
+    None
+}
+
+
+/// Returns the number of community cards on the board once `street` has been dealt.
+fn num_cards_by(street: Street) -> usize {
+    match street {
+        Street::Preflop => 0,
+        Street::Flop => 3,
+        Street::Turn => 4,
+        Street::River | Street::Showdown => 5
+    }
+}
+
+
+/// Writes the line dealing `street`. `run` is the index of the run it belongs to if the board was run more than once
+/// and the street was not shared by every run.
+fn write_street(street: Street, board: &[Card], run: Option<usize>) -> Option<String> {
+    let heading = match run {
+        Some(run) => format!("*** {} ", run_name(run)),
+        None => String::from("*** ")
+    };
+    match street {
+        Street::Flop => Some(format!("{}FLOP *** [{}]", heading, write_cards(&board[..3]))),
+        Street::Turn => Some(format!("{}TURN *** [{}] [{}]", heading, write_cards(&board[..3]), write_cards(&board[3..4]))),
+        Street::River => Some(format!("{}RIVER *** [{}] [{}]", heading, write_cards(&board[..4]), write_cards(&board[4..5]))),
+        Street::Preflop | Street::Showdown => None
+    }
+}
+
+
+/// Names a run of the board the way PokerStars does, such as "FIRST" or "SECOND".
+fn run_name(index: usize) -> String {
+    match RUN_NAMES.get(index) {
+        Some(name) => name.to_string(),
+        None => format!("RUN-{}", index + 1)
+    }
+}
+
+
+/// Splits the name of a run written by `run_name` off the start of `text`.
+fn split_run_name(text: &str) -> Option<(usize, &str)> {
+    let (name, rest) = text.split_once(' ')?;
+    let index = match RUN_NAMES.iter().position(|run_name| *run_name == name) {
+        Some(index) => index,
+        None => name.strip_prefix("RUN-")?.parse::<usize>().ok()?.checked_sub(1)?
+    };
+    Some((index, rest))
+}
+
+
+/// Scores a player's best hand from their hole cards and a full board.
+fn score(hole_cards: &[Card], board: &[Card]) -> HandScore {
+    OnePlayerAllPossibleCards::new(hole_cards.iter().chain(board.iter()).cloned().collect()).get_highest_hand_score()
+}
+
+
+fn pot_label(index: usize, num_pots: usize) -> String {
+    match (index, num_pots) {
+        (_, 1) => String::from("pot"),
+        (0, _) => String::from("main pot"),
+        (_, 2) => String::from("side pot"),
+        (i, _) => format!("side pot-{}", i)
+    }
+}
+
+
+fn parse_pot_label(label: &str) -> Option<usize> {
+    match label {
+        "pot" | "main pot" => Some(0),
+        "side pot" => Some(1),
+        _ => label.strip_prefix("side pot-")?.parse().ok()
+    }
+}
+
+
+fn write_card(card: &Card) -> String {
+    let value = match card.get_value() {
+        10 => 'T',
+        11 => 'J',
+        12 => 'Q',
+        13 => 'K',
+        14 => 'A',
+        value => char::from_digit(value as u32, 10).unwrap_or('?')
+    };
+    let suit = match card.get_suit() {
+        Suit::Hearts => 'h',
+        Suit::Diamonds => 'd',
+        Suit::Clubs => 'c',
+        Suit::Spades => 's'
+    };
+
+    format!("{}{}", value, suit)
+}
+
+
+fn write_cards(cards: &[Card]) -> String {
+    cards.iter().map(write_card).collect::<Vec<String>>().join(" ")
+}
+
+
+fn parse_card(text: &str) -> Option<Card> {
+    let mut chars = text.chars();
+    let value = match chars.next()? {
+        'T' => 10,
+        'J' => 11,
+        'Q' => 12,
+        'K' => 13,
+        'A' => 14,
+        digit @ '2'..='9' => digit.to_digit(10)? as i32,
+        _ => return None
+    };
+    let suit = match chars.next()? {
+        'h' => Suit::Hearts,
+        'd' => Suit::Diamonds,
+        'c' => Suit::Clubs,
+        's' => Suit::Spades,
+        _ => return None
+    };
+
+    if chars.next().is_some() {
+        return None;
+    }
+    Some(Card::new(suit, value))
+}
+
+
+/// Reads the cards in every pair of square brackets in `text`.
+fn parse_bracketed_cards(text: &str) -> Option<Vec<Card>> {
+    let mut cards = Vec::<Card>::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find('[') {
+        let end = rest[start..].find(']')? + start;
+        for card in rest[start + 1..end].split_whitespace() {
+            cards.push(parse_card(card)?);
+        }
+        rest = &rest[end + 1..];
+    }
+
+    Some(cards)
+}
+
+
+fn value_name(value: i32) -> &'static str {
+    match value {
+        2 => "Deuce",
+        3 => "Three",
+        4 => "Four",
+        5 => "Five",
+        6 => "Six",
+        7 => "Seven",
+        8 => "Eight",
+        9 => "Nine",
+        10 => "Ten",
+        11 => "Jack",
+        12 => "Queen",
+        13 => "King",
+        _ => "Ace"
+    }
+}
+
+
+fn plural_value_name(value: i32) -> String {
+    match value {
+        6 => String::from("Sixes"),
+        value => format!("{}s", value_name(value))
+    }
+}
+
+
+/// Describes a hand the way PokerStars does at showdown, such as "two pair, Aces and Kings".
+fn describe(score: &HandScore) -> String {
+    let ranks = score.get_ranks();
+    let rank = |i: usize| ranks.get(i).cloned().unwrap_or(0);
+    // A straight is ranked by its highest card. The wheel runs from Ace to Five.
+    let low_card = |high: i32| if high == 5 { 14 } else { high - 4 };
+
+    match score.get_hand_type() {
+        HandType::HighCard => format!("high card {}", value_name(rank(0))),
+        HandType::Pair => format!("a pair of {}", plural_value_name(rank(0))),
+        HandType::TwoPair => format!("two pair, {} and {}", plural_value_name(rank(0)), plural_value_name(rank(1))),
+        HandType::ThreeOfAKind => format!("three of a kind, {}", plural_value_name(rank(0))),
+        HandType::Straight => format!("a straight, {} to {}", value_name(low_card(rank(0))), value_name(rank(0))),
+        HandType::Flush => format!("a flush, {} high", value_name(rank(0))),
+        HandType::FullHouse => format!("a full house, {} full of {}", plural_value_name(rank(0)), plural_value_name(rank(1))),
+        HandType::FourOfAKind => format!("four of a kind, {}", plural_value_name(rank(0))),
+        HandType::StraightFlush => format!("a straight flush, {} to {}", value_name(low_card(rank(0))), value_name(rank(0))),
+        HandType::Royal | HandType::RoyalFlush => String::from("a Royal Flush")
+    }
+}
+
+
+/// Formats seconds since the Unix epoch as a UTC date and time, such as "2024/03/01 18:05:09".
+fn format_time(time: u64) -> String {
+    let days = (time / 86400) as i64;
+    let seconds = time % 86400;
+
+    // Converts days since the epoch to a civil date, from Howard Hinnant's `civil_from_days`.
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}/{:02}/{:02} {:02}:{:02}:{:02}", year, month, day, seconds / 3600, seconds % 3600 / 60, seconds % 60)
+}
+
+
+/// Reads a date and time written by `format_time`.
+fn parse_time(text: &str) -> Option<u64> {
+    let (date, time) = text.split_once(' ')?;
+    let date: Vec<i64> = date.split('/').map(|part| part.parse().ok()).collect::<Option<Vec<i64>>>()?;
+    let time: Vec<u64> = time.split(':').map(|part| part.parse().ok()).collect::<Option<Vec<u64>>>()?;
+    if date.len() != 3 || time.len() != 3 {
+        return None;
+    }
+
+    let (month, day) = (date[1], date[2]);
+    let (hours, minutes, seconds) = (time[0], time[1], time[2]);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hours >= 24 || minutes >= 60 || seconds >= 60 {
+        return None;
+    }
+
+    // Converts a civil date to days since the epoch, from Howard Hinnant's `days_from_civil`.
+    let year = date[0].checked_sub(if month <= 2 { 1 } else { 0 })?;
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era.checked_mul(146097)?.checked_add(day_of_era - 719468)?;
+
+    u64::try_from(days).ok()?.checked_mul(86400)?.checked_add(hours * 3600 + minutes * 60 + seconds)
+}
+
+
+/// Finds the seated player whose name `line` starts with. The longest name wins, in case one name starts with another.
+fn split_name<'a>(line: &'a str, names: &[(String, PlayerId)]) -> Option<(PlayerId, &'a str)> {
+    names.iter()
+        .filter(|(name, _)| line.starts_with(name.as_str()))
+        .max_by_key(|(name, _)| name.len())
+        .map(|(name, player_id)| (*player_id, &line[name.len()..]))
+}
+
+
+fn parse_hand_lines(lines: &[(usize, &str)]) -> Result<HandHistory, ParseError> {
+    let last_line_number = lines.last().map_or(0, |(line_number, _)| *line_number);
+    let mut lines = lines.iter().cloned().peekable();
+    let ended_early = || ParseError::new(last_line_number, "The hand ended early");
+
+    // PokerStars Hand #1: Hold'em No Limit (5/10) - 2024/03/01 18:05:09 UTC
+    let (line_number, line) = lines.next().ok_or_else(ended_early)?;
+    let error = |message: &str| ParseError::new(line_number, message);
+    let header = line.strip_prefix("PokerStars Hand #").ok_or_else(|| error("Expected a hand header"))?;
+    let (hand_number, header) = header.split_once(": ").ok_or_else(|| error("Expected a hand number"))?;
+    let hand_number: u64 = hand_number.parse().map_err(|_| error("Expected a hand number"))?;
+    let (game_type, header) = header.split_once(" (").ok_or_else(|| error("Expected the stakes"))?;
+    let (stakes, time) = header.split_once(") - ").ok_or_else(|| error("Expected the stakes"))?;
+    let (low_stake, high_stake) = stakes.split_once('/')
+        .and_then(|(low, high)| Some((low.parse::<i32>().ok()?, high.parse::<i32>().ok()?)))
+        .ok_or_else(|| error("Expected the stakes"))?;
+    let start_time = time.strip_suffix(" UTC").and_then(parse_time).ok_or_else(|| error("Expected a UTC date and time"))?;
+    let betting_structure = match game_type {
+        "Hold'em No Limit" => BettingStructure::NoLimit,
+        "Hold'em Pot Limit" => BettingStructure::PotLimit,
+        "Hold'em Limit" => BettingStructure::FixedLimit { small_bet: low_stake, big_bet: high_stake, raise_cap: FIXED_LIMIT_RAISE_CAP },
+        _ => return Err(error("Expected a Hold'em game"))
+    };
+
+    // Table '0' 10-max Seat #3 is the button
+    let (line_number, line) = lines.next().ok_or_else(ended_early)?;
+    let error = |message: &str| ParseError::new(line_number, message);
+    let (game_id, table) = line.strip_prefix("Table '").and_then(|table| table.split_once("' ")).ok_or_else(|| error("Expected the table"))?;
+    let game_id: u128 = game_id.parse().map_err(|_| error("Expected a numeric table name"))?;
+    let (num_seats, button) = table.split_once("-max Seat #").ok_or_else(|| error("Expected the number of seats and the button"))?;
+    let num_seats: usize = num_seats.parse().map_err(|_| error("Expected the number of seats"))?;
+    let button: usize = button.strip_suffix(" is the button").and_then(|button| button.parse().ok()).ok_or_else(|| error("Expected the button"))?;
+    let button = button.checked_sub(1).ok_or_else(|| error("Seats are numbered from 1"))?;
+
+    // Seat 1: Player 1 (100 in chips)
+    let mut seats = Vec::<(usize, PlayerId, String, i32)>::new();
+    while let Some((line_number, seat)) = lines.next_if(|(_, line)| line.starts_with("Seat ")) {
+        let seat = &seat["Seat ".len()..];
+        let error = || ParseError::new(line_number, "Expected a seat");
+        let (seat, player) = seat.split_once(": ").ok_or_else(error)?;
+        let seat: usize = seat.parse().map_err(|_| error())?;
+        let (name, stack) = player.rsplit_once(" (").ok_or_else(error)?;
+        let stack: i32 = stack.strip_suffix(" in chips)").and_then(|stack| stack.parse().ok()).ok_or_else(error)?;
+        let seat = seat.checked_sub(1).ok_or_else(error)?;
+        seats.push((seat, seat as PlayerId + 1, name.to_string(), stack));
+    }
+    let names: Vec<(String, PlayerId)> = seats.iter().map(|(_, player_id, name, _)| (name.clone(), *player_id)).collect();
+
+    let mut street = Street::Preflop;
+    let mut street_bets = HashMap::<PlayerId, i32>::new();
+    let mut actions = Vec::<ActionRecord>::new();
+    let mut hole_cards = HashMap::<PlayerId, Vec<Card>>::new();
+    let mut board = Vec::<Card>::new();
+    let mut shown = Vec::<(PlayerId, Vec<Card>)>::new();
+    let mut showdown_order = Vec::<PlayerId>::new();
+    let mut collected = Vec::<(Option<usize>, PlayerId, i32, usize)>::new();
+    // When the board was run more than once, each run's board and the run whose showdown is being read.
+    let mut run_boards = Vec::<Vec<Card>>::new();
+    let mut showdown_run = None::<usize>;
+
+    loop {
+        let (line_number, line) = lines.next().ok_or_else(ended_early)?;
+        let error = |message: &str| ParseError::new(line_number, message);
+
+        if line == "*** SUMMARY ***" {
+            break;
+        }
+        if line == "*** HOLE CARDS ***" || line == "*** SHOW DOWN ***" {
+            continue;
+        }
+
+        if let Some(heading) = line.strip_prefix("*** ") {
+            let (run, heading) = match split_run_name(heading) {
+                Some((run, heading)) if run <= run_boards.len() => (Some(run), heading),
+                Some(_) => return Err(error("Expected the runs of the board in order")),
+                None => (None, heading)
+            };
+            if run.is_some() && heading == "SHOW DOWN ***" {
+                showdown_run = run;
+                continue;
+            }
+
+            let new_street = [("FLOP *** ", Street::Flop), ("TURN *** ", Street::Turn), ("RIVER *** ", Street::River)]
+                .into_iter()
+                .find(|(prefix, _)| heading.starts_with(prefix));
+            if let Some((_, new_street)) = new_street {
+                let cards = parse_bracketed_cards(heading).ok_or_else(|| error("Expected the board"))?;
+                match run {
+                    Some(run) if run == run_boards.len() => run_boards.push(cards),
+                    Some(run) => run_boards[run] = cards,
+                    None => {
+                        street = new_street;
+                        street_bets.clear();
+                        board = cards;
+                    }
+                }
+                continue;
+            }
+        }
+
+        if let Some(dealt) = line.strip_prefix("Dealt to ") {
+            let (player_id, cards) = split_name(dealt, &names).ok_or_else(|| error("Expected a seated player"))?;
+            hole_cards.insert(player_id, parse_bracketed_cards(cards).ok_or_else(|| error("Expected hole cards"))?);
+            continue;
+        }
+
+        if let Some(uncalled) = line.strip_prefix("Uncalled bet (") {
+            let (amount, player) = uncalled.split_once(") returned to ").ok_or_else(|| error("Expected an uncalled bet"))?;
+            let player_id = names.iter().find(|(name, _)| name == player).map(|(_, player_id)| *player_id)
+                .ok_or_else(|| error("Expected a seated player"))?;
+            let amount = amount.parse::<i32>().map_err(|_| error("Expected an amount"))?;
+            actions.push(ActionRecord::new(street, player_id, ActionKind::UncalledBetReturned, amount, false));
+            continue;
+        }
+
+        let (player_id, rest) = split_name(line, &names).ok_or_else(|| error("Expected a line about a seated player"))?;
+
+        if let Some(pot) = rest.strip_prefix(" collected ") {
+            let (amount, label) = pot.split_once(" from ").ok_or_else(|| error("Expected a pot"))?;
+            let amount: i32 = amount.parse().map_err(|_| error("Expected an amount"))?;
+            collected.push((showdown_run, player_id, amount, parse_pot_label(label).ok_or_else(|| error("Expected a pot"))?));
+            continue;
+        }
+
+        let rest = rest.strip_prefix(": ").ok_or_else(|| error("Expected an action"))?;
+        if let Some(cards) = rest.strip_prefix("shows ") {
+            let cards = parse_bracketed_cards(cards).filter(|cards| cards.len() == 2).ok_or_else(|| error("Expected two shown cards"))?;
+            // Later runs show the same hands again.
+            if showdown_run.unwrap_or(0) == 0 {
+                shown.push((player_id, cards));
+                showdown_order.push(player_id);
+            }
+            continue;
+        }
+        if rest == "mucks hand" {
+            if showdown_run.unwrap_or(0) == 0 {
+                showdown_order.push(player_id);
+            }
+            continue;
+        }
+
+        let street_bet = street_bets.get(&player_id).cloned().unwrap_or(0);
+        let (kind, amount, is_all_in) = parse_action(rest, street_bet).ok_or_else(|| error("Expected an action"))?;
+        if is_live(kind) {
+            *street_bets.entry(player_id).or_insert(0) += amount;
+        }
+        actions.push(ActionRecord::new(street, player_id, kind, amount, is_all_in));
+    }
+
+    let post_amount = |kind: ActionKind| actions.iter().filter(|action| *action.kind() == kind).map(|action| *action.amount()).max();
+    let mut blind_structure = match betting_structure {
+        BettingStructure::FixedLimit { .. } => {
+            let big_blind = post_amount(ActionKind::BigBlind).unwrap_or(low_stake);
+            BlindStructure::new(post_amount(ActionKind::SmallBlind).unwrap_or(big_blind / 2), big_blind)
+        },
+        _ => BlindStructure::new(low_stake, high_stake)
+    };
+    blind_structure.set_ante(post_amount(ActionKind::Ante).unwrap_or(0));
+    blind_structure.set_big_blind_ante(post_amount(ActionKind::BigBlindAnte).unwrap_or(0));
+    blind_structure.set_straddle(post_amount(ActionKind::Straddle));
+
+    // The players dealt in, in the order they act after the flop.
+    seats.sort_by_key(|(seat, ..)| (seat + num_seats - button - 1) % num_seats);
+    let seat_records = seats.into_iter()
+        .map(|(seat, player_id, name, stack)| {
+            SeatRecord::new(seat, player_id, name, stack, hole_cards.remove(&player_id).unwrap_or_default())
+        })
+        .collect::<Vec<SeatRecord>>();
+
+    let mut pot_manager = PotManager::new();
+    for action in actions.iter() {
+        match action.kind() {
+            ActionKind::UncalledBetReturned => pot_manager.return_uncalled_bet(*action.player_id(), *action.amount()),
+            ActionKind::Fold => pot_manager.fold(*action.player_id()),
+            _ => pot_manager.add_contribution(*action.player_id(), *action.amount())
+        }
+    }
+    let mut pots: Vec<PotRecord> = pot_manager.get_pots().iter()
+        .map(|pot| PotRecord::new(pot.get_amount(), pot.get_eligible_players().clone(), BTreeMap::new()))
+        .collect();
+    let mut run_payouts = vec![vec![BTreeMap::<PlayerId, i32>::new(); pots.len()]; run_boards.len()];
+    for (run, player_id, amount, index) in collected {
+        if index >= pots.len() {
+            return Err(ParseError::new(last_line_number, format!("Chips were collected from a pot that does not exist: {}", index)));
+        }
+        *pots[index].payouts.entry(player_id).or_insert(0) += amount;
+        if let Some(run) = run {
+            *run_payouts[run][index].entry(player_id).or_insert(0) += amount;
+        }
+    }
+
+    if let Some(first_board) = run_boards.first() {
+        board = first_board.clone();
+    }
+    if !showdown_order.is_empty() && (board.len() != 5 || run_boards.iter().any(|run_board| run_board.len() != 5)) {
+        return Err(ParseError::new(last_line_number, "Hands were shown without a full board"));
+    }
+    for (_, hole_cards) in shown.iter() {
+        let cards: HashSet<&Card> = hole_cards.iter().chain(board.iter()).collect();
+        if cards.len() != 7 {
+            return Err(ParseError::new(last_line_number, "A card was shown that is also on the board"));
+        }
+    }
+    let showdown = if showdown_order.is_empty() {
+        None
+    } else {
+        let mut hands = Vec::<(PlayerId, Vec<Card>, HandScore)>::new();
+        for player_id in showdown_order.iter() {
+            // Mucked hands are scored from the cards they were dealt.
+            let hole_cards = match shown.iter().find(|(shown_id, _)| shown_id == player_id) {
+                Some((_, hole_cards)) => hole_cards.clone(),
+                None => seat_records.iter()
+                    .find(|seat| seat.player_id() == player_id)
+                    .map(|seat| seat.hole_cards().clone())
+                    .filter(|hole_cards| hole_cards.len() == 2)
+                    .ok_or_else(|| ParseError::new(last_line_number, "A hand was mucked without being dealt"))?
+            };
+            let score = score(&hole_cards, &board);
+            hands.push((*player_id, hole_cards, score));
+        }
+
+        let street = actions.iter().map(|action| *action.street()).max().unwrap_or(Street::Preflop);
+        let is_all_in = showdown_order.iter()
+            .filter(|player_id| !actions.iter().any(|action| action.player_id() == *player_id && *action.is_all_in()))
+            .count() <= 1;
+        let winners = pots.iter()
+            .filter(|pot| pot.eligible_players().len() > 1)
+            .flat_map(|pot| pot.payouts().iter())
+            .filter(|(_, amount)| **amount > 0)
+            .map(|(player_id, _)| *player_id)
+            .collect();
+        let shown_ids: HashSet<PlayerId> = shown.iter().map(|(player_id, _)| *player_id).collect();
+        Some(ShowdownResult::new(street, is_all_in, hands, &winners, |player_id| !shown_ids.contains(player_id)))
+    };
+
+    let mut hand_history = HandHistory::new(game_id, hand_number, num_seats, blind_structure, betting_structure,
+                                               OddChipRule::FirstLeftOfButton, button, seat_records);
+    hand_history.set_start_time(start_time);
+    for action in actions {
+        hand_history.record_action(action);
+    }
+    let runs = run_boards.into_iter()
+        .zip(run_payouts)
+        .map(|(board, payouts)| {
+            let scores = showdown.as_ref().map(|showdown| showdown.score_on(&board)).unwrap_or_default();
+            RunRecord::new(board, payouts, scores)
+        })
+        .collect();
+    hand_history.finish(board, pots, runs, showdown);
+
+    Ok(hand_history)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::game::action::Action;
+    use crate::game::deck::{Deck, StackedDecks};
+    use crate::game::hand_history::MemorySink;
+    use crate::game::player::Player;
+
+    /// Plays a hand where player 2 is all in for less than the others, player 3 folds to a bet on the flop and player
+    /// 1's bet is not called, then the board runs out to a showdown between players 1 and 2.
+    fn side_pot_hand() -> HandHistory {
+        let hole_cards = [
+            Card::new(Suit::Spades, 14), Card::new(Suit::Hearts, 14),
+            Card::new(Suit::Spades, 13), Card::new(Suit::Hearts, 13),
+            Card::new(Suit::Clubs, 2), Card::new(Suit::Diamonds, 7)
+        ];
+        let board = [
+            Card::new(Suit::Diamonds, 13), Card::new(Suit::Clubs, 14), Card::new(Suit::Hearts, 5),
+            Card::new(Suit::Spades, 9), Card::new(Suit::Diamonds, 11)
+        ];
+
+        let mut game = Game::new(7, 10, 100);
+        for (player_id, money) in [(1, 100), (2, 40), (3, 100)] {
+            game.add_player(Player::new(player_id, format!("Player {}", player_id), money));
+        }
+        let sink = MemorySink::new();
+        game.add_history_sink(Box::new(sink.clone()));
+        game.set_deck_source(Box::new(StackedDecks::new(vec![Deck::stacked(hole_cards.iter().chain(board.iter()).cloned().collect())])));
+
+        game.start_hand(false);
+        game.apply_action(3, Action::RaiseTo(60)).unwrap();
+        game.apply_action(1, Action::Call).unwrap();
+        game.apply_action(2, Action::Call).unwrap();
+        game.apply_action(1, Action::Bet(40)).unwrap();
+        game.apply_action(3, Action::Fold).unwrap();
+
+        sink.get_hand_histories().pop().unwrap()
+    }
+
+    #[test]
+    fn test_engine_hand_round_trips() {
+        let hand_history = side_pot_hand();
+        let text = write_hand(&hand_history);
+
+        assert!(text.contains("Player 2: calls 30 and is all-in"));
+        assert!(text.contains("*** RIVER *** [Kd Ac 5h 9s] [Jd]"));
+        assert!(text.contains("Uncalled bet (40) returned to Player 1"));
+        assert!(text.contains("Player 2: shows [Ks Kh] (three of a kind, Kings)"));
+        assert!(text.contains("Player 1 collected 40 from side pot"));
+        assert!(text.contains("Player 1 collected 120 from main pot"));
+        assert!(text.contains("Total pot 160 Main pot 120. Side pot 40. | Rake 0"));
+
+        assert_eq!(parse_hands(&text).unwrap(), vec![hand_history]);
+    }
+
+    #[test]
+    fn test_mucked_hand_round_trips() {
+        let cards = vec![
+            Card::new(Suit::Spades, 14), Card::new(Suit::Hearts, 14),
+            Card::new(Suit::Spades, 13), Card::new(Suit::Hearts, 13),
+            Card::new(Suit::Clubs, 2), Card::new(Suit::Diamonds, 7),
+            Card::new(Suit::Diamonds, 14), Card::new(Suit::Clubs, 14), Card::new(Suit::Hearts, 5),
+            Card::new(Suit::Spades, 9), Card::new(Suit::Diamonds, 11)
+        ];
+        let mut game = Game::new(8, 10, 100);
+        for player_id in 1..=3 {
+            game.add_player(Player::new(player_id, format!("Player {}", player_id), 100));
+        }
+        game.set_deck_source(Box::new(StackedDecks::new(vec![Deck::stacked(cards)])));
+
+        game.start_hand(false);
+        game.apply_action(3, Action::Fold).unwrap();
+        game.apply_action(1, Action::Call).unwrap();
+        while let Some(player_id) = game.next_to_act() {
+            game.apply_action(player_id, Action::Check).unwrap();
+        }
+        let hand_history = game.get_hand_history().unwrap().clone();
+        let text = write_hand(&hand_history);
+
+        assert!(text.contains("Player 1: shows [As Ah] (four of a kind, Aces)"));
+        assert!(text.contains("Player 2: mucks hand"));
+        assert!(text.contains("Seat 2: Player 2 (big blind) mucked [Ks Kh]"));
+
+        assert_eq!(parse_hands(&text).unwrap(), vec![hand_history]);
+    }
+
+    #[test]
+    fn test_hand_run_twice_round_trips() {
+        let cards = vec![
+            Card::new(Suit::Spades, 14), Card::new(Suit::Hearts, 14),
+            Card::new(Suit::Spades, 13), Card::new(Suit::Hearts, 13),
+            Card::new(Suit::Clubs, 2), Card::new(Suit::Diamonds, 7), Card::new(Suit::Hearts, 5),
+            Card::new(Suit::Diamonds, 14), Card::new(Suit::Spades, 9),
+            Card::new(Suit::Diamonds, 13), Card::new(Suit::Clubs, 9)
+        ];
+        let mut game = Game::new(9, 10, 100);
+        for player_id in 1..=2 {
+            game.add_player(Player::new(player_id, format!("Player {}", player_id), 100));
+        }
+        game.set_max_runs(2);
+        game.set_deck_source(Box::new(StackedDecks::new(vec![Deck::stacked(cards)])));
+
+        // The players are all in on the flop, so only the turn and river are run twice.
+        game.start_hand(false);
+        game.apply_action(2, Action::Call).unwrap();
+        game.apply_action(1, Action::Check).unwrap();
+        game.apply_action(1, Action::AllIn).unwrap();
+        game.apply_action(2, Action::Call).unwrap();
+        game.choose_run_count(1, 2).unwrap();
+        game.choose_run_count(2, 2).unwrap();
+        let hand_history = game.get_hand_history().unwrap().clone();
+        let text = write_hand(&hand_history);
+
+        assert!(text.contains("*** FLOP *** [2c 7d 5h]"));
+        assert!(text.contains("*** SECOND TURN *** [2c 7d 5h] [Kd]"));
+        assert!(text.contains("*** SECOND SHOW DOWN ***\nPlayer 1: shows [As Ah] (a pair of Aces)"));
+        assert!(text.contains("Hand was run twice\nFIRST Board [2c 7d 5h Ad 9s]"));
+
+        assert_eq!(parse_hands(&text).unwrap(), vec![hand_history]);
+    }
+
+    #[test]
+    fn test_several_hands_round_trip() {
+        let hand_history = side_pot_hand();
+        let text = write_hands(&[hand_history.clone(), hand_history.clone()]);
+
+        let mut sink = PokerStarsSink::new(Vec::<u8>::new());
+        sink.record(&hand_history).unwrap();
+        sink.record(&hand_history).unwrap();
+        let written = String::from_utf8(sink.into_inner()).unwrap();
+        assert_eq!(written.trim_end(), text);
+
+        let parsed = parse_hands(&written).unwrap();
+        assert_eq!(parsed, vec![hand_history.clone(), hand_history]);
+        assert_eq!(write_hands(&parsed), text);
+    }
+
+    #[test]
+    fn test_time_round_trips() {
+        assert_eq!(format_time(0), "1970/01/01 00:00:00");
+        assert_eq!(format_time(1709316309), "2024/03/01 18:05:09");
+        assert_eq!(parse_time("2024/03/01 18:05:09"), Some(1709316309));
+
+        // Dates and times that are out of range are rejected rather than overflowing.
+        assert_eq!(parse_time("2024/13/01 18:05:09"), None);
+        assert_eq!(parse_time("2024/03/01 24:05:09"), None);
+        assert_eq!(parse_time("99999999999999999/03/01 18:05:09"), None);
+        assert_eq!(parse_time("2024/03/01 99999999999999999:00:00"), None);
+    }
+
+    #[test]
+    fn test_describe_hands() {
+        assert_eq!(describe(&HandScore::new(HandType::TwoPair, vec![14, 13, 2])), "two pair, Aces and Kings");
+        assert_eq!(describe(&HandScore::new(HandType::Straight, vec![5])), "a straight, Ace to Five");
+        assert_eq!(describe(&HandScore::new(HandType::FullHouse, vec![6, 2])), "a full house, Sixes full of Deuces");
+    }
+
+    #[test]
+    fn test_parse_reports_the_bad_line() {
+        let text = "PokerStars Hand #1: Hold'em No Limit (5/10) - 2024/03/01 18:05:09 UTC\nTable 'x' 10-max Seat #1 is the button";
+        assert_eq!(parse_hands(text).unwrap_err().get_line_number(), 2);
+
+        let text = "PokerStars Hand #1: Hold'em No Limit (5/10) - 2024/03/01 99999999999999999:00:00 UTC";
+        assert_eq!(parse_hands(text).unwrap_err().get_line_number(), 1);
+    }
+}
@@ -0,0 +1,511 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::player::PlayerId;
+
+
+/// The betting rounds of a hand of Texas Hold'em, in the order they are played.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Street {
+    Preflop,
+    Flop,
+    Turn,
+    River,
+    /// The betting is over. Either the remaining players show their cards or everyone else folded.
+    Showdown
+}
+
+impl Street {
+    /// Returns the street that comes after this one. The street after `Showdown` is `Showdown`.
+    pub fn next(self) -> Street {
+        match self {
+            Street::Preflop => Street::Flop,
+            Street::Flop => Street::Turn,
+            Street::Turn => Street::River,
+            Street::River | Street::Showdown => Street::Showdown
+        }
+    }
+
+    /// Returns the number of community cards that are dealt at the start of this street.
+    pub fn get_num_cards_dealt(self) -> usize {
+        match self {
+            Street::Flop => 3,
+            Street::Turn | Street::River => 1,
+            Street::Preflop | Street::Showdown => 0
+        }
+    }
+}
+
+impl std::fmt::Display for Street {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Street::Preflop => write!(f, "Preflop"),
+            Street::Flop => write!(f, "Flop"),
+            Street::Turn => write!(f, "Turn"),
+            Street::River => write!(f, "River"),
+            Street::Showdown => write!(f, "Showdown")
+        }
+    }
+}
+
+
+/// The betting state of the hand being played.
+///
+/// A `HandState` does not block or ask anyone for anything. The game records each action as it arrives and asks
+/// `next_to_act` who should go next, so a hand can be advanced one action at a time.
+///
+/// # Fields
+///
+/// * `street`: The current `Street`.
+/// * `seats`: The players dealt into the hand, in the order they act after the flop.
+/// * `folded`: The players who have folded.
+/// * `all_in`: The players who have no chips left to bet.
+/// * `min_bet`: The smallest bet on this street, which is also the smallest raise.
+/// * `current_bet`: The most any player has bet on this street.
+/// * `full_bet`: The current bet as of the last bet or raise that was big enough to reopen the betting.
+/// * `min_raise`: The size of the last full bet or raise. The next raise must be at least this much more.
+/// * `street_contributions`: A `HashMap` that maps `PlayerId`s to the amount they have bet on this street.
+/// * `num_raises`: The number of full raises made on this street, not counting the bet that opened the betting.
+/// * `acted`: The players who have acted since the betting was last opened or fully raised.
+/// * `action_index`: The index in `seats` to start looking for the next player to act from.
+/// * `last_action_street`: The last street any player acted on.
+/// * `last_aggressor`: The last player to bet or raise, and the street they did it on.
+#[derive(Debug, Clone)]
+pub struct HandState {
+    street: Street,
+    seats: Vec<PlayerId>,
+    folded: HashSet<PlayerId>,
+    all_in: HashSet<PlayerId>,
+    min_bet: i32,
+    current_bet: i32,
+    full_bet: i32,
+    min_raise: i32,
+    street_contributions: HashMap<PlayerId, i32>,
+    num_raises: u32,
+    acted: HashSet<PlayerId>,
+    action_index: usize,
+    last_action_street: Street,
+    last_aggressor: Option<(Street, PlayerId)>
+}
+
+impl HandState {
+    /// Creates the state for a new hand, starting preflop.
+    ///
+    /// # Parameters
+    ///
+    /// * `seats`: The players dealt into the hand, in the order they act after the flop.
+    /// * `first_to_act`: The index in `seats` of the first player to act preflop.
+    /// * `min_bet`: The smallest bet preflop, which is normally the big blind.
+    pub(crate) fn new(seats: Vec<PlayerId>, first_to_act: usize, min_bet: i32) -> HandState {
+        HandState {
+            street: Street::Preflop,
+            seats,
+            folded: HashSet::new(),
+            all_in: HashSet::new(),
+            min_bet,
+            current_bet: 0,
+            full_bet: 0,
+            min_raise: min_bet,
+            street_contributions: HashMap::new(),
+            num_raises: 0,
+            acted: HashSet::new(),
+            action_index: first_to_act,
+            last_action_street: Street::Preflop,
+            last_aggressor: None
+        }
+    }
+
+    pub fn get_street(&self) -> Street {
+        self.street
+    }
+
+    pub fn get_seats(&self) -> &Vec<PlayerId> {
+        &self.seats
+    }
+
+    pub fn get_current_bet(&self) -> i32 {
+        self.current_bet
+    }
+
+    /// Returns the smallest amount a player can open the betting with, unless they are going all in for less.
+    pub fn get_min_bet(&self) -> i32 {
+        self.min_bet
+    }
+
+    /// Returns the number of full raises made on this street, not counting the bet that opened the betting.
+    pub fn get_num_raises(&self) -> u32 {
+        self.num_raises
+    }
+
+    /// Returns the smallest total a player can raise to, unless they are going all in for less.
+    ///
+    /// # Notes
+    ///
+    /// A raise must be at least as big as the last full bet or raise on this street, and never smaller than the
+    /// minimum bet. A raise that is smaller than this because the player is all in does not change the minimum.
+    pub fn get_min_raise_to(&self) -> i32 {
+        self.current_bet + self.min_raise
+    }
+
+    /// Returns whether `player_id` is allowed to raise if they put in more than the current bet.
+    ///
+    /// A player who has already acted on this street can only raise again after a full bet or raise. If the bet was
+    /// only raised by an all in that was too small to be a full raise, they can only call or fold.
+    pub fn can_raise(&self, player_id: &PlayerId) -> bool {
+        !self.acted.contains(player_id)
+    }
+
+    pub fn get_street_contribution(&self, player_id: &PlayerId) -> i32 {
+        *self.street_contributions.get(player_id).unwrap_or(&0)
+    }
+
+    pub fn has_folded(&self, player_id: &PlayerId) -> bool {
+        self.folded.contains(player_id)
+    }
+
+    pub fn is_all_in(&self, player_id: &PlayerId) -> bool {
+        self.all_in.contains(player_id)
+    }
+
+    /// Returns whether `player_id` was dealt into the hand and has not folded.
+    pub fn is_in_hand(&self, player_id: &PlayerId) -> bool {
+        self.seats.contains(player_id) && !self.has_folded(player_id)
+    }
+
+    /// Returns the players who have not folded, in seat order.
+    pub fn get_players_in_hand(&self) -> Vec<PlayerId> {
+        self.seats.iter().filter(|player_id| !self.has_folded(player_id)).cloned().collect()
+    }
+
+    /// Returns the player whose turn it is, or `None` if the betting round is over.
+    ///
+    /// # Notes
+    ///
+    /// The betting round is over once every player who can still bet has acted since the last bet or raise and has
+    /// matched the current bet. A player who is the only one left with chips does not act unless they have a bet to
+    /// call, and nobody acts once everyone else has folded.
+    pub fn next_to_act(&self) -> Option<PlayerId> {
+        if self.street == Street::Showdown || self.get_players_in_hand().len() <= 1 {
+            return None;
+        }
+
+        let num_can_bet = self.seats.iter().filter(|player_id| self.can_bet(player_id)).count();
+
+        (0..self.seats.len())
+            .map(|offset| self.seats[(self.action_index + offset) % self.seats.len()])
+            .find(|player_id| {
+                self.can_bet(player_id) && (
+                    self.get_street_contribution(player_id) < self.current_bet ||
+                    (!self.acted.contains(player_id) && num_can_bet >= 2)
+                )
+            })
+    }
+
+    /// Returns whether the hand has gone as far as it can, either to showdown or because everyone else folded.
+    pub fn is_complete(&self) -> bool {
+        self.street == Street::Showdown
+    }
+
+    /// Returns whether the rest of the board will be dealt without any more betting, because at most one player still
+    /// in the hand has chips left to bet with.
+    pub fn is_runout(&self) -> bool {
+        self.street < Street::River
+            && self.get_players_in_hand().len() > 1
+            && self.seats.iter().filter(|player_id| self.can_bet(player_id)).count() <= 1
+    }
+
+    /// Returns the last street any player checked, called, bet, raised or folded on.
+    pub fn get_last_action_street(&self) -> Street {
+        self.last_action_street
+    }
+
+    /// Returns the last player to bet or raise on the last street anyone acted on, or `None` if nobody did. Blinds
+    /// do not count.
+    pub fn get_last_aggressor(&self) -> Option<PlayerId> {
+        self.last_aggressor
+            .filter(|(street, _)| *street == self.last_action_street)
+            .map(|(_, player_id)| player_id)
+    }
+
+    /// Returns the player with the biggest bet on this street and the part of it that nobody else matched, or `None`
+    /// if the bet was matched. Players who folded count as having matched what they put in before folding, and the
+    /// chips of a player who folded the biggest bet stay in the pot.
+    pub fn get_uncalled_bet(&self) -> Option<(PlayerId, i32)> {
+        let (&player_id, &biggest) = self.street_contributions.iter().max_by_key(|(&player_id, &amount)| (amount, player_id))?;
+        if self.has_folded(&player_id) {
+            return None;
+        }
+        let next_biggest = self.street_contributions.iter()
+            .filter(|(&p, _)| p != player_id)
+            .map(|(_, &amount)| amount)
+            .max()
+            .unwrap_or(0);
+
+        if biggest > next_biggest {
+            Some((player_id, biggest - next_biggest))
+        } else {
+            None
+        }
+    }
+
+    fn can_bet(&self, player_id: &PlayerId) -> bool {
+        !self.has_folded(player_id) && !self.is_all_in(player_id)
+    }
+
+    /// Puts `amount` in front of `player_id` without it counting as their action, such as when posting a blind.
+    ///
+    /// The next raise must be at least as big as the largest blind, so a straddle raises the minimum raise.
+    pub(crate) fn post(&mut self, player_id: PlayerId, amount: i32, is_all_in: bool) {
+        self.street_contributions.insert(player_id, amount);
+        self.current_bet = self.current_bet.max(amount);
+        self.full_bet = self.full_bet.max(amount);
+        self.min_raise = self.min_raise.max(amount);
+        if is_all_in {
+            self.all_in.insert(player_id);
+        }
+    }
+
+    /// Records that `player_id` has no chips left, such as after posting an ante with the last of their chips.
+    pub(crate) fn set_all_in(&mut self, player_id: PlayerId) {
+        self.all_in.insert(player_id);
+    }
+
+    /// Records that `player_id` checked, called, bet or raised so that they have bet `contribution` on this street.
+    ///
+    /// A full bet or raise reopens the betting, so everyone else has to act again. An all in that raises by less than
+    /// a full raise only makes the other players call the extra chips.
+    pub(crate) fn record_bet(&mut self, player_id: PlayerId, contribution: i32, is_all_in: bool) {
+        if contribution >= self.full_bet + self.min_raise {
+            if self.current_bet > 0 {
+                self.num_raises += 1;
+            }
+            self.min_raise = self.min_raise.max(contribution - self.current_bet);
+            self.full_bet = contribution;
+            self.acted.clear();
+        }
+
+        if contribution > self.current_bet {
+            self.last_aggressor = Some((self.street, player_id));
+        }
+        self.last_action_street = self.street;
+
+        self.street_contributions.insert(player_id, contribution);
+        self.current_bet = self.current_bet.max(contribution);
+        if is_all_in {
+            self.all_in.insert(player_id);
+        }
+        self.acted.insert(player_id);
+        self.move_action_past(player_id);
+    }
+
+    /// Gives `amount` of `player_id`'s bet on this street back to them. They are no longer all in, since they have
+    /// chips again.
+    pub(crate) fn return_uncalled_bet(&mut self, player_id: PlayerId, amount: i32) {
+        let contribution = self.get_street_contribution(&player_id) - amount;
+        self.street_contributions.insert(player_id, contribution);
+        self.current_bet = self.street_contributions.values().cloned().max().unwrap_or(0);
+        self.all_in.remove(&player_id);
+    }
+
+    /// Records that `player_id` folded.
+    pub(crate) fn record_fold(&mut self, player_id: PlayerId) {
+        self.folded.insert(player_id);
+        self.last_action_street = self.street;
+        self.move_action_past(player_id);
+    }
+
+    /// Takes `player_id` out of the hand without it being their turn, such as when they leave the table.
+    pub(crate) fn remove(&mut self, player_id: PlayerId) {
+        self.folded.insert(player_id);
+        self.last_action_street = self.street;
+    }
+
+    fn move_action_past(&mut self, player_id: PlayerId) {
+        let index = self.seats.iter().position(|&p| p == player_id).expect("Expected the player to be seated");
+        self.action_index = (index + 1) % self.seats.len();
+    }
+
+    /// Moves on to the next street, where the smallest bet is `min_bet`. The first player after the button acts first.
+    pub(crate) fn next_street(&mut self, min_bet: i32) {
+        self.street = self.street.next();
+        self.min_bet = min_bet;
+        self.current_bet = 0;
+        self.full_bet = 0;
+        self.min_raise = min_bet;
+        self.street_contributions.clear();
+        self.num_raises = 0;
+        self.acted.clear();
+        self.action_index = 0;
+    }
+
+    /// Ends the betting, for example because everyone else folded.
+    pub(crate) fn finish(&mut self) {
+        self.street = Street::Showdown;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Three players with the blinds posted. Player 3 is first to act.
+    fn preflop() -> HandState {
+        let mut hand_state = HandState::new(vec![1, 2, 3], 2, 10);
+        hand_state.post(1, 5, false);
+        hand_state.post(2, 10, false);
+        hand_state
+    }
+
+    #[test]
+    fn test_big_blind_gets_option() {
+        let mut hand_state = preflop();
+
+        assert_eq!(hand_state.next_to_act(), Some(3));
+        hand_state.record_bet(3, 10, false);
+        assert_eq!(hand_state.next_to_act(), Some(1));
+        hand_state.record_bet(1, 10, false);
+        assert_eq!(hand_state.next_to_act(), Some(2));
+        hand_state.record_bet(2, 10, false);
+        assert_eq!(hand_state.next_to_act(), None);
+    }
+
+    #[test]
+    fn test_raise_reopens_action() {
+        let mut hand_state = preflop();
+        hand_state.record_bet(3, 10, false);
+        hand_state.record_bet(1, 10, false);
+        hand_state.record_bet(2, 30, false);
+
+        assert_eq!(hand_state.next_to_act(), Some(3));
+        hand_state.record_bet(3, 30, false);
+        assert_eq!(hand_state.next_to_act(), Some(1));
+        hand_state.record_fold(1);
+        assert_eq!(hand_state.next_to_act(), None);
+    }
+
+    #[test]
+    fn test_last_aggressor_is_from_the_last_street_played() {
+        let mut hand_state = preflop();
+        hand_state.record_bet(3, 10, false);
+        hand_state.record_bet(1, 10, false);
+        assert_eq!(hand_state.get_last_aggressor(), None);
+        hand_state.record_bet(2, 30, false);
+        hand_state.record_bet(3, 30, false);
+        hand_state.record_fold(1);
+        assert_eq!(hand_state.get_last_aggressor(), Some(2));
+
+        // Nobody acts on the flop yet, so the preflop raise still counts.
+        hand_state.next_street(10);
+        assert_eq!(hand_state.get_last_aggressor(), Some(2));
+        hand_state.record_bet(2, 0, false);
+        assert_eq!(hand_state.get_last_action_street(), Street::Flop);
+        assert_eq!(hand_state.get_last_aggressor(), None);
+    }
+
+    #[test]
+    fn test_uncalled_bet() {
+        let mut hand_state = preflop();
+        hand_state.record_bet(3, 100, true);
+        hand_state.record_bet(1, 40, true);
+        hand_state.record_fold(2);
+        assert_eq!(hand_state.get_uncalled_bet(), Some((3, 60)));
+
+        hand_state.return_uncalled_bet(3, 60);
+        assert_eq!(hand_state.get_street_contribution(&3), 40);
+        assert_eq!(hand_state.get_current_bet(), 40);
+        assert!(!hand_state.is_all_in(&3));
+        assert_eq!(hand_state.get_uncalled_bet(), None);
+    }
+
+    #[test]
+    fn test_postflop_starts_after_button() {
+        let mut hand_state = preflop();
+        hand_state.record_bet(3, 10, false);
+        hand_state.record_bet(1, 10, false);
+        hand_state.record_bet(2, 10, false);
+        hand_state.next_street(10);
+
+        assert_eq!(hand_state.get_street(), Street::Flop);
+        assert_eq!(hand_state.get_current_bet(), 0);
+        assert_eq!(hand_state.next_to_act(), Some(1));
+    }
+
+    #[test]
+    fn test_no_action_when_only_one_player_can_bet() {
+        let mut hand_state = preflop();
+        hand_state.record_bet(3, 100, true);
+        hand_state.record_fold(1);
+
+        // Player 2 still has to call the all in.
+        assert_eq!(hand_state.next_to_act(), Some(2));
+        hand_state.record_bet(2, 100, false);
+        assert_eq!(hand_state.next_to_act(), None);
+
+        // Nobody is left to bet against player 2 on later streets.
+        hand_state.next_street(10);
+        assert_eq!(hand_state.next_to_act(), None);
+    }
+
+    #[test]
+    fn test_no_action_after_everyone_folds() {
+        let mut hand_state = preflop();
+        hand_state.record_fold(3);
+        hand_state.record_fold(1);
+
+        assert_eq!(hand_state.next_to_act(), None);
+        assert_eq!(hand_state.get_players_in_hand(), vec![2]);
+    }
+
+    #[test]
+    fn test_min_raise_follows_last_raise() {
+        let mut hand_state = preflop();
+        assert_eq!(hand_state.get_min_raise_to(), 20);
+
+        hand_state.record_bet(3, 35, false);
+        assert_eq!(hand_state.get_min_raise_to(), 60);
+
+        hand_state.record_bet(1, 60, false);
+        assert_eq!(hand_state.get_min_raise_to(), 85);
+
+        hand_state.next_street(10);
+        assert_eq!(hand_state.get_min_bet(), 10);
+        assert_eq!(hand_state.get_min_raise_to(), 10);
+    }
+
+    #[test]
+    fn test_short_all_in_does_not_reopen_betting() {
+        let mut hand_state = HandState::new(vec![1, 2, 3], 0, 10);
+        hand_state.next_street(10);
+
+        hand_state.record_bet(1, 100, false);
+        hand_state.record_bet(2, 150, true);
+
+        // Player 3 has not acted yet, so they can raise, and the raise must be a full 100 more than the all in.
+        assert!(hand_state.can_raise(&3));
+        assert_eq!(hand_state.get_min_raise_to(), 250);
+        hand_state.record_bet(3, 150, false);
+
+        // Player 1 has to call the extra 50, but the all in was not a full raise so they cannot raise again.
+        assert_eq!(hand_state.next_to_act(), Some(1));
+        assert!(!hand_state.can_raise(&1));
+    }
+
+    #[test]
+    fn test_short_all_ins_that_add_up_to_a_full_raise_reopen_betting() {
+        let mut hand_state = HandState::new(vec![1, 2, 3, 4], 0, 10);
+        hand_state.next_street(10);
+
+        hand_state.record_bet(1, 100, false);
+        hand_state.record_bet(2, 150, true);
+        hand_state.record_bet(3, 200, true);
+
+        assert_eq!(hand_state.next_to_act(), Some(4));
+        assert!(hand_state.can_raise(&4));
+        hand_state.record_bet(4, 200, false);
+
+        assert_eq!(hand_state.next_to_act(), Some(1));
+        assert!(hand_state.can_raise(&1));
+    }
+}
@@ -0,0 +1,356 @@
+use std::cmp::min;
+use std::collections::{HashMap, HashSet};
+
+use crate::game::player::PlayerId;
+
+
+/// Represents the main pot or a side pot.
+///
+/// # Fields
+///
+/// * `amount`: An `i32` that represents the number of chips in the pot.
+/// * `eligible_players`: The players that can win the pot, sorted by `PlayerId`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pot {
+    amount: i32,
+    eligible_players: Vec<PlayerId>
+}
+
+impl Pot {
+    pub fn get_amount(&self) -> i32 {
+        self.amount
+    }
+
+    pub fn get_eligible_players(&self) -> &Vec<PlayerId> {
+        &self.eligible_players
+    }
+}
+
+
+/// Keeps track of how much each player has put into the pot during a hand and splits it into a main pot and side pots.
+///
+/// # Fields
+///
+/// * `contributions`: A `HashMap` that maps `PlayerId`s to the total amount they have put in this hand.
+/// * `folded`: A `HashSet` of the `PlayerId`s that have folded. Their chips stay in the pot, but they cannot win it.
+#[derive(Debug, Clone, Default)]
+pub struct PotManager {
+    contributions: HashMap<PlayerId, i32>,
+    folded: HashSet<PlayerId>
+}
+
+impl PotManager {
+    pub fn new() -> PotManager {
+        PotManager {
+            contributions: HashMap::new(),
+            folded: HashSet::new()
+        }
+    }
+
+    /// Adds `amount` to the total that `player_id` has put in this hand.
+    pub fn add_contribution(&mut self, player_id: PlayerId, amount: i32) {
+        *self.contributions.entry(player_id).or_insert(0) += amount;
+    }
+
+    /// Takes an uncalled bet of `amount` back out of what `player_id` has put in this hand.
+    pub fn return_uncalled_bet(&mut self, player_id: PlayerId, amount: i32) {
+        *self.contributions.entry(player_id).or_insert(0) -= amount;
+    }
+
+    /// Marks `player_id` as folded, so they can no longer win any pot.
+    pub fn fold(&mut self, player_id: PlayerId) {
+        self.folded.insert(player_id);
+    }
+
+    pub fn get_contribution(&self, player_id: &PlayerId) -> i32 {
+        *self.contributions.get(player_id).unwrap_or(&0)
+    }
+
+    pub fn get_contributions(&self) -> &HashMap<PlayerId, i32> {
+        &self.contributions
+    }
+
+    pub fn has_folded(&self, player_id: &PlayerId) -> bool {
+        self.folded.contains(player_id)
+    }
+
+    /// Returns the total number of chips put in this hand.
+    pub fn get_total(&self) -> i32 {
+        self.contributions.values().sum()
+    }
+
+    pub fn clear(&mut self) {
+        self.contributions.clear();
+        self.folded.clear();
+    }
+
+    /// Splits the chips put in this hand into the main pot and side pots.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Pot>` - The main pot first, followed by each side pot in the order they were created.
+    ///
+    /// # Notes
+    ///
+    /// Every distinct amount put in by a player who has not folded closes a pot. A pot holds what every player,
+    /// folded or not, put in between the previous amount and this one, and it can only be won by the players who have
+    /// not folded and put in at least this amount. Chips that folded players put in above the largest amount go into
+    /// the last pot.
+    pub fn get_pots(&self) -> Vec<Pot> {
+        let mut levels: Vec<i32> = self.contributions.iter()
+            .filter(|(player_id, &amount)| !self.folded.contains(player_id) && amount > 0)
+            .map(|(_, &amount)| amount)
+            .collect();
+        levels.sort();
+        levels.dedup();
+
+        let mut pots = Vec::<Pot>::new();
+        let mut prev_level = 0;
+
+        for (i, &level) in levels.iter().enumerate() {
+            let is_last = i == levels.len() - 1;
+
+            let amount: i32 = self.contributions.values()
+                .map(|&contribution| {
+                    let upper = if is_last { contribution } else { min(contribution, level) };
+                    upper - min(contribution, prev_level)
+                })
+                .sum();
+
+            let mut eligible_players: Vec<PlayerId> = self.contributions.iter()
+                .filter(|(player_id, &contribution)| !self.folded.contains(player_id) && contribution >= level)
+                .map(|(&player_id, _)| player_id)
+                .collect();
+            eligible_players.sort();
+
+            pots.push(Pot { amount, eligible_players });
+            prev_level = level;
+        }
+
+        pots
+    }
+
+    /// Works out how many chips each player wins at showdown.
+    ///
+    /// # Parameters
+    ///
+    /// * `rankings`: The players still in the hand grouped by hand strength, best hand first. Players in the same group
+    ///   have hands of equal value.
+    /// * `odd_chip_order`: The order in which the winners of a split pot receive the chips left over after splitting.
+    ///   Winners that do not appear in it receive odd chips last, by `PlayerId`.
+    ///
+    /// # Returns
+    ///
+    /// * `HashMap<PlayerId, i32>` - The chips won by each player who won at least one chip.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if a pot has no eligible player in `rankings`.
+    pub fn get_payouts(&self, rankings: &[Vec<PlayerId>], odd_chip_order: &[PlayerId]) -> HashMap<PlayerId, i32> {
+        let mut payouts = HashMap::<PlayerId, i32>::new();
+
+        for pot_payouts in self.get_pot_payouts(rankings, odd_chip_order) {
+            for (player_id, amount) in pot_payouts {
+                *payouts.entry(player_id).or_insert(0) += amount;
+            }
+        }
+
+        payouts
+    }
+
+    /// Works out how many chips each player wins from each pot at showdown. The parameters are the same as
+    /// `get_payouts`.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<HashMap<PlayerId, i32>>` - The chips won by each winner of each pot, in the order of `get_pots`.
+    pub fn get_pot_payouts(&self, rankings: &[Vec<PlayerId>], odd_chip_order: &[PlayerId]) -> Vec<HashMap<PlayerId, i32>> {
+        self.get_run_payouts(&[rankings], odd_chip_order).remove(0)
+    }
+
+    /// Works out how many chips each player wins from each pot when the board is run more than once.
+    ///
+    /// # Parameters
+    ///
+    /// * `run_rankings`: The rankings of the players on each run's board, in the same form as for `get_payouts`.
+    /// * `odd_chip_order`: The same as for `get_payouts`.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<HashMap<PlayerId, i32>>>` - For each run, the chips won by each winner of each pot, in the order of
+    ///   `get_pots`.
+    ///
+    /// # Notes
+    ///
+    /// Each pot is split into one portion per run, and each portion goes to the best hand on its run's board. If a pot
+    /// cannot be split evenly between the runs, the earlier runs get one chip more.
+    pub fn get_run_payouts(&self, run_rankings: &[&[Vec<PlayerId>]], odd_chip_order: &[PlayerId]) -> Vec<Vec<HashMap<PlayerId, i32>>> {
+        let pots = self.get_pots();
+        let num_runs = run_rankings.len() as i32;
+
+        run_rankings.iter()
+            .enumerate()
+            .map(|(run, rankings)| {
+                pots.iter()
+                    .map(|pot| {
+                        let portion = pot.amount / num_runs + if (run as i32) < pot.amount % num_runs { 1 } else { 0 };
+                        Self::split_pot(portion, &pot.eligible_players, rankings, odd_chip_order)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Splits `amount` evenly between the best ranked players in `eligible_players`, handing out the odd chips in
+    /// `odd_chip_order`.
+    fn split_pot(amount: i32, eligible_players: &[PlayerId], rankings: &[Vec<PlayerId>], odd_chip_order: &[PlayerId]) -> HashMap<PlayerId, i32> {
+        let mut payouts = HashMap::<PlayerId, i32>::new();
+
+        let mut winners: Vec<PlayerId> = rankings.iter()
+            .map(|group| group.iter().filter(|player_id| eligible_players.contains(player_id)).cloned().collect::<Vec<PlayerId>>())
+            .find(|group| !group.is_empty())
+            .expect("Expected a ranked player to be eligible for the pot");
+
+        winners.sort_by_key(|player_id| {
+            (odd_chip_order.iter().position(|p| p == player_id).unwrap_or(odd_chip_order.len()), *player_id)
+        });
+
+        let share = amount / winners.len() as i32;
+        let odd_chips = amount % winners.len() as i32;
+
+        for (i, winner) in winners.iter().enumerate() {
+            let odd_chip = if (i as i32) < odd_chips { 1 } else { 0 };
+            payouts.insert(*winner, share + odd_chip);
+        }
+
+        payouts
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn pot_manager(contributions: &[(PlayerId, i32, bool)]) -> PotManager {
+        let mut pot_manager = PotManager::new();
+        for &(player_id, amount, folded) in contributions {
+            pot_manager.add_contribution(player_id, amount);
+            if folded {
+                pot_manager.fold(player_id);
+            }
+        }
+        pot_manager
+    }
+
+    #[test]
+    fn test_single_pot() {
+        let pot_manager = pot_manager(&[(1, 50, false), (2, 50, false), (3, 20, true)]);
+        let pots = pot_manager.get_pots();
+
+        assert_eq!(pots.len(), 1);
+        assert_eq!(pots[0].get_amount(), 120);
+        assert_eq!(pots[0].get_eligible_players(), &vec![1, 2]);
+    }
+
+    #[test]
+    fn test_side_pots_from_all_ins() {
+        let pot_manager = pot_manager(&[(1, 100, false), (2, 30, false), (3, 70, false), (4, 50, true)]);
+        let pots = pot_manager.get_pots();
+
+        assert_eq!(pots, vec![
+            Pot { amount: 120, eligible_players: vec![1, 2, 3] },
+            Pot { amount: 100, eligible_players: vec![1, 3] },
+            Pot { amount: 30, eligible_players: vec![1] },
+        ]);
+    }
+
+    #[test]
+    fn test_returned_bet_is_taken_out_of_the_pots() {
+        let mut pot_manager = pot_manager(&[(1, 100, false), (2, 30, false), (3, 70, true)]);
+        pot_manager.return_uncalled_bet(1, 30);
+
+        assert_eq!(pot_manager.get_contribution(&1), 70);
+        assert_eq!(pot_manager.get_pots(), vec![
+            Pot { amount: 90, eligible_players: vec![1, 2] },
+            Pot { amount: 80, eligible_players: vec![1] },
+        ]);
+    }
+
+    #[test]
+    fn test_payouts_with_side_pots() {
+        let pot_manager = pot_manager(&[(1, 100, false), (2, 30, false), (3, 70, false)]);
+
+        // The short stack has the best hand, so they only win the main pot.
+        let payouts = pot_manager.get_payouts(&[vec![2], vec![3], vec![1]], &[1, 2, 3]);
+        assert_eq!(payouts.get(&2), Some(&90));
+        assert_eq!(payouts.get(&3), Some(&80));
+        assert_eq!(payouts.get(&1), Some(&30));
+    }
+
+    #[test]
+    fn test_split_pot_odd_chips() {
+        let pot_manager = pot_manager(&[(1, 11, false), (2, 11, false), (3, 11, true)]);
+
+        let payouts = pot_manager.get_payouts(&[vec![1, 2]], &[2, 3, 1]);
+        assert_eq!(payouts.get(&2), Some(&17));
+        assert_eq!(payouts.get(&1), Some(&16));
+    }
+
+    #[test]
+    fn test_run_twice_splits_each_pot() {
+        let pot_manager = pot_manager(&[(1, 51, false), (2, 51, false), (3, 11, true)]);
+
+        // Player 1 wins the first run and player 2 the second. The first run gets the odd chip.
+        let run_payouts = pot_manager.get_run_payouts(&[&[vec![1], vec![2]], &[vec![2], vec![1]]], &[1, 2]);
+        assert_eq!(run_payouts, vec![
+            vec![HashMap::from([(1, 57)])],
+            vec![HashMap::from([(2, 56)])]
+        ]);
+    }
+
+    /// A randomised hand: each player's contribution, whether they folded, and a hand strength to rank them by.
+    fn scenarios() -> impl Strategy<Value = Vec<(i32, bool, u8)>> {
+        prop::collection::vec((1..=500i32, any::<bool>(), 0..4u8), 2..=9)
+            .prop_filter("at least one player must not have folded", |players| players.iter().any(|(_, folded, _)| !folded))
+    }
+
+    proptest! {
+        #[test]
+        fn test_payouts_conserve_chips(players in scenarios()) {
+            let contributions: Vec<(PlayerId, i32, bool)> = players.iter()
+                .enumerate()
+                .map(|(i, &(amount, folded, _))| (i as PlayerId, amount, folded))
+                .collect();
+            let pot_manager = pot_manager(&contributions);
+
+            let mut rankings = Vec::<Vec<PlayerId>>::new();
+            for strength in (0..4u8).rev() {
+                let group: Vec<PlayerId> = players.iter()
+                    .enumerate()
+                    .filter(|(_, &(_, folded, player_strength))| !folded && player_strength == strength)
+                    .map(|(i, _)| i as PlayerId)
+                    .collect();
+                if !group.is_empty() {
+                    rankings.push(group);
+                }
+            }
+
+            let pots = pot_manager.get_pots();
+            prop_assert_eq!(pots.iter().map(|pot| pot.get_amount()).sum::<i32>(), pot_manager.get_total());
+
+            let payouts = pot_manager.get_payouts(&rankings, &[]);
+            prop_assert_eq!(payouts.values().sum::<i32>(), pot_manager.get_total());
+
+            for (player_id, _) in payouts.iter() {
+                prop_assert!(!pot_manager.has_folded(player_id));
+            }
+
+            let reversed: Vec<Vec<PlayerId>> = rankings.iter().rev().cloned().collect();
+            let run_payouts = pot_manager.get_run_payouts(&[&rankings, &reversed, &rankings], &[]);
+            prop_assert_eq!(run_payouts.iter().flatten().flat_map(|payouts| payouts.values()).sum::<i32>(), pot_manager.get_total());
+        }
+    }
+}
@@ -0,0 +1,352 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::Game;
+use crate::game::action::{Action, ActionError};
+use crate::game::hand::Card;
+use crate::game::hand_history::{ActionKind, ActionRecord, HandHistory, PotRecord, RunRecord};
+use crate::game::showdown::ShowdownResult;
+
+
+/// Plays a recorded hand again through the engine, one action at a time.
+///
+/// # Fields
+///
+/// * `hand_history`: The `HandHistory` being replayed.
+/// * `game`: The `Game` rebuilt from it with `Game::from_hand_history`.
+/// * `next_action`: The index in the history of the next action to apply.
+///
+/// # Notes
+///
+/// The engine posts the blinds and antes itself, so only the actions after them are applied. After every step the
+/// actions the engine has recorded are checked against the history, so the first action where the two differ is the
+/// one that is reported.
+#[derive(Debug)]
+pub struct Replayer {
+    hand_history: HandHistory,
+    game: Game,
+    next_action: usize
+}
+
+impl Replayer {
+    /// Rebuilds the game of `hand_history` and posts the blinds and antes.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Replayer, ReplayError>` - An error if the engine did not post the same blinds and antes.
+    pub fn new(hand_history: HandHistory) -> Result<Replayer, ReplayError> {
+        let game = Game::from_hand_history(&hand_history);
+        let next_action = game.get_hand_history().map_or(0, |replayed| replayed.actions().len());
+
+        let replayer = Replayer {
+            hand_history,
+            game,
+            next_action
+        };
+        replayer.check_actions()?;
+        Ok(replayer)
+    }
+
+    pub fn get_game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn get_hand_history(&self) -> &HandHistory {
+        &self.hand_history
+    }
+
+    /// Returns the next recorded action to apply, or `None` if every action has been applied.
+    pub fn get_next_action(&self) -> Option<&ActionRecord> {
+        self.hand_history.actions().get(self.next_action)
+    }
+
+    /// Applies the next recorded action.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<bool, ReplayError>` - Whether there are more actions to apply, or an error if the engine rejected
+    ///   the action or recorded something different.
+    ///
+    /// # Notes
+    ///
+    /// A fold by a player who was not next to act is replayed as the player leaving the table, since that is the only
+    /// way the engine folds a hand out of turn.
+    pub fn step(&mut self) -> Result<bool, ReplayError> {
+        let index = self.next_action;
+        let expected = *self.get_next_action().ok_or(ReplayError::NoMoreActions)?;
+        let player_id = *expected.player_id();
+
+        if !self.game.is_hand_in_progress() {
+            return Err(ReplayError::HandEndedEarly { index });
+        }
+
+        if *expected.kind() == ActionKind::Fold && self.game.next_to_act() != Some(player_id) {
+            self.game.remove_player(player_id);
+        } else {
+            let action = match expected.kind() {
+                ActionKind::Fold => Action::Fold,
+                ActionKind::Check => Action::Check,
+                ActionKind::Call => Action::Call,
+                ActionKind::Bet(amount) => Action::Bet(*amount),
+                ActionKind::RaiseTo(amount) => Action::RaiseTo(*amount),
+                _ => return Err(ReplayError::ActionMismatch { index, expected, actual: None })
+            };
+            self.game.apply_action(player_id, action).map_err(|reason| ReplayError::IllegalAction { index, reason })?;
+        }
+
+        // The engine records returned uncalled bets itself, so skip past whatever it recorded.
+        self.next_action = self.game.get_hand_history().map_or(index + 1, |replayed| replayed.actions().len().max(index + 1));
+        self.check_actions()?;
+        Ok(self.next_action < self.hand_history.actions().len())
+    }
+
+    /// Applies the rest of the recorded actions and checks that the engine ended the hand the same way.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<HandHistory, ReplayError>` - The `HandHistory` the engine recorded while replaying, or the first
+    ///   difference from the recorded hand.
+    pub fn finish(mut self) -> Result<HandHistory, ReplayError> {
+        while self.get_next_action().is_some() {
+            self.step()?;
+        }
+
+        if self.game.is_hand_in_progress() {
+            return Err(ReplayError::HandNotOver);
+        }
+
+        let replayed = self.game.get_hand_history().expect("Expected the replayed hand to have a history").clone();
+        if replayed.board() != self.hand_history.board() {
+            return Err(ReplayError::BoardMismatch { expected: self.hand_history.board().clone(), actual: replayed.board().clone() });
+        }
+        if replayed.pots() != self.hand_history.pots() {
+            return Err(ReplayError::PotMismatch { expected: self.hand_history.pots().clone(), actual: replayed.pots().clone() });
+        }
+        if replayed.runs() != self.hand_history.runs() {
+            return Err(ReplayError::RunMismatch { expected: self.hand_history.runs().clone(), actual: replayed.runs().clone() });
+        }
+        if replayed.showdown() != self.hand_history.showdown() {
+            return Err(ReplayError::ShowdownMismatch {
+                expected: self.hand_history.showdown().clone(),
+                actual: replayed.showdown().clone()
+            });
+        }
+
+        Ok(replayed)
+    }
+
+    /// Checks that every action the engine has recorded so far matches the history.
+    fn check_actions(&self) -> Result<(), ReplayError> {
+        let replayed = self.game.get_hand_history().expect("Expected the replayed hand to have a history").actions();
+        let expected = self.hand_history.actions();
+
+        for (index, actual) in replayed.iter().enumerate() {
+            if expected.get(index) != Some(actual) {
+                return Err(ReplayError::ActionMismatch { index, expected: *expected.get(index).unwrap_or(actual), actual: Some(*actual) });
+            }
+        }
+        if replayed.len() < self.next_action {
+            let index = replayed.len();
+            return Err(ReplayError::ActionMismatch { index, expected: expected[index], actual: None });
+        }
+
+        Ok(())
+    }
+}
+
+
+/// Replays `hand_history` through the engine and checks that it produces the same actions, board, pots, payouts and
+/// showdown.
+pub fn replay(hand_history: &HandHistory) -> Result<(), ReplayError> {
+    Replayer::new(hand_history.clone())?.finish().map(|_| ())
+}
+
+
+/// Replays every hand in a file of JSON lines written by `JsonLinesSink`.
+///
+/// # Returns
+///
+/// * `std::io::Result<Vec<(u64, ReplayError)>>` - The hand number and error of every hand that did not replay the
+///   same, or an error if the file could not be read.
+pub fn replay_file(path: impl AsRef<Path>) -> std::io::Result<Vec<(u64, ReplayError)>> {
+    let mut failures = Vec::<(u64, ReplayError)>::new();
+
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let hand_history: HandHistory = serde_json::from_str(&line)?;
+        if let Err(error) = replay(&hand_history) {
+            failures.push((*hand_history.hand_number(), error));
+        }
+    }
+
+    Ok(failures)
+}
+
+
+/// The ways a replayed hand can differ from its history.
+///
+/// `index` is the position of the action in `HandHistory::actions`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "error")]
+pub enum ReplayError {
+    /// The engine rejected a recorded action.
+    IllegalAction { index: usize, reason: ActionError },
+    /// The engine recorded a different action, or no action, where the history has `expected`.
+    ActionMismatch { index: usize, expected: ActionRecord, actual: Option<ActionRecord> },
+    /// The hand ended before every recorded action was applied.
+    HandEndedEarly { index: usize },
+    /// Every recorded action was applied but the hand is still being played.
+    HandNotOver,
+    /// `step` was called after every action was applied.
+    NoMoreActions,
+    BoardMismatch { expected: Vec<Card>, actual: Vec<Card> },
+    /// The pots, or who won them, are different.
+    PotMismatch { expected: Vec<PotRecord>, actual: Vec<PotRecord> },
+    /// The board was run a different number of times, or a run was dealt or paid out differently.
+    RunMismatch { expected: Vec<RunRecord>, actual: Vec<RunRecord> },
+    ShowdownMismatch { expected: Option<ShowdownResult>, actual: Option<ShowdownResult> }
+}
+
+impl std::fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ReplayError::IllegalAction { index, reason } => write!(f, "Action {} was rejected: {}", index, reason),
+            ReplayError::ActionMismatch { index, expected, actual } => {
+                write!(f, "Action {} was {:?} but the engine recorded {:?}", index, expected, actual)
+            },
+            ReplayError::HandEndedEarly { index } => write!(f, "The hand ended before action {}", index),
+            ReplayError::HandNotOver => write!(f, "The hand was not over after the last action"),
+            ReplayError::NoMoreActions => write!(f, "Every action has been replayed"),
+            ReplayError::BoardMismatch { expected, actual } => write!(f, "The board was {:?} but the engine dealt {:?}", expected, actual),
+            ReplayError::PotMismatch { expected, actual } => write!(f, "The pots were {:?} but the engine paid out {:?}", expected, actual),
+            ReplayError::RunMismatch { expected, actual } => write!(f, "The runs were {:?} but the engine ran {:?}", expected, actual),
+            ReplayError::ShowdownMismatch { expected, actual } => {
+                write!(f, "The showdown was {:?} but the engine showed {:?}", expected, actual)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::game::betting::BettingStructure;
+    use crate::game::blinds::BlindStructure;
+    use crate::game::deck::ShuffledDecks;
+    use crate::game::hand_history::MemorySink;
+    use crate::game::player::Player;
+
+    /// Plays a few hands of a seeded game with antes and a straddle, where the players call, raise and fold in turn.
+    fn recorded_hands() -> Vec<HandHistory> {
+        let mut game = Game::new(3, 10, 200);
+        for player_id in 1..=4 {
+            game.add_player(Player::new(player_id, format!("Player {}", player_id), 200));
+        }
+        let sink = MemorySink::new();
+        game.add_history_sink(Box::new(sink.clone()));
+        game.set_deck_source(Box::new(ShuffledDecks::from_seed(16)));
+        game.set_blind_structure({
+            let mut blinds = BlindStructure::new(5, 10);
+            blinds.set_ante(1);
+            blinds.set_straddle(Some(20));
+            blinds
+        });
+
+        for hand in 0..4 {
+            game.start_hand(false);
+            let mut turn = hand;
+            while let Some(player_id) = game.next_to_act() {
+                let action = match turn % 5 {
+                    0 => Action::RaiseTo(game.get_hand_state().unwrap().get_min_raise_to()),
+                    1 if hand % 2 == 0 => Action::Fold,
+                    _ => Action::Call
+                };
+                if game.apply_action(player_id, action).is_err() {
+                    game.apply_action(player_id, Action::Check).or_else(|_| game.apply_action(player_id, Action::Call)).unwrap();
+                }
+                turn += 1;
+            }
+        }
+
+        sink.get_hand_histories()
+    }
+
+    #[test]
+    fn test_recorded_hands_replay() {
+        let hand_histories = recorded_hands();
+        assert_eq!(hand_histories.len(), 4);
+
+        for hand_history in hand_histories.iter() {
+            assert_eq!(replay(hand_history), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_replayer_steps_through_the_hand() {
+        let hand_history = recorded_hands().remove(0);
+        let mut replayer = Replayer::new(hand_history.clone()).unwrap();
+        let num_posts = hand_history.actions().iter()
+            .take_while(|action| !matches!(action.kind(), ActionKind::Fold | ActionKind::Check | ActionKind::Call | ActionKind::Bet(_) | ActionKind::RaiseTo(_)))
+            .count();
+        assert_eq!(replayer.get_next_action(), hand_history.actions().get(num_posts));
+
+        let mut num_steps = 0;
+        while replayer.get_next_action().is_some() {
+            let next_player = *replayer.get_next_action().unwrap().player_id();
+            assert_eq!(replayer.get_game().next_to_act(), Some(next_player));
+            replayer.step().unwrap();
+            num_steps += 1;
+        }
+
+        assert_eq!(num_steps, hand_history.actions().len() - num_posts);
+        assert_eq!(replayer.step(), Err(ReplayError::NoMoreActions));
+        assert_eq!(replayer.finish().unwrap(), hand_history);
+    }
+
+    #[test]
+    fn test_replay_finds_changed_payouts() {
+        let hand_history = recorded_hands().remove(1);
+        let mut json: serde_json::Value = serde_json::to_value(&hand_history).unwrap();
+        let payouts = &mut json["pots"][0]["payouts"];
+        let (winner, amount) = serde_json::from_value::<BTreeMap<String, i32>>(payouts.clone()).unwrap().into_iter().next().unwrap();
+        payouts[winner] = serde_json::json!(amount - 1);
+
+        let changed: HandHistory = serde_json::from_value(json).unwrap();
+        assert!(matches!(replay(&changed), Err(ReplayError::PotMismatch { .. })));
+    }
+
+    #[test]
+    fn test_replay_finds_illegal_actions() {
+        let hand_history = recorded_hands().remove(0);
+        let mut json: serde_json::Value = serde_json::to_value(&hand_history).unwrap();
+        json["betting_structure"] = serde_json::to_value(BettingStructure::FixedLimit { small_bet: 10, big_bet: 20, raise_cap: 0 }).unwrap();
+
+        let changed: HandHistory = serde_json::from_value(json).unwrap();
+        assert!(matches!(replay(&changed), Err(ReplayError::IllegalAction { .. })));
+    }
+
+    /// Replays every hand in the `hand_histories` directory. Hands from bug reports go there so that changes to the
+    /// engine are checked against them.
+    #[test]
+    fn test_corpus_replays() {
+        let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("hand_histories");
+        for entry in std::fs::read_dir(corpus).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|extension| extension == "jsonl") {
+                assert_eq!(replay_file(&path).unwrap(), Vec::new(), "{} did not replay", path.display());
+            }
+        }
+    }
+}
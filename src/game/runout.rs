@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+
+use crate::game::player::PlayerId;
+
+
+/// The reasons `Game::choose_run_count` can reject a player's choice of how many times to run the board.
+///
+/// These are serializable so the server can send them back to the client that made the choice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "error")]
+pub enum RunoutError {
+    /// There is no hand being played.
+    NoHandInProgress,
+    /// The game only runs the board once.
+    NotAllowed,
+    /// The player is not seated at this game.
+    UnknownPlayer { player_id: PlayerId },
+    /// The player is not in the hand, because they folded or were not dealt in.
+    NotInHand,
+    /// The run count is 0 or more than the game allows.
+    InvalidRunCount { maximum: u32 }
+}
+
+
+impl std::fmt::Display for RunoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RunoutError::NoHandInProgress => write!(f, "There is no hand in progress"),
+            RunoutError::NotAllowed => write!(f, "This game only runs the board once"),
+            RunoutError::UnknownPlayer { player_id } => write!(f, "Player {} is not in this game", player_id),
+            RunoutError::NotInHand => write!(f, "You are not in this hand"),
+            RunoutError::InvalidRunCount { maximum } => write!(f, "The board can be run from 1 to {} times", maximum)
+        }
+    }
+}
+
+impl std::error::Error for RunoutError {}
@@ -0,0 +1,132 @@
+use std::collections::{BTreeMap, HashSet};
+
+use getset::Getters;
+use serde::{Deserialize, Serialize};
+
+use crate::game::hand::{Card, HandScore, OnePlayerAllPossibleCards};
+use crate::game::hand_history::ShowdownRecord;
+use crate::game::hand_state::Street;
+use crate::game::player::PlayerId;
+
+
+/// Who showed their hand and who mucked at the end of a hand that went to showdown.
+///
+/// # Fields
+///
+/// * `street`: The last street anyone acted on. The hands were turned over once its betting was over.
+/// * `is_all_in`: Whether the hands were turned face up because the players were all in and there could be no more
+///   betting. Every hand is shown when this is true.
+/// * `order`: The players still in the hand, in the order they showed or mucked.
+/// * `shown`: The hands that were shown, in the order they were shown.
+///
+/// # Notes
+///
+/// The last player to bet or raise on the last street anyone acted on shows first. If nobody bet on that street, the
+/// first player after the button shows first. The rest follow in the order of play.
+///
+/// The first player always shows, and so does every player who wins part of a pot. Any other player shows if their hand
+/// is at least as good as the best hand shown before them. Otherwise they muck, unless they have chosen to show losing
+/// hands.
+#[derive(Debug, Clone, PartialEq, Eq, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct ShowdownResult {
+    street: Street,
+    is_all_in: bool,
+    order: Vec<PlayerId>,
+    shown: Vec<ShowdownRecord>
+}
+
+impl ShowdownResult {
+    /// Decides who shows and who mucks.
+    ///
+    /// # Parameters
+    ///
+    /// * `street`: The last street anyone acted on.
+    /// * `is_all_in`: Whether the players were all in, so every hand is shown.
+    /// * `hands`: The hole cards and score of each player still in the hand, in the order they show.
+    /// * `winners`: The players who won part of a pot.
+    /// * `mucks_losing_hands`: Whether a player mucks a hand they do not have to show.
+    pub(crate) fn new(street: Street, is_all_in: bool, hands: Vec<(PlayerId, Vec<Card>, HandScore)>, winners: &HashSet<PlayerId>,
+                      mucks_losing_hands: impl Fn(&PlayerId) -> bool) -> ShowdownResult {
+        let mut order = Vec::<PlayerId>::new();
+        let mut shown = Vec::<ShowdownRecord>::new();
+
+        for (player_id, hole_cards, score) in hands {
+            let best_shown = shown.iter().map(|shown| shown.score()).max();
+            let must_show = is_all_in || winners.contains(&player_id) || best_shown.is_none_or(|best| &score >= best);
+
+            order.push(player_id);
+            if must_show || !mucks_losing_hands(&player_id) {
+                shown.push(ShowdownRecord::new(player_id, hole_cards, score));
+            }
+        }
+
+        ShowdownResult {
+            street,
+            is_all_in,
+            order,
+            shown
+        }
+    }
+
+    /// Scores each hand that was shown against `board`, which is how the hands are read on a run of the board other
+    /// than the first.
+    pub(crate) fn score_on(&self, board: &[Card]) -> BTreeMap<PlayerId, HandScore> {
+        self.shown.iter()
+            .map(|shown| {
+                let seven_cards = shown.hole_cards().iter().chain(board.iter()).cloned().collect();
+                (*shown.player_id(), OnePlayerAllPossibleCards::new(seven_cards).get_highest_hand_score())
+            })
+            .collect()
+    }
+
+    /// Returns the players who mucked, in the order they mucked.
+    pub fn get_mucked(&self) -> Vec<PlayerId> {
+        self.order.iter().filter(|player_id| self.get_shown_cards(player_id).is_none()).cloned().collect()
+    }
+
+    /// Returns the hole cards `player_id` showed, or `None` if they mucked or were not at showdown.
+    pub fn get_shown_cards(&self, player_id: &PlayerId) -> Option<&Vec<Card>> {
+        self.shown.iter().find(|shown| shown.player_id() == player_id).map(|shown| shown.hole_cards())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::hand::{HandType, Suit};
+
+    fn hand(player_id: PlayerId, hand_type: HandType) -> (PlayerId, Vec<Card>, HandScore) {
+        (player_id, vec![Card::new(Suit::Spades, player_id + 1)], HandScore::new(hand_type, vec![10]))
+    }
+
+    #[test]
+    fn test_losing_hands_muck() {
+        let hands = vec![hand(1, HandType::Pair), hand(2, HandType::HighCard), hand(3, HandType::TwoPair), hand(4, HandType::Pair)];
+        let winners = HashSet::from([3]);
+
+        let result = ShowdownResult::new(Street::River, false, hands.clone(), &winners, |_| true);
+        assert_eq!(result.order(), &vec![1, 2, 3, 4]);
+        assert_eq!(result.shown().iter().map(|shown| *shown.player_id()).collect::<Vec<PlayerId>>(), vec![1, 3]);
+        assert_eq!(result.get_mucked(), vec![2, 4]);
+
+        // A player who chose to show their losing hands shows them.
+        let result = ShowdownResult::new(Street::River, false, hands.clone(), &winners, |&player_id| player_id != 2);
+        assert_eq!(result.get_mucked(), vec![4]);
+
+        // When the players are all in, every hand is face up.
+        let result = ShowdownResult::new(Street::Flop, true, hands, &winners, |_| true);
+        assert!(result.get_mucked().is_empty());
+    }
+
+    #[test]
+    fn test_side_pot_winners_show() {
+        // Player 2 loses the main pot to player 1 but wins a side pot against player 3, so they have to show.
+        let hands = vec![hand(1, HandType::Flush), hand(2, HandType::Pair), hand(3, HandType::HighCard)];
+        let result = ShowdownResult::new(Street::River, false, hands, &HashSet::from([1, 2]), |_| true);
+
+        assert_eq!(result.get_mucked(), vec![3]);
+        assert!(result.get_shown_cards(&2).is_some());
+    }
+}
@@ -0,0 +1,352 @@
+use crate::game::player::PlayerId;
+
+
+/// The number of seats at a table, unless another number is given to `Table::new`.
+pub const DEFAULT_NUM_SEATS: usize = 10;
+
+
+/// Who is in which position for one hand, as worked out by `Table::next_hand`.
+///
+/// # Fields
+///
+/// * `button`: The seat with the dealer button. It may be empty if the player who would have had it left.
+/// * `small_blind`: The player who posts the small blind, or `None` if the small blind is dead this hand.
+/// * `big_blind`: The player who posts the big blind.
+/// * `players`: The players dealt in, in the order they act after the flop, starting with the first seat after the button.
+/// * `missed_small_blind`: A player sitting out in the small blind's seat.
+/// * `missed_big_blind`: The players sitting out in the seats the big blind moved past.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandPositions {
+    button: usize,
+    small_blind: Option<PlayerId>,
+    big_blind: PlayerId,
+    players: Vec<PlayerId>,
+    missed_small_blind: Option<PlayerId>,
+    missed_big_blind: Vec<PlayerId>
+}
+
+impl HandPositions {
+    /// Creates the positions of a hand that has already been dealt, such as one being replayed from its history. Nobody
+    /// is recorded as missing a blind.
+    pub(crate) fn new(button: usize, small_blind: Option<PlayerId>, big_blind: PlayerId, players: Vec<PlayerId>) -> HandPositions {
+        HandPositions {
+            button,
+            small_blind,
+            big_blind,
+            players,
+            missed_small_blind: None,
+            missed_big_blind: Vec::new()
+        }
+    }
+
+    pub fn get_button(&self) -> usize {
+        self.button
+    }
+
+    pub fn get_small_blind(&self) -> Option<PlayerId> {
+        self.small_blind
+    }
+
+    pub fn get_big_blind(&self) -> PlayerId {
+        self.big_blind
+    }
+
+    pub fn get_players(&self) -> &Vec<PlayerId> {
+        &self.players
+    }
+
+    pub fn get_missed_small_blind(&self) -> Option<PlayerId> {
+        self.missed_small_blind
+    }
+
+    pub fn get_missed_big_blind(&self) -> &Vec<PlayerId> {
+        &self.missed_big_blind
+    }
+}
+
+
+/// The seats at a poker table and the position of the dealer button.
+///
+/// # Fields
+///
+/// * `seats`: The player in each seat, or `None` if the seat is empty.
+/// * `button`: The seat with the button in the last hand.
+/// * `small_blind_seat`: The seat the small blind was posted from in the last hand, even if it was dead.
+/// * `big_blind_seat`: The seat the big blind was posted from in the last hand.
+///
+/// # Notes
+///
+/// The blinds move by the dead button rule. The big blind always moves on to the next player dealt in, so nobody
+/// misses it or pays it twice. The small blind is posted by whoever was the big blind last hand, and the button moves
+/// to the seat of last hand's small blind. When those players have left, the small blind or the button is dead.
+///
+/// Heads up, the button posts the small blind and acts first preflop, and the big blind acts first after the flop.
+#[derive(Debug, Clone)]
+pub struct Table {
+    seats: Vec<Option<PlayerId>>,
+    button: Option<usize>,
+    small_blind_seat: Option<usize>,
+    big_blind_seat: Option<usize>
+}
+
+impl Table {
+    pub fn new(num_seats: usize) -> Table {
+        Table {
+            seats: vec![None; num_seats],
+            button: None,
+            small_blind_seat: None,
+            big_blind_seat: None
+        }
+    }
+
+    pub fn get_num_seats(&self) -> usize {
+        self.seats.len()
+    }
+
+    /// Returns the seat the button was on in the last hand, or `None` if no hand has been dealt.
+    pub fn get_button(&self) -> Option<usize> {
+        self.button
+    }
+
+    /// Returns the seat `player_id` is sitting in.
+    pub fn get_seat(&self, player_id: &PlayerId) -> Option<usize> {
+        self.seats.iter().position(|seat| seat.as_ref() == Some(player_id))
+    }
+
+    /// Returns the seated players in seat order.
+    pub fn get_players(&self) -> Vec<PlayerId> {
+        self.seats.iter().flatten().cloned().collect()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.seats.iter().all(|seat| seat.is_some())
+    }
+
+    /// Seats `player_id` in the first empty seat.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<usize>` - The seat the player was given, or `None` if the table is full.
+    pub fn add_player(&mut self, player_id: PlayerId) -> Option<usize> {
+        let seat = self.seats.iter().position(|seat| seat.is_none())?;
+        self.seats[seat] = Some(player_id);
+        Some(seat)
+    }
+
+    /// Seats `player_id` in `seat`.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether the player was seated. The seat must exist and be empty.
+    pub fn add_player_to_seat(&mut self, player_id: PlayerId, seat: usize) -> bool {
+        match self.seats.get_mut(seat) {
+            Some(empty_seat @ None) => {
+                *empty_seat = Some(player_id);
+                true
+            },
+            _ => false
+        }
+    }
+
+    /// Empties the seat of `player_id`.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether the player was seated at the table.
+    pub fn remove_player(&mut self, player_id: &PlayerId) -> bool {
+        match self.get_seat(player_id) {
+            Some(seat) => {
+                self.seats[seat] = None;
+                true
+            },
+            None => false
+        }
+    }
+
+    /// Moves the button and blinds on for a new hand.
+    ///
+    /// # Parameters
+    ///
+    /// * `is_active`: Whether a seated player is dealt into the hand. Players who are not, such as players sitting
+    ///   out, are skipped over by the button and blinds.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<HandPositions>` - The positions for the hand, or `None` if fewer than two players are active.
+    ///
+    /// # Notes
+    ///
+    /// In the first hand the button goes to the last active seat, so the first active seat posts the small blind.
+    pub fn next_hand(&mut self, is_active: impl Fn(&PlayerId) -> bool) -> Option<HandPositions> {
+        let num_seats = self.seats.len();
+        let active: Vec<bool> = self.seats.iter()
+            .map(|seat| seat.as_ref().is_some_and(&is_active))
+            .collect();
+        let num_active = active.iter().filter(|&&is_active| is_active).count();
+
+        if num_active < 2 {
+            return None;
+        }
+
+        let next_active_after = |seat: usize| {
+            (1..=num_seats).map(|offset| (seat + offset) % num_seats).find(|&s| active[s]).expect("Expected an active seat")
+        };
+
+        let (button, small_blind_seat, big_blind_seat) = match self.big_blind_seat {
+            None => {
+                let button = (0..num_seats).rev().find(|&s| active[s]).expect("Expected an active seat");
+                let small_blind_seat = if num_active == 2 { button } else { next_active_after(button) };
+                (button, small_blind_seat, next_active_after(small_blind_seat))
+            },
+            Some(prev_big_blind_seat) => {
+                let big_blind_seat = next_active_after(prev_big_blind_seat);
+
+                if num_active == 2 {
+                    let button = next_active_after(big_blind_seat);
+                    (button, button, big_blind_seat)
+                } else {
+                    let small_blind_seat = prev_big_blind_seat;
+                    let mut button = self.small_blind_seat.unwrap_or((small_blind_seat + num_seats - 1) % num_seats);
+                    if button == big_blind_seat || button == small_blind_seat {
+                        button = (small_blind_seat + num_seats - 1) % num_seats;
+                    }
+                    (button, small_blind_seat, big_blind_seat)
+                }
+            }
+        };
+
+        // The seats the big blind moved past. On the first hand, it moved past the seats after the small blind.
+        let big_blind_start = self.big_blind_seat.unwrap_or(small_blind_seat);
+        let missed_big_blind = (1..num_seats)
+            .map(|offset| (big_blind_start + offset) % num_seats)
+            .take_while(|&s| s != big_blind_seat)
+            .filter_map(|s| self.seats[s].filter(|_| !active[s]))
+            .collect();
+
+        let players = (1..=num_seats)
+            .map(|offset| (button + offset) % num_seats)
+            .filter(|&s| active[s])
+            .map(|s| self.seats[s].expect("Expected an active seat to have a player"))
+            .collect();
+
+        self.button = Some(button);
+        self.small_blind_seat = Some(small_blind_seat);
+        self.big_blind_seat = Some(big_blind_seat);
+
+        Some(HandPositions {
+            button,
+            small_blind: self.seats[small_blind_seat].filter(|_| active[small_blind_seat]),
+            big_blind: self.seats[big_blind_seat].expect("Expected the big blind's seat to have a player"),
+            players,
+            missed_small_blind: self.seats[small_blind_seat].filter(|_| !active[small_blind_seat]),
+            missed_big_blind
+        })
+    }
+}
+
+impl Default for Table {
+    fn default() -> Self {
+        Table::new(DEFAULT_NUM_SEATS)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table_with_players(num_seats: usize, player_ids: &[PlayerId]) -> Table {
+        let mut table = Table::new(num_seats);
+        for &player_id in player_ids {
+            table.add_player(player_id);
+        }
+        table
+    }
+
+    #[test]
+    fn test_add_player_to_seat() {
+        let mut table = Table::new(3);
+
+        assert!(table.add_player_to_seat(1, 2));
+        assert!(!table.add_player_to_seat(2, 2));
+        assert!(!table.add_player_to_seat(2, 3));
+        assert_eq!(table.add_player(2), Some(0));
+        assert_eq!(table.get_players(), vec![2, 1]);
+    }
+
+    #[test]
+    fn test_blinds_move_around_the_table() {
+        let mut table = table_with_players(4, &[1, 2, 3]);
+
+        let positions = table.next_hand(|_| true).unwrap();
+        assert_eq!(positions.get_button(), 2);
+        assert_eq!((positions.get_small_blind(), positions.get_big_blind()), (Some(1), 2));
+        assert_eq!(positions.get_players(), &vec![1, 2, 3]);
+
+        let positions = table.next_hand(|_| true).unwrap();
+        assert_eq!(positions.get_button(), 0);
+        assert_eq!((positions.get_small_blind(), positions.get_big_blind()), (Some(2), 3));
+        assert_eq!(positions.get_players(), &vec![2, 3, 1]);
+    }
+
+    #[test]
+    fn test_heads_up_button_posts_small_blind() {
+        let mut table = table_with_players(6, &[1, 2]);
+
+        for (button_player, big_blind) in [(2, 1), (1, 2), (2, 1)] {
+            let positions = table.next_hand(|_| true).unwrap();
+            assert_eq!(positions.get_small_blind(), Some(button_player));
+            assert_eq!(positions.get_big_blind(), big_blind);
+            // The big blind acts first after the flop.
+            assert_eq!(positions.get_players(), &vec![big_blind, button_player]);
+        }
+    }
+
+    #[test]
+    fn test_dead_button_when_small_blind_leaves() {
+        let mut table = table_with_players(6, &[1, 2, 3, 4]);
+        table.next_hand(|_| true).unwrap();
+
+        // Player 1 posted the small blind and leaves, so the button is dead in their empty seat.
+        table.remove_player(&1);
+        let positions = table.next_hand(|_| true).unwrap();
+        assert_eq!(positions.get_button(), 0);
+        assert_eq!((positions.get_small_blind(), positions.get_big_blind()), (Some(2), 3));
+        assert_eq!(positions.get_players(), &vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn test_dead_small_blind_when_big_blind_leaves() {
+        let mut table = table_with_players(6, &[1, 2, 3, 4]);
+        table.next_hand(|_| true).unwrap();
+
+        // Player 2 posted the big blind and leaves. The big blind still moves on to player 3, so nobody posts the
+        // small blind and player 1 has the button.
+        table.remove_player(&2);
+        let positions = table.next_hand(|_| true).unwrap();
+        assert_eq!(positions.get_button(), 0);
+        assert_eq!((positions.get_small_blind(), positions.get_big_blind()), (None, 3));
+        assert_eq!(positions.get_players(), &vec![3, 4, 1]);
+    }
+
+    #[test]
+    fn test_sitting_out_players_miss_blinds() {
+        let mut table = table_with_players(6, &[1, 2, 3, 4]);
+        table.next_hand(|_| true).unwrap();
+
+        let positions = table.next_hand(|&player_id| player_id != 3).unwrap();
+        assert_eq!(positions.get_big_blind(), 4);
+        assert_eq!(positions.get_missed_big_blind(), &vec![3]);
+
+        let positions = table.next_hand(|&player_id| player_id != 3).unwrap();
+        assert_eq!(positions.get_big_blind(), 1);
+        assert_eq!(positions.get_missed_big_blind(), &Vec::<PlayerId>::new());
+        assert_eq!(positions.get_small_blind(), Some(4));
+    }
+
+    #[test]
+    fn test_not_enough_active_players() {
+        let mut table = table_with_players(6, &[1, 2]);
+        assert_eq!(table.next_hand(|&player_id| player_id == 1), None);
+    }
+}
@@ -0,0 +1,259 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use getset::Getters;
+use serde::{Deserialize, Serialize};
+
+use crate::game::betting::LegalActions;
+use crate::game::hand::Card;
+use crate::game::hand_state::Street;
+use crate::game::player::PlayerId;
+
+
+/// The public information about one player at the table.
+#[derive(Debug, Clone, PartialEq, Eq, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct SeatView {
+    seat: usize,
+    player_id: PlayerId,
+    name: String,
+    money: i32,
+    /// The amount the player has put in the pot this hand.
+    contribution: i32,
+    has_folded: bool,
+    is_all_in: bool,
+    /// The hole cards the player showed at showdown, or `None` if they have not shown them.
+    shown_cards: Option<Vec<Card>>
+}
+
+impl SeatView {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        seat: usize,
+        player_id: PlayerId,
+        name: String,
+        money: i32,
+        contribution: i32,
+        has_folded: bool,
+        is_all_in: bool,
+        shown_cards: Option<Vec<Card>>
+    ) -> SeatView {
+        SeatView {
+            seat,
+            player_id,
+            name,
+            money,
+            contribution,
+            has_folded,
+            is_all_in,
+            shown_cards
+        }
+    }
+
+    /// Returns whether the player can still win the pot.
+    pub fn is_in_hand(&self) -> bool {
+        !self.has_folded
+    }
+}
+
+
+/// What one player is allowed to see of the table.
+///
+/// This holds the public state of the hand plus the player's own hole cards, and is what a `PlayerAgent` decides on
+/// and what is sent to the player's client. Other players' hole cards are only in it once they are shown at showdown.
+#[derive(Debug, Clone, PartialEq, Eq, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct PlayerView {
+    player_id: PlayerId,
+    name: String,
+    money: i32,
+    /// The player's hole cards. It is empty if they were not dealt into the hand.
+    hole_cards: Vec<Card>,
+    /// The street being played, or `None` if no hand has been dealt.
+    street: Option<Street>,
+    /// The seat with the dealer button, or `None` if no hand has been dealt.
+    button: Option<usize>,
+    /// The player whose turn it is, or `None` if nobody has to act.
+    next_to_act: Option<PlayerId>,
+    community_cards: Vec<Card>,
+    /// The table's current bet on this street.
+    current_bet: i32,
+    /// The amount the player has bet on this street.
+    street_contribution: i32,
+    /// The amount the player has put in the pot this hand.
+    hand_contribution: i32,
+    /// The total number of chips put in this hand by every player.
+    pot: i32,
+    /// What the player is allowed to do, including the range of amounts they can bet or raise to. The player cannot do
+    /// anything when it is not their turn.
+    legal_actions: LegalActions,
+    /// The players dealt into the hand, starting with this player if they were dealt in and then in turn order.
+    seats: Vec<SeatView>
+}
+
+impl PlayerView {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        player_id: PlayerId,
+        name: String,
+        money: i32,
+        hole_cards: Vec<Card>,
+        street: Option<Street>,
+        button: Option<usize>,
+        next_to_act: Option<PlayerId>,
+        community_cards: Vec<Card>,
+        current_bet: i32,
+        street_contribution: i32,
+        hand_contribution: i32,
+        pot: i32,
+        legal_actions: LegalActions,
+        seats: Vec<SeatView>
+    ) -> PlayerView {
+        PlayerView {
+            player_id,
+            name,
+            money,
+            hole_cards,
+            street,
+            button,
+            next_to_act,
+            community_cards,
+            current_bet,
+            street_contribution,
+            hand_contribution,
+            pot,
+            legal_actions,
+            seats
+        }
+    }
+
+    /// Returns the amount the player has to put in to call the current bet.
+    pub fn get_amount_to_call(&self) -> i32 {
+        (self.current_bet - self.street_contribution).max(0)
+    }
+}
+
+
+/// What someone watching the table is allowed to see. Nobody's hole cards are in it until they are shown at showdown.
+#[derive(Debug, Clone, PartialEq, Eq, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct SpectatorView {
+    game_id: u128,
+    /// The number of hands that have been started.
+    hand_number: u64,
+    /// The street being played, or `None` if no hand has been dealt.
+    street: Option<Street>,
+    /// The seat with the dealer button, or `None` if no hand has been dealt.
+    button: Option<usize>,
+    /// The player whose turn it is, or `None` if nobody has to act.
+    next_to_act: Option<PlayerId>,
+    community_cards: Vec<Card>,
+    /// The table's current bet on this street.
+    current_bet: i32,
+    /// The total number of chips put in this hand by every player.
+    pot: i32,
+    /// The players dealt into the hand, in the order they act after the flop.
+    seats: Vec<SeatView>
+}
+
+impl SpectatorView {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        game_id: u128,
+        hand_number: u64,
+        street: Option<Street>,
+        button: Option<usize>,
+        next_to_act: Option<PlayerId>,
+        community_cards: Vec<Card>,
+        current_bet: i32,
+        pot: i32,
+        seats: Vec<SeatView>
+    ) -> SpectatorView {
+        SpectatorView {
+            game_id,
+            hand_number,
+            street,
+            button,
+            next_to_act,
+            community_cards,
+            current_bet,
+            pot,
+            seats
+        }
+    }
+}
+
+
+/// Holds `SpectatorView`s back from spectators for a delay, so that someone watching cannot tell a player at the table
+/// what is happening as it happens.
+///
+/// # Fields
+///
+/// * `delay`: How long each view is held back for.
+/// * `pending`: The views that are still being held back, oldest first, with when they were pushed.
+/// * `current`: The newest view that has been held back for the whole delay.
+#[derive(Debug, Clone)]
+pub struct SpectatorDelay {
+    delay: Duration,
+    pending: VecDeque<(Instant, SpectatorView)>,
+    current: Option<SpectatorView>
+}
+
+impl SpectatorDelay {
+    /// Creates a delay of `delay`. A delay of zero shows every view straight away.
+    pub fn new(delay: Duration) -> SpectatorDelay {
+        SpectatorDelay {
+            delay,
+            pending: VecDeque::new(),
+            current: None
+        }
+    }
+
+    pub fn get_delay(&self) -> Duration {
+        self.delay
+    }
+
+    /// Adds the view of the table at `now`. This should be called every time the table changes.
+    pub fn push(&mut self, view: SpectatorView, now: Instant) {
+        self.pending.push_back((now, view));
+    }
+
+    /// Returns what spectators can see at `now`: the newest view that was pushed at least `delay` ago, or `None` if
+    /// there is no such view yet.
+    pub fn get_view(&mut self, now: Instant) -> Option<&SpectatorView> {
+        while let Some((pushed, _)) = self.pending.front() {
+            if now.saturating_duration_since(*pushed) < self.delay {
+                break;
+            }
+            self.current = self.pending.pop_front().map(|(_, view)| view);
+        }
+
+        self.current.as_ref()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view_with_pot(pot: i32) -> SpectatorView {
+        SpectatorView::new(0, 1, Some(Street::Preflop), Some(0), Some(1), Vec::new(), 10, pot, Vec::new())
+    }
+
+    #[test]
+    fn test_spectator_delay_holds_views_back() {
+        let start = Instant::now();
+        let mut delay = SpectatorDelay::new(Duration::from_secs(30));
+
+        delay.push(view_with_pot(15), start);
+        delay.push(view_with_pot(45), start + Duration::from_secs(10));
+        assert_eq!(delay.get_view(start + Duration::from_secs(29)), None);
+        assert_eq!(delay.get_view(start + Duration::from_secs(30)).map(|view| *view.pot()), Some(15));
+        assert_eq!(delay.get_view(start + Duration::from_secs(45)).map(|view| *view.pot()), Some(45));
+
+        let mut no_delay = SpectatorDelay::new(Duration::ZERO);
+        no_delay.push(view_with_pot(15), start);
+        assert_eq!(no_delay.get_view(start).map(|view| *view.pot()), Some(15));
+    }
+}
@@ -0,0 +1,467 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use rand::Rng;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::task;
+
+use crate::lobby::clock::ClockSettings;
+use crate::lobby::table::{LobbyTable, TableHandle, TableSettings};
+use crate::messages::{AddShuffleEntropyOk, ChooseRunCountOk, JoinTableOk, LeaveTableOk, MessageType, ServerError, StartNewTableOk, TableInfo, TablesInfo};
+
+pub mod clock;
+pub mod session;
+pub mod table;
+
+
+/// The open tables on the server. Each table runs as its own task, and the lobby only keeps a `TableHandle` to each
+/// one, so tables never wait on each other and a table that panics closes without taking the others with it.
+///
+/// # Notes
+///
+/// The lock is only held to look up, add or remove a handle, never while waiting on a table.
+#[derive(Debug, Clone)]
+pub(crate) struct Lobby {
+    tables: Arc<RwLock<HashMap<u128, TableHandle>>>,
+    clock_settings: ClockSettings
+}
+
+impl Lobby {
+    /// Makes an empty lobby whose tables time each decision with `clock_settings`.
+    pub fn new(clock_settings: ClockSettings) -> Lobby {
+        Lobby {
+            tables: Arc::new(RwLock::new(HashMap::new())),
+            clock_settings
+        }
+    }
+
+    /// Opens a new table with `client_id` seated at it as the leader. The table is taken out of the lobby once its
+    /// task finishes.
+    ///
+    /// # Parameters
+    ///
+    /// * `client_id`: The client who will lead the table.
+    /// * `settings`: The stakes and rules of the table.
+    /// * `sender`: Where to send the client's messages.
+    /// * `rng`: Picks the id of the table.
+    pub fn create_table(&self, client_id: u128, settings: TableSettings, sender: UnboundedSender<MessageType>, rng: &mut impl Rng) -> TableHandle {
+        let mut tables = self.tables.write().expect("Failed to lock tables");
+        let mut table_id: u128 = rng.gen();
+        while tables.contains_key(&table_id) {
+            table_id = rng.gen();
+        }
+
+        let (handle, table_task) = TableHandle::spawn(LobbyTable::new(table_id, client_id, self.clock_settings, settings), sender);
+        tables.insert(table_id, handle.clone());
+
+        let lobby_tables = Arc::clone(&self.tables);
+        task::spawn(async move {
+            if let Err(error) = table_task.await {
+                println!("Table {} closed: {}", table_id, error);
+            }
+            lobby_tables.write().expect("Failed to lock tables").remove(&table_id);
+        });
+
+        handle
+    }
+
+    pub fn get_table(&self, table_id: u128) -> Option<TableHandle> {
+        self.tables.read().expect("Failed to lock tables").get(&table_id).cloned()
+    }
+
+    /// Returns every open table, ordered by id.
+    pub fn get_tables_info(&self) -> Vec<TableInfo> {
+        let mut tables_info: Vec<TableInfo> = self.tables.read().expect("Failed to lock tables")
+            .values()
+            .map(TableHandle::get_info)
+            .collect();
+        tables_info.sort_by_key(|table_info| *table_info.table_id());
+        tables_info
+    }
+}
+
+
+/// A connected client's place in the lobby. A client can only sit at one table at a time.
+///
+/// # Fields
+///
+/// * `client_id`: The id the server gave the connection.
+/// * `sender`: Sends messages to the client's connection, both the replies to their own messages and whatever their
+///   table sends them.
+/// * `table`: The table the client is seated at.
+#[derive(Debug)]
+pub(crate) struct LobbyClient {
+    client_id: u128,
+    sender: UnboundedSender<MessageType>,
+    table: Option<TableHandle>
+}
+
+impl LobbyClient {
+    pub fn new(client_id: u128, sender: UnboundedSender<MessageType>) -> LobbyClient {
+        LobbyClient {
+            client_id,
+            sender,
+            table: None
+        }
+    }
+
+    pub fn get_client_id(&self) -> u128 {
+        self.client_id
+    }
+
+    /// Returns the table the client is seated at, or `None` if they are not at a table.
+    pub fn get_table_id(&self) -> Option<u128> {
+        self.table.as_ref().map(TableHandle::get_table_id)
+    }
+
+    /// Sends `message` to the client. Nothing is sent if they have disconnected.
+    pub fn send(&self, message: impl Into<MessageType>) {
+        let _ = self.sender.send(message.into());
+    }
+
+    /// Acts on a message from the client and sends them the reply, which is an `ErrorMessage` saying why if what they
+    /// asked for could not be done. Anything sent to the rest of their table is sent by the table.
+    ///
+    /// # Parameters
+    ///
+    /// * `lobby`: The open tables.
+    /// * `message`: The message the client sent.
+    /// * `rng`: Picks the ids of new tables.
+    pub async fn handle_message(&mut self, lobby: &Lobby, message: MessageType, rng: &mut (impl Rng + Send)) {
+        match self.answer(lobby, message, rng).await {
+            Ok(Some(reply)) => self.send(reply),
+            Ok(None) => {},
+            Err(error) => self.send(error)
+        }
+    }
+
+    /// Acts on a message from the client.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<MessageType>, ServerError>` - The reply, or `None` if the client's table replies instead by
+    ///   sending what happened to everyone seated at it.
+    async fn answer(&mut self, lobby: &Lobby, message: MessageType, rng: &mut (impl Rng + Send)) -> Result<Option<MessageType>, ServerError> {
+        let client_id = self.client_id;
+        let check_client_id = |message_client_id: &u128| {
+            if *message_client_id == client_id { Ok(()) } else { Err(ServerError::WrongClientId) }
+        };
+
+        match message {
+            MessageType::StartNewTable(start_new_table) => {
+                check_client_id(start_new_table.client_id())?;
+                let table_id = self.create_table(lobby, TableSettings::from_message(&start_new_table)?, rng)?;
+                Ok(Some(StartNewTableOk::new(client_id, table_id).into()))
+            },
+            MessageType::QueryTables(query_tables) => {
+                check_client_id(query_tables.client_id())?;
+                Ok(Some(TablesInfo::new(lobby.get_tables_info()).into()))
+            },
+            MessageType::JoinTable(join_table) => {
+                check_client_id(join_table.client_id())?;
+                self.join_table(lobby, *join_table.table_id()).await?;
+                Ok(Some(JoinTableOk::new(client_id, *join_table.table_id()).into()))
+            },
+            MessageType::LeaveTable(leave_table) => {
+                check_client_id(leave_table.client_id())?;
+                self.leave_table(*leave_table.table_id()).await?;
+                Ok(Some(LeaveTableOk::new(client_id, *leave_table.table_id()).into()))
+            },
+            MessageType::StartGame(start_game) => {
+                check_client_id(start_game.client_id())?;
+                let table = self.get_table(*start_game.table_id())?;
+                let result = table.start_game(client_id).await;
+                self.answered_by(result).map(|_| None)
+            },
+            MessageType::PlayerAction(player_action) => {
+                check_client_id(player_action.client_id())?;
+                let table = self.get_table(*player_action.table_id())?;
+                let result = table.play_action(client_id, *player_action.action()).await;
+                self.answered_by(result).map(|_| None)
+            },
+            MessageType::AddShuffleEntropy(add_shuffle_entropy) => {
+                check_client_id(add_shuffle_entropy.client_id())?;
+                let table_id = *add_shuffle_entropy.table_id();
+                let table = self.get_table(table_id)?;
+                let result = table.add_shuffle_entropy(client_id, add_shuffle_entropy.entropy().clone()).await;
+                self.answered_by(result)?;
+                Ok(Some(AddShuffleEntropyOk::new(client_id, table_id).into()))
+            },
+            MessageType::ChooseRunCount(choose_run_count) => {
+                check_client_id(choose_run_count.client_id())?;
+                let table_id = *choose_run_count.table_id();
+                let table = self.get_table(table_id)?;
+                let result = table.choose_run_count(client_id, *choose_run_count.runs()).await;
+                self.answered_by(result)?;
+                Ok(Some(ChooseRunCountOk::new(client_id, table_id).into()))
+            },
+            message => Err(ServerError::UnexpectedMessage { message_type: message.to_string() })
+        }
+    }
+
+    /// Opens a new table with the client seated at it as the leader.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<u128, ServerError>` - The id of the new table, or `AlreadyAtTable` if the client is seated elsewhere.
+    fn create_table(&mut self, lobby: &Lobby, settings: TableSettings, rng: &mut impl Rng) -> Result<u128, ServerError> {
+        if let Some(table_id) = self.get_table_id() {
+            return Err(ServerError::AlreadyAtTable { table_id });
+        }
+
+        let table = lobby.create_table(self.client_id, settings, self.sender.clone(), rng);
+        let table_id = table.get_table_id();
+        self.table = Some(table);
+        Ok(table_id)
+    }
+
+    /// Seats the client at `table_id`.
+    async fn join_table(&mut self, lobby: &Lobby, table_id: u128) -> Result<(), ServerError> {
+        if let Some(table_id) = self.get_table_id() {
+            return Err(ServerError::AlreadyAtTable { table_id });
+        }
+
+        let table = lobby.get_table(table_id).ok_or(ServerError::UnknownTable { table_id })?;
+        table.join(self.client_id, self.sender.clone()).await?;
+        self.table = Some(table);
+        Ok(())
+    }
+
+    /// Takes the client away from `table_id`, folding their hand if one is being played. If the table has closed, the
+    /// client has left it anyway.
+    async fn leave_table(&mut self, table_id: u128) -> Result<(), ServerError> {
+        let table = self.get_table(table_id)?;
+        self.table = None;
+        match table.leave(self.client_id).await {
+            Err(ServerError::UnknownTable { .. }) => Ok(()),
+            result => result
+        }
+    }
+
+    /// Sends the client's messages through `sender` from now on, after they reconnect. If they are still seated at a
+    /// table, it sends them where the game is up to.
+    pub async fn reconnect(&mut self, sender: UnboundedSender<MessageType>) {
+        self.sender = sender;
+        if let Some(table) = self.table.clone() {
+            if table.reconnect(self.client_id, self.sender.clone()).await.is_err() {
+                self.table = None;
+            }
+        }
+    }
+
+    /// Takes the client away from their table, if any, when they disconnect.
+    pub async fn disconnect(&mut self) {
+        if let Some(table_id) = self.get_table_id() {
+            let _ = self.leave_table(table_id).await;
+        }
+    }
+
+    /// Returns the client's table if it is `table_id`, or `NotAtTable`.
+    fn get_table(&self, table_id: u128) -> Result<TableHandle, ServerError> {
+        match &self.table {
+            Some(table) if table.get_table_id() == table_id => Ok(table.clone()),
+            _ => Err(ServerError::NotAtTable { table_id })
+        }
+    }
+
+    /// Passes on a table's answer, forgetting the table if it has closed.
+    fn answered_by(&mut self, result: Result<(), ServerError>) -> Result<(), ServerError> {
+        if let Err(ServerError::UnknownTable { .. }) = result {
+            self.table = None;
+        }
+        result
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::OsRng;
+    use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+    use super::*;
+    use crate::game::action::Action;
+    use crate::game::fair_shuffle::{self, ShuffleError, MAX_ENTROPY_LEN};
+    use crate::game::runout::RunoutError;
+    use crate::messages::{AddShuffleEntropy, ChooseRunCount, JoinTable, LeaveTable, PlayerAction, QueryTables, StartGame, StartNewTable, TableStatus};
+
+    fn client(client_id: u128) -> (LobbyClient, UnboundedReceiver<MessageType>) {
+        let (sender, receiver) = unbounded_channel();
+        (LobbyClient::new(client_id, sender), receiver)
+    }
+
+    fn error(message: Option<MessageType>) -> ServerError {
+        match message {
+            Some(MessageType::ErrorMessage(error_message)) => error_message.error().clone(),
+            other => panic!("Expected an ErrorMessage, got {:?}", other)
+        }
+    }
+
+    /// Takes every message sent to a client so far.
+    fn receive(messages: &mut UnboundedReceiver<MessageType>) -> Vec<MessageType> {
+        std::iter::from_fn(|| messages.try_recv().ok()).collect()
+    }
+
+    fn shuffle_commitments(messages: &[MessageType]) -> Vec<String> {
+        messages.iter()
+            .filter_map(|message| match message {
+                MessageType::ShuffleCommitment(shuffle_commitment) => Some(shuffle_commitment.commitment().clone()),
+                _ => None
+            })
+            .collect()
+    }
+
+    /// Waits for the lobby to take closed tables out, which it does in a task of its own.
+    async fn wait_for_num_tables(lobby: &Lobby, num_tables: usize) {
+        for _ in 0..100 {
+            if lobby.get_tables_info().len() == num_tables {
+                return;
+            }
+            task::yield_now().await;
+        }
+        assert_eq!(lobby.get_tables_info().len(), num_tables);
+    }
+
+    #[tokio::test]
+    async fn test_lobby_messages_are_answered() {
+        let lobby = Lobby::new(ClockSettings::default());
+        let (mut leader, mut leader_messages) = client(1);
+        let (mut player, mut player_messages) = client(2);
+
+        // The small blind is half the big blind unless it is chosen too.
+        leader.handle_message(&lobby, StartNewTable::new(1, None, Some(10), Some(500), false, None).into(), &mut OsRng).await;
+        let table_id = match leader_messages.try_recv() {
+            Ok(MessageType::StartNewTableOk(start_new_table_ok)) => *start_new_table_ok.table_id(),
+            other => panic!("Expected StartNewTableOk, got {:?}", other)
+        };
+        player.handle_message(&lobby, JoinTable::new(2, table_id).into(), &mut OsRng).await;
+        assert!(matches!(player_messages.try_recv(), Ok(MessageType::JoinTableOk(_))));
+
+        let (mut other, mut other_messages) = client(3);
+        other.handle_message(&lobby, QueryTables::new(3).into(), &mut OsRng).await;
+        match other_messages.try_recv() {
+            Ok(MessageType::TablesInfo(tables_info)) => {
+                assert_eq!(tables_info.tables(), &vec![TableInfo::new(table_id, 2, 10, 5, 10, 500, TableStatus::Waiting, false, 1)]);
+            },
+            other => panic!("Expected TablesInfo, got {:?}", other)
+        }
+
+        // The table sends the game's messages to everyone seated at it.
+        leader.handle_message(&lobby, StartGame::new(1, table_id).into(), &mut OsRng).await;
+        assert!(matches!(leader_messages.try_recv(), Ok(MessageType::GameStarted(_))));
+        assert!(matches!(player_messages.try_recv(), Ok(MessageType::GameStarted(_))));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_lobby_messages_get_errors() {
+        let lobby = Lobby::new(ClockSettings::default());
+        let (mut leader, mut leader_messages) = client(1);
+        leader.handle_message(&lobby, StartNewTable::new(1, None, None, None, false, None).into(), &mut OsRng).await;
+        leader_messages.try_recv().unwrap();
+        let table_id = leader.get_table_id().unwrap();
+        let unknown_table_id = table_id.wrapping_add(1);
+
+        let (mut player, mut player_messages) = client(2);
+        player.handle_message(&lobby, JoinTable::new(3, table_id).into(), &mut OsRng).await;
+        assert_eq!(error(player_messages.try_recv().ok()), ServerError::WrongClientId);
+        player.handle_message(&lobby, JoinTable::new(2, unknown_table_id).into(), &mut OsRng).await;
+        assert_eq!(error(player_messages.try_recv().ok()), ServerError::UnknownTable { table_id: unknown_table_id });
+        player.handle_message(&lobby, PlayerAction::new(2, table_id, Action::Check).into(), &mut OsRng).await;
+        assert_eq!(error(player_messages.try_recv().ok()), ServerError::NotAtTable { table_id });
+        player.handle_message(&lobby, StartNewTableOk::new(2, table_id).into(), &mut OsRng).await;
+        assert_eq!(error(player_messages.try_recv().ok()),
+                   ServerError::UnexpectedMessage { message_type: String::from("StartNewTableOk") });
+        assert_eq!(player.get_table_id(), None);
+
+        leader.handle_message(&lobby, StartNewTable::new(1, None, None, None, false, None).into(), &mut OsRng).await;
+        assert_eq!(error(leader_messages.try_recv().ok()), ServerError::AlreadyAtTable { table_id });
+        leader.handle_message(&lobby, StartGame::new(1, table_id).into(), &mut OsRng).await;
+        assert_eq!(error(leader_messages.try_recv().ok()), ServerError::NotEnoughPlayers { table_id });
+        leader.handle_message(&lobby, ChooseRunCount::new(1, table_id, 2).into(), &mut OsRng).await;
+        assert_eq!(error(leader_messages.try_recv().ok()), ServerError::InvalidRunCount { reason: RunoutError::NotAllowed });
+
+        player.handle_message(&lobby, StartNewTable::new(2, None, None, None, false, Some(0)).into(), &mut OsRng).await;
+        assert_eq!(error(player_messages.try_recv().ok()), ServerError::InvalidMaxRuns);
+        player.handle_message(&lobby, StartNewTable::new(2, Some(0), None, None, false, None).into(), &mut OsRng).await;
+        assert_eq!(error(player_messages.try_recv().ok()), ServerError::InvalidStakes);
+        player.handle_message(&lobby, StartNewTable::new(2, Some(20), Some(10), None, false, None).into(), &mut OsRng).await;
+        assert_eq!(error(player_messages.try_recv().ok()), ServerError::InvalidStakes);
+        player.handle_message(&lobby, StartNewTable::new(2, None, Some(10), Some(5), false, None).into(), &mut OsRng).await;
+        assert_eq!(error(player_messages.try_recv().ok()), ServerError::InvalidStakes);
+        assert_eq!(player.get_table_id(), None);
+    }
+
+    #[tokio::test]
+    async fn test_empty_tables_close() {
+        let lobby = Lobby::new(ClockSettings::default());
+        let (mut leader, mut leader_messages) = client(1);
+        let (mut player, mut player_messages) = client(2);
+        leader.handle_message(&lobby, StartNewTable::new(1, None, None, None, false, None).into(), &mut OsRng).await;
+        let table_id = leader.get_table_id().unwrap();
+        player.handle_message(&lobby, JoinTable::new(2, table_id).into(), &mut OsRng).await;
+
+        leader.handle_message(&lobby, LeaveTable::new(1, table_id).into(), &mut OsRng).await;
+        assert_eq!(leader.get_table_id(), None);
+
+        // A client can open another table once they have left, and it keeps running when the first one closes.
+        leader.handle_message(&lobby, StartNewTable::new(1, None, None, None, false, None).into(), &mut OsRng).await;
+        let other_table_id = leader.get_table_id().unwrap();
+        player.disconnect().await;
+        wait_for_num_tables(&lobby, 1).await;
+        assert_eq!(*lobby.get_tables_info()[0].table_id(), other_table_id);
+
+        let messages: Vec<String> = std::iter::from_fn(|| leader_messages.try_recv().ok()).map(|message| message.to_string()).collect();
+        assert_eq!(messages, vec!["StartNewTableOk", "LeaveTableOk", "StartNewTableOk"]);
+        assert!(matches!(player_messages.try_recv(), Ok(MessageType::JoinTableOk(_))));
+    }
+
+    #[tokio::test]
+    async fn test_provably_fair_hands_can_be_verified() {
+        let lobby = Lobby::new(ClockSettings::default());
+        let (mut leader, mut leader_messages) = client(1);
+        let (mut player, mut player_messages) = client(2);
+        leader.handle_message(&lobby, StartNewTable::new(1, None, None, None, true, None).into(), &mut OsRng).await;
+        let table_id = leader.get_table_id().unwrap();
+        player.handle_message(&lobby, JoinTable::new(2, table_id).into(), &mut OsRng).await;
+        assert!(*lobby.get_tables_info()[0].provably_fair());
+
+        // Both clients are sent the commitment to the first hand's shuffle when they sit down.
+        let commitments = shuffle_commitments(&receive(&mut leader_messages));
+        assert_eq!(shuffle_commitments(&receive(&mut player_messages)), commitments);
+        let commitment = commitments[0].clone();
+
+        player.handle_message(&lobby, AddShuffleEntropy::new(2, table_id, String::from("player two")).into(), &mut OsRng).await;
+        assert!(matches!(player_messages.try_recv(), Ok(MessageType::AddShuffleEntropyOk(_))));
+        leader.handle_message(&lobby, AddShuffleEntropy::new(1, table_id, "x".repeat(MAX_ENTROPY_LEN + 1)).into(), &mut OsRng).await;
+        assert_eq!(error(leader_messages.try_recv().ok()),
+                   ServerError::InvalidEntropy { reason: ShuffleError::EntropyTooLong { maximum: MAX_ENTROPY_LEN } });
+
+        // Once the hand is dealt, everyone is sent the commitment to the next hand's shuffle.
+        leader.handle_message(&lobby, StartGame::new(1, table_id).into(), &mut OsRng).await;
+        let messages = receive(&mut leader_messages);
+        let next_commitments = shuffle_commitments(&messages);
+        assert_eq!(next_commitments.len(), 1);
+        assert_ne!(next_commitments[0], commitment);
+        let hole_cards = messages.iter()
+            .find_map(|message| match message {
+                MessageType::HandStarted(hand_started) => Some(hand_started.view().hole_cards().clone()),
+                _ => None
+            })
+            .unwrap();
+
+        // Whoever is asked to act folds, and the reveal in HandFinished matches the commitment from before the hand.
+        let to_act = if receive(&mut player_messages).iter().any(|message| matches!(message, MessageType::ActionRequest(_))) { &mut player } else { &mut leader };
+        let client_id = to_act.get_client_id();
+        to_act.handle_message(&lobby, PlayerAction::new(client_id, table_id, Action::Fold).into(), &mut OsRng).await;
+        let reveal = receive(&mut leader_messages).into_iter()
+            .find_map(|message| match message {
+                MessageType::HandFinished(hand_finished) => hand_finished.shuffle_reveal().clone(),
+                _ => None
+            })
+            .expect("Expected HandFinished to reveal the shuffle");
+
+        assert!(fair_shuffle::verify_shuffle(&commitment, &reveal));
+        assert_eq!(reveal.client_entropy().get(&2), Some(&String::from("player two")));
+        assert!(hole_cards.iter().all(|card| reveal.deck()[0..4].contains(card)));
+    }
+}
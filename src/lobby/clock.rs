@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use getset::Getters;
+use tokio::time::Instant;
+
+use crate::game::player::PlayerId;
+
+/// The time players get for each decision at tables created with `StartNewTable`.
+const ACTION_TIME: Duration = Duration::from_secs(30);
+/// The most time players can have in their time bank, which is also what they sit down with.
+const TIME_BANK: Duration = Duration::from_secs(60);
+/// The time added to each player's time bank at the start of every hand.
+const TIME_BANK_REFILL: Duration = Duration::from_secs(5);
+
+
+/// How long players get to act.
+///
+/// # Fields
+///
+/// * `action_time`: The time a player gets for each decision.
+/// * `time_bank`: The most time a player can have in their time bank, which is also what they sit down with. Once a
+///   decision's `action_time` runs out, the player's time bank is used.
+/// * `time_bank_refill`: The time added to each player's time bank at the start of every hand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Getters)]
+#[getset(get = "pub")]
+pub(crate) struct ClockSettings {
+    action_time: Duration,
+    time_bank: Duration,
+    time_bank_refill: Duration
+}
+
+impl ClockSettings {
+    pub fn new(action_time: Duration, time_bank: Duration, time_bank_refill: Duration) -> ClockSettings {
+        ClockSettings {
+            action_time,
+            time_bank,
+            time_bank_refill
+        }
+    }
+}
+
+impl Default for ClockSettings {
+    fn default() -> ClockSettings {
+        ClockSettings::new(ACTION_TIME, TIME_BANK, TIME_BANK_REFILL)
+    }
+}
+
+
+/// The decision a player is making.
+///
+/// # Fields
+///
+/// * `player_id`: The player whose turn it is.
+/// * `started`: When the player was asked for their action.
+#[derive(Debug, Clone, Copy)]
+struct Turn {
+    player_id: PlayerId,
+    started: Instant
+}
+
+
+/// Times each decision at a table, and keeps each player's time bank.
+///
+/// # Fields
+///
+/// * `settings`: How long players get to act.
+/// * `time_banks`: What is left in each player's time bank.
+/// * `turn`: The decision of the player whose turn it is.
+/// * `run_count_choice`: When the players who are all in were asked how many times to run the board. They choose at
+///   the same time, and only get `action_time` to do it.
+#[derive(Debug)]
+pub(crate) struct ActionClock {
+    settings: ClockSettings,
+    time_banks: HashMap<PlayerId, Duration>,
+    turn: Option<Turn>,
+    run_count_choice: Option<Instant>
+}
+
+impl ActionClock {
+    pub fn new(settings: ClockSettings) -> ActionClock {
+        ActionClock {
+            settings,
+            time_banks: HashMap::new(),
+            turn: None,
+            run_count_choice: None
+        }
+    }
+
+    /// Gives a player who sits down a full time bank.
+    pub fn add_player(&mut self, player_id: PlayerId) {
+        self.time_banks.insert(player_id, self.settings.time_bank);
+    }
+
+    /// Forgets a player who has left, stopping the clock if it was their turn.
+    pub fn remove_player(&mut self, player_id: PlayerId) {
+        self.time_banks.remove(&player_id);
+        if self.get_player() == Some(player_id) {
+            self.turn = None;
+        }
+    }
+
+    /// Adds `time_bank_refill` to every player's time bank, up to `time_bank`. Called at the start of every hand.
+    pub fn refill(&mut self) {
+        for time_bank in self.time_banks.values_mut() {
+            *time_bank = (*time_bank + self.settings.time_bank_refill).min(self.settings.time_bank);
+        }
+    }
+
+    /// Returns the player whose turn is being timed.
+    pub fn get_player(&self) -> Option<PlayerId> {
+        self.turn.map(|turn| turn.player_id)
+    }
+
+    pub fn get_time_bank(&self, player_id: PlayerId) -> Duration {
+        self.time_banks.get(&player_id).cloned().unwrap_or_default()
+    }
+
+    /// Starts timing `player_id`'s decision.
+    pub fn start_turn(&mut self, player_id: PlayerId) {
+        self.turn = Some(Turn { player_id, started: Instant::now() });
+    }
+
+    /// Stops the clock, taking any time the player used beyond `action_time` out of their time bank.
+    pub fn end_turn(&mut self) {
+        if let Some(turn) = self.turn.take() {
+            let over_time = turn.started.elapsed().saturating_sub(self.settings.action_time);
+            if let Some(time_bank) = self.time_banks.get_mut(&turn.player_id) {
+                *time_bank = time_bank.saturating_sub(over_time);
+            }
+        }
+    }
+
+    /// Returns when the player whose turn it is runs out of time, or `None` if nobody is being timed.
+    pub fn get_deadline(&self) -> Option<Instant> {
+        self.turn.map(|turn| turn.started + self.settings.action_time + self.get_time_bank(turn.player_id))
+    }
+
+    /// Starts timing the choice of how many times to run the board.
+    pub fn start_run_count_choice(&mut self) {
+        self.run_count_choice = Some(Instant::now());
+    }
+
+    pub fn end_run_count_choice(&mut self) {
+        self.run_count_choice = None;
+    }
+
+    /// Returns when the players choosing how many times to run the board run out of time, or `None` if they are not
+    /// being timed.
+    pub fn get_run_count_deadline(&self) -> Option<Instant> {
+        self.run_count_choice.map(|started| started + self.settings.action_time)
+    }
+
+    /// Returns how long the player whose turn it is has left.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<(PlayerId, Duration, Duration)>` - The player, what is left of their `action_time`, and what is left
+    ///   of their time bank, or `None` if nobody is being timed.
+    pub fn get_remaining(&self) -> Option<(PlayerId, Duration, Duration)> {
+        self.turn.map(|turn| {
+            let elapsed = turn.started.elapsed();
+            let action_time = self.settings.action_time.saturating_sub(elapsed);
+            let over_time = elapsed.saturating_sub(self.settings.action_time);
+            (turn.player_id, action_time, self.get_time_bank(turn.player_id).saturating_sub(over_time))
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use tokio::time;
+
+    use super::*;
+
+    fn clock() -> ActionClock {
+        let mut clock = ActionClock::new(ClockSettings::new(Duration::from_secs(10), Duration::from_secs(20), Duration::from_secs(5)));
+        clock.add_player(1);
+        clock.add_player(2);
+        clock
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_time_bank_is_used_after_the_action_time() {
+        let mut clock = clock();
+        clock.start_turn(1);
+        assert_eq!(clock.get_deadline(), Some(Instant::now() + Duration::from_secs(30)));
+
+        time::advance(Duration::from_secs(4)).await;
+        assert_eq!(clock.get_remaining(), Some((1, Duration::from_secs(6), Duration::from_secs(20))));
+        time::advance(Duration::from_secs(8)).await;
+        assert_eq!(clock.get_remaining(), Some((1, Duration::ZERO, Duration::from_secs(18))));
+
+        clock.end_turn();
+        assert_eq!(clock.get_player(), None);
+        assert_eq!(clock.get_time_bank(1), Duration::from_secs(18));
+        assert_eq!(clock.get_time_bank(2), Duration::from_secs(20));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_time_bank_refills_up_to_its_size() {
+        let mut clock = clock();
+        clock.start_turn(1);
+        time::advance(Duration::from_secs(45)).await;
+        clock.end_turn();
+        assert_eq!(clock.get_time_bank(1), Duration::ZERO);
+
+        clock.refill();
+        assert_eq!(clock.get_time_bank(1), Duration::from_secs(5));
+        assert_eq!(clock.get_time_bank(2), Duration::from_secs(20));
+
+        clock.start_turn(2);
+        clock.remove_player(2);
+        assert_eq!(clock.get_deadline(), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_run_count_choice_does_not_use_time_banks() {
+        let mut clock = clock();
+        clock.start_run_count_choice();
+        assert_eq!(clock.get_run_count_deadline(), Some(Instant::now() + Duration::from_secs(10)));
+        assert_eq!(clock.get_deadline(), None);
+
+        time::advance(Duration::from_secs(15)).await;
+        clock.end_run_count_choice();
+        assert_eq!(clock.get_run_count_deadline(), None);
+        assert_eq!(clock.get_time_bank(1), Duration::from_secs(20));
+    }
+}
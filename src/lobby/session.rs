@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rand::Rng;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::{task, time};
+
+use crate::lobby::LobbyClient;
+use crate::messages::{Handshake, HandshakeOk, MessageType, ServerError};
+
+/// How long a client's session, and their seat, is kept for them after their connection drops.
+pub(crate) const RECONNECT_GRACE_PERIOD: Duration = Duration::from_secs(60);
+
+
+/// A client's session.
+///
+/// # Fields
+///
+/// * `client_id`: The id the client keeps across connections.
+/// * `parked`: The client's place in the lobby while they are disconnected, or `None` while they are connected.
+/// * `num_disconnections`: How many times the client has disconnected, so a grace period that has been cut short by
+///   the client reconnecting does not end a later one.
+#[derive(Debug)]
+struct Session {
+    client_id: u128,
+    parked: Option<LobbyClient>,
+    num_disconnections: u32
+}
+
+
+/// The sessions of the clients connected to the server, and of those who disconnected less than a grace period ago.
+/// Each session has a secret token, which is the only way to get its client_id back on a new connection.
+///
+/// # Notes
+///
+/// The lock is never held while waiting on a table.
+#[derive(Debug, Clone)]
+pub(crate) struct Sessions {
+    sessions: Arc<Mutex<HashMap<String, Session>>>,
+    grace_period: Duration
+}
+
+impl Sessions {
+    pub fn new(grace_period: Duration) -> Sessions {
+        Sessions {
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+            grace_period
+        }
+    }
+
+    /// Answers a client's `Handshake`, either starting a new session or resuming the one named by its token. The
+    /// client is sent `HandshakeOk` and, if they are resuming a session while seated at a table, where the game is up
+    /// to.
+    ///
+    /// # Parameters
+    ///
+    /// * `handshake`: The client's handshake.
+    /// * `sender`: Where to send the client's messages on this connection.
+    /// * `rng`: Picks client ids and session tokens.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(LobbyClient, String), ServerError>` - The client and their session token, or `UnknownSession` or
+    ///   `SessionInUse` if the session cannot be resumed.
+    pub async fn handshake(&self, handshake: &Handshake, sender: UnboundedSender<MessageType>, rng: &mut (impl Rng + Send))
+        -> Result<(LobbyClient, String), ServerError> {
+        match handshake.session_token() {
+            None => {
+                let (client_id, session_token) = self.start(rng);
+                let _ = sender.send(HandshakeOk::new(client_id, session_token.clone()).into());
+                Ok((LobbyClient::new(client_id, sender), session_token))
+            },
+            Some(session_token) => {
+                let (client_id, mut client) = self.resume(session_token)?;
+                let _ = sender.send(HandshakeOk::new(client_id, session_token.clone()).into());
+                client.reconnect(sender).await;
+                Ok((client, session_token.clone()))
+            }
+        }
+    }
+
+    /// Starts a session with a new client_id.
+    ///
+    /// # Returns
+    ///
+    /// * `(u128, String)` - The client_id and the session token.
+    fn start(&self, rng: &mut impl Rng) -> (u128, String) {
+        let mut sessions = self.sessions.lock().expect("Failed to lock sessions");
+
+        let mut client_id: u128 = rng.gen();
+        while sessions.values().any(|session| session.client_id == client_id) {
+            client_id = rng.gen();
+        }
+        let mut session_token = hex::encode(rng.gen::<[u8; 32]>());
+        while sessions.contains_key(&session_token) {
+            session_token = hex::encode(rng.gen::<[u8; 32]>());
+        }
+
+        sessions.insert(session_token.clone(), Session { client_id, parked: None, num_disconnections: 0 });
+        (client_id, session_token)
+    }
+
+    /// Takes a disconnected client's place in the lobby back out of their session.
+    fn resume(&self, session_token: &str) -> Result<(u128, LobbyClient), ServerError> {
+        let mut sessions = self.sessions.lock().expect("Failed to lock sessions");
+        let session = sessions.get_mut(session_token).ok_or(ServerError::UnknownSession)?;
+        let client = session.parked.take().ok_or(ServerError::SessionInUse)?;
+        Ok((session.client_id, client))
+    }
+
+    /// Keeps `client` in their session after their connection drops. If they have not reconnected by the end of the
+    /// grace period, the session ends and they leave their table, which folds their hand if one is being played.
+    pub fn park(&self, session_token: String, client: LobbyClient) {
+        let num_disconnections = {
+            let mut sessions = self.sessions.lock().expect("Failed to lock sessions");
+            let session = sessions.get_mut(&session_token).expect("Expected the client to have a session");
+            session.parked = Some(client);
+            session.num_disconnections += 1;
+            session.num_disconnections
+        };
+
+        let sessions = Arc::clone(&self.sessions);
+        let grace_period = self.grace_period;
+        task::spawn(async move {
+            time::sleep(grace_period).await;
+
+            let expired = {
+                let mut sessions = sessions.lock().expect("Failed to lock sessions");
+                match sessions.get(&session_token) {
+                    Some(session) if session.parked.is_some() && session.num_disconnections == num_disconnections => {
+                        sessions.remove(&session_token).and_then(|session| session.parked)
+                    },
+                    _ => None
+                }
+            };
+            if let Some(mut client) = expired {
+                client.disconnect().await;
+            }
+        });
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::OsRng;
+    use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+    use super::*;
+    use crate::lobby::Lobby;
+    use crate::lobby::clock::ClockSettings;
+    use crate::messages::{JoinTable, StartGame, StartNewTable, TableStatus};
+
+    const GRACE_PERIOD: Duration = Duration::from_secs(30);
+
+    async fn connect(sessions: &Sessions, session_token: Option<String>)
+        -> Result<(LobbyClient, String, UnboundedReceiver<MessageType>), ServerError> {
+        let (sender, mut receiver) = unbounded_channel();
+        let (client, session_token) = sessions.handshake(&Handshake::new(session_token), sender, &mut OsRng).await?;
+        match receiver.try_recv() {
+            Ok(MessageType::HandshakeOk(handshake_ok)) => assert_eq!(handshake_ok.session_token(), &session_token),
+            other => panic!("Expected HandshakeOk, got {:?}", other)
+        }
+        Ok((client, session_token, receiver))
+    }
+
+    /// Seats two connected clients at a table and starts the game.
+    async fn start_game(lobby: &Lobby, sessions: &Sessions) -> [(LobbyClient, String, UnboundedReceiver<MessageType>); 2] {
+        let (mut leader, leader_token, leader_messages) = connect(sessions, None).await.unwrap();
+        let (mut player, player_token, player_messages) = connect(sessions, None).await.unwrap();
+        leader.handle_message(lobby, StartNewTable::new(leader.client_id, None, None, None, false, None).into(), &mut OsRng).await;
+        let table_id = leader.get_table_id().unwrap();
+        player.handle_message(lobby, JoinTable::new(player.client_id, table_id).into(), &mut OsRng).await;
+        leader.handle_message(lobby, StartGame::new(leader.client_id, table_id).into(), &mut OsRng).await;
+        [(leader, leader_token, leader_messages), (player, player_token, player_messages)]
+    }
+
+    fn last_action_request(messages: &mut UnboundedReceiver<MessageType>) -> bool {
+        std::iter::from_fn(|| messages.try_recv().ok()).last().is_some_and(|message| matches!(message, MessageType::ActionRequest(_)))
+    }
+
+    #[tokio::test]
+    async fn test_sessions_are_resumed_with_their_token() {
+        let sessions = Sessions::new(GRACE_PERIOD);
+        let (client, session_token, _) = connect(&sessions, None).await.unwrap();
+        let client_id = client.client_id;
+        assert_eq!(session_token.len(), 64);
+
+        assert_eq!(connect(&sessions, Some(String::from("guess"))).await.unwrap_err(), ServerError::UnknownSession);
+        assert_eq!(connect(&sessions, Some(session_token.clone())).await.unwrap_err(), ServerError::SessionInUse);
+
+        sessions.park(session_token.clone(), client);
+        let (client, _, _) = connect(&sessions, Some(session_token)).await.unwrap();
+        assert_eq!(client.client_id, client_id);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_reconnecting_client_gets_their_seat_back() {
+        let lobby = Lobby::new(ClockSettings::default());
+        let sessions = Sessions::new(GRACE_PERIOD);
+        let [mut leader, player] = start_game(&lobby, &sessions).await;
+        let (client, session_token, _) = if last_action_request(&mut leader.2) { leader } else { player };
+        let table_id = client.get_table_id().unwrap();
+
+        // The client whose turn it is disconnects and comes back within the grace period.
+        sessions.park(session_token.clone(), client);
+        time::sleep(GRACE_PERIOD / 2).await;
+        let (client, _, mut messages) = connect(&sessions, Some(session_token.clone())).await.unwrap();
+        assert_eq!(client.get_table_id(), Some(table_id));
+
+        match messages.try_recv() {
+            Ok(MessageType::TableSnapshot(snapshot)) => {
+                assert_eq!(*snapshot.table_id(), table_id);
+                assert_eq!(*snapshot.info().status(), TableStatus::Playing);
+                assert_eq!(snapshot.view().as_ref().unwrap().hole_cards().len(), 2);
+            },
+            other => panic!("Expected a TableSnapshot, got {:?}", other)
+        }
+        assert!(matches!(messages.try_recv(), Ok(MessageType::ActionRequest(_))));
+
+        // The grace period from the first disconnection does not end the session after it has been resumed.
+        time::sleep(GRACE_PERIOD).await;
+        sessions.park(session_token.clone(), client);
+        assert!(connect(&sessions, Some(session_token)).await.is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_seat_is_given_up_after_the_grace_period() {
+        let lobby = Lobby::new(ClockSettings::default());
+        let sessions = Sessions::new(GRACE_PERIOD);
+        let [(leader, leader_token, _), (_player, _, mut player_messages)] = start_game(&lobby, &sessions).await;
+
+        sessions.park(leader_token.clone(), leader);
+        time::sleep(GRACE_PERIOD * 2).await;
+
+        // The leader's hand is folded, and the game stops with only one player left.
+        assert_eq!(connect(&sessions, Some(leader_token)).await.unwrap_err(), ServerError::UnknownSession);
+        let messages: Vec<String> = std::iter::from_fn(|| player_messages.try_recv().ok()).map(|message| message.to_string()).collect();
+        assert_eq!(messages.last().map(String::as_str), Some("HandFinished"));
+        assert_eq!(*lobby.get_tables_info()[0].num_players(), 1);
+        assert_eq!(*lobby.get_tables_info()[0].status(), TableStatus::Waiting);
+    }
+}
@@ -0,0 +1,940 @@
+use std::collections::HashMap;
+
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::sync::{oneshot, watch};
+use tokio::task::{self, JoinHandle};
+use tokio::time::{self, Instant};
+
+use crate::game::Game;
+use crate::game::action::Action;
+use crate::game::blinds::BlindStructure;
+use crate::game::player::{Player, PlayerId};
+use crate::lobby::clock::{ActionClock, ClockSettings};
+use crate::messages::{ActionRequest, ActionTaken, ClockUpdate, GameStarted, HandFinished, HandStarted, MessageType, RunCountRequest, ServerError, ShuffleCommitment, StartNewTable, TableInfo, TableSnapshot, TableStatus};
+
+/// The big blind at tables created with `StartNewTable` that do not choose one.
+const BIG_BLIND: i32 = 2;
+/// The chips each player sits down with at tables created with `StartNewTable` that do not choose a buy-in.
+const BUY_IN: i32 = 1000;
+
+
+/// The messages to send, and the client to send each one to, in order.
+pub(crate) type Outbox = Vec<(u128, MessageType)>;
+
+
+/// How a table is played, chosen by the client who opens it.
+///
+/// # Fields
+///
+/// * `small_blind`: The small blind.
+/// * `big_blind`: The big blind.
+/// * `buy_in`: The chips each player sits down with.
+/// * `provably_fair`: Whether every hand is dealt with a commit-reveal shuffle that the players add entropy to.
+/// * `max_runs`: The most times the board can be run when the players are all in before the river.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct TableSettings {
+    small_blind: i32,
+    big_blind: i32,
+    buy_in: i32,
+    provably_fair: bool,
+    max_runs: u32
+}
+
+impl TableSettings {
+    /// Checks the settings before a table is opened with them.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<TableSettings, ServerError>` - The settings, or `InvalidStakes` if a blind or the buy-in is not
+    ///   positive, the small blind is more than the big blind, or the buy-in is less than the big blind, or
+    ///   `InvalidMaxRuns` if `max_runs` is 0.
+    pub fn new(small_blind: i32, big_blind: i32, buy_in: i32, provably_fair: bool, max_runs: u32) -> Result<TableSettings, ServerError> {
+        if small_blind <= 0 || big_blind < small_blind || buy_in < big_blind {
+            return Err(ServerError::InvalidStakes);
+        }
+        if max_runs == 0 {
+            return Err(ServerError::InvalidMaxRuns);
+        }
+
+        Ok(TableSettings {
+            small_blind,
+            big_blind,
+            buy_in,
+            provably_fair,
+            max_runs
+        })
+    }
+
+    /// Reads the settings a client asked for. The small blind defaults to half the big blind, and the other stakes to
+    /// those of a default table.
+    pub fn from_message(start_new_table: &StartNewTable) -> Result<TableSettings, ServerError> {
+        let big_blind = start_new_table.big_blind().unwrap_or(BIG_BLIND);
+        TableSettings::new(start_new_table.small_blind().unwrap_or(big_blind / 2), big_blind,
+                           start_new_table.buy_in().unwrap_or(BUY_IN), *start_new_table.provably_fair(),
+                           start_new_table.max_runs().unwrap_or(1))
+    }
+}
+
+impl Default for TableSettings {
+    fn default() -> TableSettings {
+        TableSettings::new(BIG_BLIND / 2, BIG_BLIND, BUY_IN, false, 1).expect("Expected the default stakes to be valid")
+    }
+}
+
+
+/// A table in the lobby.
+///
+/// # Fields
+///
+/// * `game`: The game played at the table.
+/// * `leader`: The client who created the table, or the longest seated client once they have left.
+/// * `players`: The `PlayerId` each seated client plays as.
+/// * `next_player_id`: The `PlayerId` the next client to join will get.
+/// * `is_playing`: Whether the leader has started the game. Hands are dealt one after another until fewer than two
+///   players have chips.
+/// * `num_sent_actions`: How many of the current hand's actions have been sent to the table.
+/// * `clock`: Times the decision of the player whose turn it is, and the choice of how many times to run the board.
+/// * `buy_in`: The chips each player sits down with.
+#[derive(Debug)]
+pub(crate) struct LobbyTable {
+    game: Game,
+    leader: u128,
+    players: HashMap<u128, PlayerId>,
+    next_player_id: PlayerId,
+    is_playing: bool,
+    num_sent_actions: usize,
+    clock: ActionClock,
+    buy_in: i32
+}
+
+impl LobbyTable {
+    /// Opens a table with `leader` seated at it. At a provably fair table, every hand is dealt with a commit-reveal
+    /// shuffle that the players can add entropy to with `add_shuffle_entropy`. If `max_runs` is more than 1, players
+    /// who are all in before the river choose how many times to run the board with `choose_run_count`.
+    pub(crate) fn new(table_id: u128, leader: u128, clock_settings: ClockSettings, settings: TableSettings) -> LobbyTable {
+        let mut table = LobbyTable {
+            game: Game::new(table_id, settings.big_blind, settings.buy_in),
+            leader,
+            players: HashMap::new(),
+            next_player_id: 1,
+            is_playing: false,
+            num_sent_actions: 0,
+            clock: ActionClock::new(clock_settings),
+            buy_in: settings.buy_in
+        };
+        table.game.set_blind_structure(BlindStructure::new(settings.small_blind, settings.big_blind));
+        table.game.set_provably_fair(settings.provably_fair);
+        table.game.set_max_runs(settings.max_runs);
+        table.seat(leader);
+        table
+    }
+
+    fn seat(&mut self, client_id: u128) {
+        let player_id = self.next_player_id;
+        self.next_player_id += 1;
+        self.game.add_player(Player::new(player_id, format!("Player {}", player_id), self.buy_in));
+        self.clock.add_player(player_id);
+        self.players.insert(client_id, player_id);
+    }
+
+    pub(crate) fn get_info(&self) -> TableInfo {
+        let blind_structure = self.game.get_blind_structure();
+        let status = if self.is_playing { TableStatus::Playing } else { TableStatus::Waiting };
+        TableInfo::new(self.game.get_game_id(), self.game.get_num_players(), self.game.get_table().get_num_seats(),
+                       blind_structure.get_small_blind(), blind_structure.get_big_blind(), self.buy_in, status,
+                       self.game.is_provably_fair(), self.game.get_max_runs())
+    }
+
+    /// Returns the commitment to the shuffle of the next hand, or `None` if the table is not provably fair.
+    pub(crate) fn get_shuffle_commitment(&self) -> Option<ShuffleCommitment> {
+        self.game.get_shuffle_commitment()
+            .map(|commitment| ShuffleCommitment::new(self.game.get_game_id(), commitment.to_string()))
+    }
+
+    /// Returns when the player whose turn it is, or the players choosing how many times to run the board, run out of
+    /// time, or `None` if nobody is being timed.
+    pub(crate) fn get_deadline(&self) -> Option<Instant> {
+        self.clock.get_deadline().or(self.clock.get_run_count_deadline())
+    }
+
+    /// Returns whether everyone has left the table.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.players.is_empty()
+    }
+
+    fn get_client_id(&self, player_id: PlayerId) -> Option<u128> {
+        self.players.iter().find(|(_, id)| **id == player_id).map(|(client_id, _)| *client_id)
+    }
+
+    fn get_player_id(&self, client_id: u128) -> Result<PlayerId, ServerError> {
+        self.players.get(&client_id).cloned().ok_or(ServerError::NotAtTable { table_id: self.game.get_game_id() })
+    }
+
+    /// Returns the seated clients whose players are still at the game, so have not busted.
+    fn get_clients_in_game(&self) -> Vec<(u128, PlayerId)> {
+        let mut clients: Vec<(u128, PlayerId)> = self.players.iter()
+            .filter(|(_, player_id)| self.game.get_table().get_seat(player_id).is_some())
+            .map(|(client_id, player_id)| (*client_id, *player_id))
+            .collect();
+        clients.sort_by_key(|(_, player_id)| *player_id);
+        clients
+    }
+
+    /// Builds a message for every seated client, including those who have busted, so they can keep watching.
+    fn broadcast(&self, message: impl Fn() -> MessageType) -> Outbox {
+        let mut client_ids: Vec<u128> = self.players.keys().cloned().collect();
+        client_ids.sort_by_key(|client_id| self.players[client_id]);
+        client_ids.into_iter().map(|client_id| (client_id, message())).collect()
+    }
+
+    /// Seats `client_id` at the table. A client who joins while a hand is being played is dealt in from the next hand.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Outbox, ServerError>` - The commitment to the next hand's shuffle for the client if the table is
+    ///   provably fair, or an error if the client is already seated or the table is full.
+    pub(crate) fn join(&mut self, client_id: u128) -> Result<Outbox, ServerError> {
+        let table_id = self.game.get_game_id();
+        if self.players.contains_key(&client_id) {
+            return Err(ServerError::AlreadyAtTable { table_id });
+        }
+        if self.game.get_table().is_full() {
+            return Err(ServerError::TableFull { table_id });
+        }
+
+        self.seat(client_id);
+        Ok(self.get_shuffle_commitment().map(|commitment| (client_id, commitment.into())).into_iter().collect())
+    }
+
+    /// Starts the game and deals the first hand.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Outbox, ServerError>` - `GameStarted` for everyone at the table followed by the first hand, or an
+    ///   error if the client is not the table's leader, the game has already started, or fewer than two players have
+    ///   chips.
+    pub(crate) fn start_game(&mut self, client_id: u128) -> Result<Outbox, ServerError> {
+        let table_id = self.game.get_game_id();
+        self.get_player_id(client_id)?;
+        if self.leader != client_id {
+            return Err(ServerError::NotLeader { table_id });
+        }
+        if self.is_playing {
+            return Err(ServerError::GameAlreadyStarted { table_id });
+        }
+        if self.game.get_table().get_players().len() < 2 {
+            return Err(ServerError::NotEnoughPlayers { table_id });
+        }
+
+        self.is_playing = true;
+        let mut outbox = self.broadcast(|| GameStarted::new(table_id).into());
+        outbox.extend(self.start_hand());
+        outbox.extend(self.sync());
+        Ok(outbox)
+    }
+
+    /// Plays `action` for `client_id`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Outbox, ServerError>` - The action sent to everyone at the table and whatever it led to, or
+    ///   `InvalidAction` if the game rejected it.
+    pub(crate) fn play_action(&mut self, client_id: u128, action: Action) -> Result<Outbox, ServerError> {
+        let player_id = self.get_player_id(client_id)?;
+        self.game.apply_action(player_id, action).map_err(|reason| ServerError::InvalidAction { reason })?;
+        self.clock.end_turn();
+        Ok(self.sync())
+    }
+
+    /// Adds `entropy` from `client_id` to the shuffle of the next hand.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Outbox, ServerError>` - Nothing to send, or `InvalidEntropy` if the game rejected the entropy.
+    pub(crate) fn add_shuffle_entropy(&mut self, client_id: u128, entropy: String) -> Result<Outbox, ServerError> {
+        let player_id = self.get_player_id(client_id)?;
+        self.game.add_shuffle_entropy(player_id, entropy).map_err(|reason| ServerError::InvalidEntropy { reason })?;
+        Ok(Outbox::new())
+    }
+
+    /// Chooses for `client_id` how many times to run the board.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Outbox, ServerError>` - The rest of the hand if they were the last to choose, or `InvalidRunCount` if
+    ///   the game rejected the choice.
+    pub(crate) fn choose_run_count(&mut self, client_id: u128, runs: u32) -> Result<Outbox, ServerError> {
+        let player_id = self.get_player_id(client_id)?;
+        self.game.choose_run_count(player_id, runs).map_err(|reason| ServerError::InvalidRunCount { reason })?;
+        Ok(self.sync())
+    }
+
+    /// Acts for the player whose turn it is once they have run out of time. They check if they can, and otherwise fold.
+    /// Players who run out of time to choose how many times to run the board run it once.
+    ///
+    /// # Returns
+    ///
+    /// * `Outbox` - The action sent to everyone at the table and whatever it led to, which is nothing if nobody has run
+    ///   out of time.
+    pub(crate) fn time_out(&mut self) -> Outbox {
+        if self.clock.get_run_count_deadline().is_some_and(|deadline| deadline <= Instant::now()) {
+            self.clock.end_run_count_choice();
+            for player_id in self.game.get_players_to_choose_runs() {
+                self.game.choose_run_count(player_id, 1).expect("Expected a player the game is waiting on to be able to choose");
+            }
+            return self.sync();
+        }
+
+        let player_id = match (self.clock.get_player(), self.clock.get_deadline()) {
+            (Some(player_id), Some(deadline)) if deadline <= Instant::now() => player_id,
+            _ => return Outbox::new()
+        };
+
+        self.clock.end_turn();
+        if self.game.apply_action(player_id, Action::Check).is_err() {
+            self.game.apply_action(player_id, Action::Fold).expect("Expected the player whose turn it is to be able to fold");
+        }
+        self.sync()
+    }
+
+    /// Takes `client_id` away from the table, folding their hand if one is being played.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Outbox, ServerError>` - What the rest of the table is sent because the client left, or `NotAtTable`.
+    ///
+    /// # Notes
+    ///
+    /// If the leader leaves, the client who has been seated the longest becomes the leader.
+    pub(crate) fn leave(&mut self, client_id: u128) -> Result<Outbox, ServerError> {
+        let player_id = self.get_player_id(client_id)?;
+        self.players.remove(&client_id);
+        self.game.remove_player(player_id);
+        self.clock.remove_player(player_id);
+
+        match self.players.iter().min_by_key(|(_, player_id)| **player_id) {
+            Some((next_leader, _)) if self.leader == client_id => self.leader = *next_leader,
+            Some(_) => {},
+            None => return Ok(Outbox::new())
+        }
+
+        Ok(self.sync())
+    }
+
+    /// Builds what a client who has reconnected needs to pick the game back up: a `TableSnapshot`, followed by an
+    /// `ActionRequest` or `RunCountRequest` if the game is waiting on them, the clock, and the commitment to the next
+    /// hand's shuffle.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Outbox, ServerError>` - The messages for the client, or `NotAtTable`.
+    pub(crate) fn snapshot(&self, client_id: u128) -> Result<Outbox, ServerError> {
+        let table_id = self.game.get_game_id();
+        let player_id = self.get_player_id(client_id)?;
+        let view = self.game.get_table().get_seat(&player_id).map(|_| self.game.view_for(player_id));
+
+        let mut outbox: Outbox = vec![(client_id, TableSnapshot::new(table_id, self.get_info(), view, self.game.spectator_view()).into())];
+        if self.game.is_hand_in_progress() && self.game.next_to_act() == Some(player_id) {
+            outbox.push((client_id, ActionRequest::new(table_id, self.game.view_for(player_id)).into()));
+        }
+        if self.game.get_players_to_choose_runs().contains(&player_id) {
+            outbox.extend(self.get_run_count_request().map(|request| (client_id, request.into())));
+        }
+        if let Some(clock_update) = self.get_clock_update() {
+            outbox.push((client_id, clock_update.into()));
+        }
+        if let Some(commitment) = self.get_shuffle_commitment() {
+            outbox.push((client_id, commitment.into()));
+        }
+        Ok(outbox)
+    }
+
+    /// Deals a new hand and sends each player dealt in their hole cards. At a provably fair table, everyone is then sent
+    /// the commitment to the shuffle of the hand after this one.
+    fn start_hand(&mut self) -> Outbox {
+        self.game.start_hand(false);
+        self.num_sent_actions = 0;
+        self.clock.refill();
+
+        let table_id = self.game.get_game_id();
+        let mut outbox: Outbox = self.get_clients_in_game().into_iter()
+            .map(|(client_id, player_id)| (client_id, HandStarted::new(table_id, self.game.view_for(player_id)).into()))
+            .collect();
+        if let Some(commitment) = self.get_shuffle_commitment() {
+            outbox.extend(self.broadcast(|| commitment.clone().into()));
+        }
+        outbox
+    }
+
+    /// Returns how long the player whose turn it is has left, or `None` if nobody is being timed.
+    fn get_clock_update(&self) -> Option<ClockUpdate> {
+        self.clock.get_remaining().map(|(player_id, action_time, time_bank)| {
+            ClockUpdate::new(self.game.get_game_id(), player_id, action_time.as_millis() as u64, time_bank.as_millis() as u64)
+        })
+    }
+
+    /// Starts `player_id`'s turn, asking them for their action and starting the clock for everyone.
+    fn start_turn(&mut self, player_id: PlayerId) -> Outbox {
+        let table_id = self.game.get_game_id();
+        self.clock.start_turn(player_id);
+
+        let mut outbox = Outbox::new();
+        if let Some(client_id) = self.get_client_id(player_id) {
+            outbox.push((client_id, ActionRequest::new(table_id, self.game.view_for(player_id)).into()));
+        }
+        if let Some(clock_update) = self.get_clock_update() {
+            outbox.extend(self.broadcast(|| clock_update.clone().into()));
+        }
+        outbox
+    }
+
+    /// Returns what to ask the players choosing how many times to run the board, or `None` if they are not being timed.
+    fn get_run_count_request(&self) -> Option<RunCountRequest> {
+        self.clock.get_run_count_deadline().map(|deadline| {
+            let time = deadline.saturating_duration_since(Instant::now());
+            RunCountRequest::new(self.game.get_game_id(), self.game.get_max_runs(), time.as_millis() as u64)
+        })
+    }
+
+    /// Asks the players the game is waiting on how many times to run the board and starts the clock on their choice,
+    /// unless they have already been asked.
+    fn start_run_count_choice(&mut self) -> Outbox {
+        let player_ids = self.game.get_players_to_choose_runs();
+        if player_ids.is_empty() || self.clock.get_run_count_deadline().is_some() {
+            return Outbox::new();
+        }
+
+        self.clock.start_run_count_choice();
+        let request = self.get_run_count_request().expect("Expected the run count choice to be timed");
+        player_ids.into_iter()
+            .filter_map(|player_id| self.get_client_id(player_id))
+            .map(|client_id| (client_id, request.clone().into()))
+            .collect()
+    }
+
+    /// Sends the table what has happened since it was last synced, then keeps the game going: a finished hand is
+    /// followed by the next one, the player whose turn it is is asked for their action, and players who are all in are
+    /// asked how many times to run the board.
+    fn sync(&mut self) -> Outbox {
+        let table_id = self.game.get_game_id();
+        let mut outbox = Outbox::new();
+
+        loop {
+            let actions = self.game.get_hand_history().map_or(Vec::new(), |hand_history| hand_history.actions().clone());
+            for action in actions.into_iter().skip(self.num_sent_actions) {
+                outbox.extend(self.broadcast(|| ActionTaken::new(table_id, action).into()));
+                self.num_sent_actions += 1;
+            }
+
+            if self.game.is_hand_in_progress() {
+                // A player who is already being timed has already been asked.
+                match self.game.next_to_act() {
+                    Some(player_id) if self.clock.get_player().is_none() => outbox.extend(self.start_turn(player_id)),
+                    Some(_) => {},
+                    None => {
+                        self.clock.end_turn();
+                        outbox.extend(self.start_run_count_choice());
+                    }
+                }
+                return outbox;
+            }
+            self.clock.end_turn();
+            self.clock.end_run_count_choice();
+            if !self.is_playing {
+                return outbox;
+            }
+
+            // The hand is over.
+            for error in self.game.take_history_errors() {
+                eprintln!("Table {}: {}", table_id, error);
+            }
+            let view = self.game.spectator_view();
+            let shuffle_reveal = self.game.get_shuffle_reveal();
+            outbox.extend(self.broadcast(|| HandFinished::new(table_id, view.clone(), shuffle_reveal.clone()).into()));
+            if self.game.get_table().get_players().len() < 2 {
+                self.is_playing = false;
+                return outbox;
+            }
+            outbox.extend(self.start_hand());
+        }
+    }
+}
+
+
+/// A request to a table's task. The task answers on `reply` once it has acted on the request and sent the table
+/// whatever the request led to.
+#[derive(Debug)]
+enum TableCommand {
+    Join { client_id: u128, sender: UnboundedSender<MessageType>, reply: oneshot::Sender<Result<(), ServerError>> },
+    Reconnect { client_id: u128, sender: UnboundedSender<MessageType>, reply: oneshot::Sender<Result<(), ServerError>> },
+    Leave { client_id: u128, reply: oneshot::Sender<Result<(), ServerError>> },
+    StartGame { client_id: u128, reply: oneshot::Sender<Result<(), ServerError>> },
+    PlayerAction { client_id: u128, action: Action, reply: oneshot::Sender<Result<(), ServerError>> },
+    AddShuffleEntropy { client_id: u128, entropy: String, reply: oneshot::Sender<Result<(), ServerError>> },
+    ChooseRunCount { client_id: u128, runs: u32, reply: oneshot::Sender<Result<(), ServerError>> }
+}
+
+
+/// A way to reach a table running as its own task. The task owns the table's `Game` and the connections of the
+/// clients seated at it, so tables never wait on each other.
+///
+/// # Fields
+///
+/// * `table_id`: The id of the table.
+/// * `commands`: Sends requests to the task.
+/// * `info`: The table's `TableInfo`, which the task updates after every request.
+#[derive(Debug, Clone)]
+pub(crate) struct TableHandle {
+    table_id: u128,
+    commands: UnboundedSender<TableCommand>,
+    info: watch::Receiver<TableInfo>
+}
+
+impl TableHandle {
+    /// Starts a task for `table`, which must only have its leader seated.
+    ///
+    /// # Parameters
+    ///
+    /// * `table`: The table the task will own.
+    /// * `leader_sender`: Where to send the leader's messages.
+    ///
+    /// # Returns
+    ///
+    /// * `(TableHandle, JoinHandle<()>)` - The handle to the table, and the task, which finishes once everyone has left
+    ///   the table or panics if the table does.
+    pub(crate) fn spawn(table: LobbyTable, leader_sender: UnboundedSender<MessageType>) -> (TableHandle, JoinHandle<()>) {
+        let table_id = table.game.get_game_id();
+        let (commands, receiver) = unbounded_channel();
+        let (info_sender, info) = watch::channel(table.get_info());
+        // The leader is sent the commitment to the first hand's shuffle, as a client who joins is.
+        if let Some(commitment) = table.get_shuffle_commitment() {
+            let _ = leader_sender.send(commitment.into());
+        }
+        let senders = HashMap::from([(table.leader, leader_sender)]);
+
+        let task = task::spawn(run_table(table, senders, receiver, info_sender));
+        (TableHandle { table_id, commands, info }, task)
+    }
+
+    pub(crate) fn get_table_id(&self) -> u128 {
+        self.table_id
+    }
+
+    pub(crate) fn get_info(&self) -> TableInfo {
+        self.info.borrow().clone()
+    }
+
+    /// Seats `client_id` at the table and sends them the table's messages through `sender` from then on.
+    pub(crate) async fn join(&self, client_id: u128, sender: UnboundedSender<MessageType>) -> Result<(), ServerError> {
+        self.request(|reply| TableCommand::Join { client_id, sender, reply }).await
+    }
+
+    /// Sends `client_id` the table's messages through `sender` from now on, starting with a `TableSnapshot`.
+    pub(crate) async fn reconnect(&self, client_id: u128, sender: UnboundedSender<MessageType>) -> Result<(), ServerError> {
+        self.request(|reply| TableCommand::Reconnect { client_id, sender, reply }).await
+    }
+
+    pub(crate) async fn leave(&self, client_id: u128) -> Result<(), ServerError> {
+        self.request(|reply| TableCommand::Leave { client_id, reply }).await
+    }
+
+    pub(crate) async fn start_game(&self, client_id: u128) -> Result<(), ServerError> {
+        self.request(|reply| TableCommand::StartGame { client_id, reply }).await
+    }
+
+    pub(crate) async fn play_action(&self, client_id: u128, action: Action) -> Result<(), ServerError> {
+        self.request(|reply| TableCommand::PlayerAction { client_id, action, reply }).await
+    }
+
+    pub(crate) async fn add_shuffle_entropy(&self, client_id: u128, entropy: String) -> Result<(), ServerError> {
+        self.request(|reply| TableCommand::AddShuffleEntropy { client_id, entropy, reply }).await
+    }
+
+    pub(crate) async fn choose_run_count(&self, client_id: u128, runs: u32) -> Result<(), ServerError> {
+        self.request(|reply| TableCommand::ChooseRunCount { client_id, runs, reply }).await
+    }
+
+    /// Sends a request to the task and waits for its answer.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), ServerError>` - The task's answer, or `UnknownTable` if the table has closed.
+    async fn request(&self, command: impl FnOnce(oneshot::Sender<Result<(), ServerError>>) -> TableCommand) -> Result<(), ServerError> {
+        let closed = ServerError::UnknownTable { table_id: self.table_id };
+        let (reply, answer) = oneshot::channel();
+        if self.commands.send(command(reply)).is_err() {
+            return Err(closed);
+        }
+        answer.await.unwrap_or(Err(closed))
+    }
+}
+
+
+/// Acts on the requests for a table, one at a time, and for players who run out of time, until everyone has left it.
+async fn run_table(
+    mut table: LobbyTable,
+    mut senders: HashMap<u128, UnboundedSender<MessageType>>,
+    mut commands: UnboundedReceiver<TableCommand>,
+    info: watch::Sender<TableInfo>
+) {
+    loop {
+        let deadline = table.get_deadline();
+        let command = tokio::select! {
+            command = commands.recv() => match command {
+                Some(command) => command,
+                None => break
+            },
+            _ = sleep_until(deadline) => {
+                deliver(&senders, table.time_out());
+                info.send_replace(table.get_info());
+                continue;
+            }
+        };
+
+        let (result, reply) = match command {
+            TableCommand::Join { client_id, sender, reply } => {
+                let result = table.join(client_id);
+                if result.is_ok() {
+                    senders.insert(client_id, sender);
+                }
+                (result, reply)
+            },
+            TableCommand::Reconnect { client_id, sender, reply } => {
+                let result = table.snapshot(client_id);
+                if result.is_ok() {
+                    senders.insert(client_id, sender);
+                }
+                (result, reply)
+            },
+            TableCommand::Leave { client_id, reply } => {
+                let result = table.leave(client_id);
+                if result.is_ok() {
+                    senders.remove(&client_id);
+                }
+                (result, reply)
+            },
+            TableCommand::StartGame { client_id, reply } => (table.start_game(client_id), reply),
+            TableCommand::PlayerAction { client_id, action, reply } => (table.play_action(client_id, action), reply),
+            TableCommand::AddShuffleEntropy { client_id, entropy, reply } => (table.add_shuffle_entropy(client_id, entropy), reply),
+            TableCommand::ChooseRunCount { client_id, runs, reply } => (table.choose_run_count(client_id, runs), reply)
+        };
+
+        // Send the table what happened before answering, so the client who asked sees it before anything they send
+        // next is acted on.
+        let result = result.map(|outbox| deliver(&senders, outbox));
+        info.send_replace(table.get_info());
+        // The client may have disconnected while waiting, and then nobody needs the answer.
+        let _ = reply.send(result);
+
+        if table.is_empty() {
+            break;
+        }
+    }
+}
+
+/// Waits until `deadline`, or forever if there is none.
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => time::sleep_until(deadline).await,
+        None => std::future::pending().await
+    }
+}
+
+/// Hands each message in `outbox` to its client's connection. Messages for clients who have disconnected are dropped.
+fn deliver(senders: &HashMap<u128, UnboundedSender<MessageType>>, outbox: Outbox) {
+    for (client_id, message) in outbox {
+        if let Some(sender) = senders.get(&client_id) {
+            let _ = sender.send(message);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::game::action::ActionError;
+    use crate::game::deck::{Deck, StackedDecks};
+    use crate::game::hand::{Card, Suit};
+    use crate::game::hand_history::ActionKind;
+    use crate::game::runout::RunoutError;
+
+    fn table_with_players(num_players: u128) -> LobbyTable {
+        let mut table = LobbyTable::new(7, 1, ClockSettings::default(), TableSettings::default());
+        for client_id in 2..=num_players {
+            table.join(client_id).unwrap();
+        }
+        table
+    }
+
+    /// Returns the client who was asked to act, checking that only one was.
+    fn requested_client(outbox: &Outbox) -> u128 {
+        let requests: Vec<u128> = outbox.iter()
+            .filter(|(_, message)| matches!(message, MessageType::ActionRequest(_)))
+            .map(|(client_id, _)| *client_id)
+            .collect();
+        assert_eq!(requests.len(), 1, "Expected one ActionRequest, got {:?}", outbox);
+        requests[0]
+    }
+
+    /// Takes every message sent to a client so far.
+    fn receive(messages: &mut UnboundedReceiver<MessageType>) -> Vec<MessageType> {
+        std::iter::from_fn(|| messages.try_recv().ok()).collect()
+    }
+
+    fn clock_updates(messages: &[MessageType]) -> Vec<(PlayerId, u64, u64)> {
+        messages.iter()
+            .filter_map(|message| match message {
+                MessageType::ClockUpdate(update) => Some((*update.player_id(), *update.action_time_ms(), *update.time_bank_ms())),
+                _ => None
+            })
+            .collect()
+    }
+
+    fn actions_taken(messages: &[MessageType]) -> Vec<(PlayerId, ActionKind)> {
+        messages.iter()
+            .filter_map(|message| match message {
+                MessageType::ActionTaken(action_taken) => Some((*action_taken.action().player_id(), *action_taken.action().kind())),
+                _ => None
+            })
+            .collect()
+    }
+
+    /// Opens a heads-up table that runs the board up to twice. Aces and kings are dealt, and the first run makes quad
+    /// aces and the second quad kings, so the players split the pot if they run it twice.
+    fn run_twice_table(clock_settings: ClockSettings) -> LobbyTable {
+        let cards = vec![
+            Card::new(Suit::Spades, 14), Card::new(Suit::Hearts, 14),
+            Card::new(Suit::Spades, 13), Card::new(Suit::Hearts, 13),
+            Card::new(Suit::Diamonds, 14), Card::new(Suit::Clubs, 14), Card::new(Suit::Hearts, 5),
+            Card::new(Suit::Spades, 9), Card::new(Suit::Diamonds, 11),
+            Card::new(Suit::Diamonds, 13), Card::new(Suit::Clubs, 13), Card::new(Suit::Clubs, 5),
+            Card::new(Suit::Diamonds, 9), Card::new(Suit::Clubs, 11)
+        ];
+
+        let mut table = LobbyTable::new(7, 1, clock_settings, TableSettings::new(1, 2, BUY_IN, false, 2).unwrap());
+        table.game.set_deck_source(Box::new(StackedDecks::new(vec![Deck::stacked(cards), Deck::new()])));
+        table
+    }
+
+    fn run_count_requests(messages: &[MessageType]) -> Vec<(u32, u64)> {
+        messages.iter()
+            .filter_map(|message| match message {
+                MessageType::RunCountRequest(request) => Some((*request.max_runs(), *request.time_ms())),
+                _ => None
+            })
+            .collect()
+    }
+
+    /// Returns each player's chips once the hand in `messages` has finished.
+    fn money_after_hand<'a>(messages: impl IntoIterator<Item = &'a MessageType>) -> Vec<i32> {
+        messages.into_iter()
+            .find_map(|message| match message {
+                MessageType::HandFinished(hand_finished) => Some(hand_finished.view().seats().iter().map(|seat| *seat.money()).collect()),
+                _ => None
+            })
+            .expect("Expected the hand to finish")
+    }
+
+    fn count_messages(outbox: &Outbox, client_id: u128, message_type: &str) -> usize {
+        outbox.iter().filter(|(recipient, message)| *recipient == client_id && message.to_string() == message_type).count()
+    }
+
+    #[test]
+    fn test_table_fills_up() {
+        let mut table = table_with_players(10);
+
+        assert_eq!(table.join(11).unwrap_err(), ServerError::TableFull { table_id: 7 });
+        assert_eq!(table.join(1).unwrap_err(), ServerError::AlreadyAtTable { table_id: 7 });
+    }
+
+    #[test]
+    fn test_leader_passes_on() {
+        let mut table = table_with_players(3);
+
+        assert_eq!(table.leave(4).unwrap_err(), ServerError::NotAtTable { table_id: 7 });
+        table.leave(1).unwrap();
+        assert_eq!(table.leader, 2);
+        table.leave(2).unwrap();
+        table.leave(3).unwrap();
+        assert!(table.is_empty());
+    }
+
+    #[test]
+    fn test_only_the_leader_can_start_the_game() {
+        let mut table = table_with_players(1);
+        assert_eq!(table.start_game(1).unwrap_err(), ServerError::NotEnoughPlayers { table_id: 7 });
+        assert_eq!(table.start_game(2).unwrap_err(), ServerError::NotAtTable { table_id: 7 });
+
+        table.join(2).unwrap();
+        assert_eq!(table.start_game(2).unwrap_err(), ServerError::NotLeader { table_id: 7 });
+        assert_eq!(table.play_action(1, Action::Check).unwrap_err(),
+                   ServerError::InvalidAction { reason: ActionError::NoHandInProgress });
+
+        table.start_game(1).unwrap();
+        assert_eq!(table.start_game(1).unwrap_err(), ServerError::GameAlreadyStarted { table_id: 7 });
+        assert_eq!(*table.get_info().status(), TableStatus::Playing);
+    }
+
+    #[test]
+    fn test_game_is_played_over_messages() {
+        let mut table = table_with_players(2);
+
+        let outbox = table.start_game(1).unwrap();
+        assert!(matches!(outbox[0], (1, MessageType::GameStarted(_))));
+        assert!(matches!(outbox[1], (2, MessageType::GameStarted(_))));
+        for client_id in [1, 2] {
+            assert_eq!(count_messages(&outbox, client_id, "HandStarted"), 1);
+            // Both clients see the blinds being posted.
+            assert_eq!(count_messages(&outbox, client_id, "ActionTaken"), 2);
+        }
+        for (client_id, message) in &outbox {
+            if let MessageType::HandStarted(hand_started) = message {
+                assert_eq!(*hand_started.view().player_id(), table.players[client_id]);
+                assert_eq!(hand_started.view().hole_cards().len(), 2);
+            }
+        }
+
+        // Only the player whose turn it is may act.
+        let to_act = requested_client(&outbox);
+        let waiting = if to_act == 1 { 2 } else { 1 };
+        let player_to_act = Some(table.players[&to_act]);
+        assert_eq!(table.play_action(waiting, Action::Fold).unwrap_err(),
+                   ServerError::InvalidAction { reason: ActionError::NotYourTurn { player_to_act } });
+
+        // Folding ends the hand, and the next one is dealt straight away.
+        let outbox = table.play_action(to_act, Action::Fold).unwrap();
+        for client_id in [1, 2] {
+            assert_eq!(count_messages(&outbox, client_id, "HandFinished"), 1);
+            assert_eq!(count_messages(&outbox, client_id, "HandStarted"), 1);
+            // The fold, the big blind's uncalled chips being returned, and the next hand's blinds.
+            assert_eq!(count_messages(&outbox, client_id, "ActionTaken"), 4);
+        }
+        requested_client(&outbox);
+    }
+
+    #[test]
+    fn test_game_stops_when_a_player_leaves_heads_up() {
+        let mut table = table_with_players(2);
+        table.start_game(1).unwrap();
+
+        let outbox = table.leave(2).unwrap();
+        assert_eq!(count_messages(&outbox, 1, "HandFinished"), 1);
+        assert_eq!(count_messages(&outbox, 2, "HandFinished"), 0);
+        assert_eq!(count_messages(&outbox, 1, "HandStarted"), 0);
+        assert_eq!(*table.get_info().status(), TableStatus::Waiting);
+    }
+
+    #[test]
+    fn test_players_who_are_all_in_choose_how_many_times_to_run_the_board() {
+        let mut table = run_twice_table(ClockSettings::default());
+        table.join(2).unwrap();
+        let to_act = requested_client(&table.start_game(1).unwrap());
+        table.play_action(to_act, Action::RaiseTo(BUY_IN)).unwrap();
+        let outbox = table.play_action(3 - to_act, Action::Call).unwrap();
+        for client_id in [1, 2] {
+            assert_eq!(count_messages(&outbox, client_id, "RunCountRequest"), 1);
+            assert_eq!(count_messages(&outbox, client_id, "HandFinished"), 0);
+        }
+
+        assert_eq!(table.choose_run_count(1, 3).unwrap_err(),
+                   ServerError::InvalidRunCount { reason: RunoutError::InvalidRunCount { maximum: 2 } });
+        // The hand waits for both players, who are not asked again.
+        assert!(table.choose_run_count(1, 2).unwrap().is_empty());
+        let outbox = table.choose_run_count(2, 2).unwrap();
+        assert_eq!(count_messages(&outbox, 1, "HandFinished"), 1);
+        assert_eq!(money_after_hand(outbox.iter().map(|(_, message)| message)), vec![BUY_IN, BUY_IN]);
+        requested_client(&outbox);
+    }
+
+    #[tokio::test]
+    async fn test_table_task_sends_messages_and_closes_when_empty() {
+        let (leader_sender, mut leader_messages) = unbounded_channel();
+        let (sender, mut messages) = unbounded_channel();
+        let (handle, task) = TableHandle::spawn(LobbyTable::new(7, 1, ClockSettings::default(), TableSettings::default()), leader_sender);
+
+        handle.join(2, sender.clone()).await.unwrap();
+        assert_eq!(handle.join(2, sender).await, Err(ServerError::AlreadyAtTable { table_id: 7 }));
+        assert_eq!(*handle.get_info().num_players(), 2);
+
+        // Everything the game sends is in each client's channel by the time the request is answered.
+        handle.start_game(1).await.unwrap();
+        assert!(matches!(leader_messages.try_recv(), Ok(MessageType::GameStarted(_))));
+        assert!(matches!(messages.try_recv(), Ok(MessageType::GameStarted(_))));
+        assert_eq!(*handle.get_info().status(), TableStatus::Playing);
+
+        handle.leave(1).await.unwrap();
+        handle.leave(2).await.unwrap();
+        task.await.unwrap();
+        assert_eq!(handle.start_game(2).await, Err(ServerError::UnknownTable { table_id: 7 }));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_players_who_run_out_of_time_check_or_fold() {
+        let clock_settings = ClockSettings::new(Duration::from_secs(10), Duration::from_secs(20), Duration::from_secs(5));
+        let (leader_sender, mut leader_messages) = unbounded_channel();
+        let (sender, _messages) = unbounded_channel();
+        let (handle, _task) = TableHandle::spawn(LobbyTable::new(7, 1, clock_settings, TableSettings::default()), leader_sender);
+        handle.join(2, sender).await.unwrap();
+        handle.start_game(1).await.unwrap();
+
+        // Heads up, the small blind acts first, and everyone sees their clock start.
+        let updates = clock_updates(&receive(&mut leader_messages));
+        assert_eq!(updates.len(), 1);
+        let (small_blind, _, _) = updates[0];
+        let big_blind = 3 - small_blind;
+        assert_eq!(updates[0], (small_blind, 10_000, 20_000));
+
+        // Facing the big blind, the small blind folds once both their action time and time bank have run out.
+        time::sleep(Duration::from_secs(29)).await;
+        assert!(receive(&mut leader_messages).is_empty());
+        time::sleep(Duration::from_secs(2)).await;
+        let messages = receive(&mut leader_messages);
+        assert_eq!(actions_taken(&messages)[0], (small_blind, ActionKind::Fold));
+        assert_eq!(clock_updates(&messages), vec![(big_blind, 10_000, 20_000)]);
+
+        // In the next hand, the player who timed out has the refill in their time bank, and checks when it runs out.
+        let client_id = big_blind as u128;
+        handle.play_action(client_id, Action::Call).await.unwrap();
+        let messages = receive(&mut leader_messages);
+        assert_eq!(clock_updates(&messages), vec![(small_blind, 10_000, 5_000)]);
+
+        time::sleep(Duration::from_secs(16)).await;
+        let messages = receive(&mut leader_messages);
+        assert_eq!(actions_taken(&messages)[0], (small_blind, ActionKind::Check));
+        assert_eq!(*handle.get_info().status(), TableStatus::Playing);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_players_who_run_out_of_time_to_choose_run_the_board_once() {
+        let clock_settings = ClockSettings::new(Duration::from_secs(10), Duration::from_secs(20), Duration::from_secs(5));
+        let (leader_sender, mut leader_messages) = unbounded_channel();
+        let (sender, mut messages) = unbounded_channel();
+        let (handle, _task) = TableHandle::spawn(run_twice_table(clock_settings), leader_sender);
+        handle.join(2, sender.clone()).await.unwrap();
+        handle.start_game(1).await.unwrap();
+
+        let (small_blind, _, _) = clock_updates(&receive(&mut leader_messages))[0];
+        handle.play_action(small_blind as u128, Action::RaiseTo(BUY_IN)).await.unwrap();
+        handle.play_action(3 - small_blind as u128, Action::Call).await.unwrap();
+        assert_eq!(run_count_requests(&receive(&mut messages)), vec![(2, 10_000)]);
+
+        // Only the leader agrees to run it twice. A player who reconnects while choosing is asked again.
+        handle.choose_run_count(1, 2).await.unwrap();
+        time::sleep(Duration::from_secs(4)).await;
+        handle.reconnect(2, sender).await.unwrap();
+        assert_eq!(run_count_requests(&receive(&mut messages)), vec![(2, 6_000)]);
+
+        // The other player's time bank is not used, and once their action time runs out the board is run once, so one
+        // player wins every chip and the game stops.
+        time::sleep(Duration::from_secs(5)).await;
+        assert!(receive(&mut leader_messages).iter().all(|message| !matches!(message, MessageType::HandFinished(_))));
+        time::sleep(Duration::from_secs(2)).await;
+        let mut money = money_after_hand(&receive(&mut leader_messages));
+        money.sort();
+        assert_eq!(money, vec![0, 2 * BUY_IN]);
+        assert_eq!(*handle.get_info().status(), TableStatus::Waiting);
+    }
+}
@@ -1,16 +1,14 @@
 #[macro_use] extern crate rocket;
 
-use std::collections::{HashMap, HashSet};
 use std::{env, io};
 use std::hash::BuildHasherDefault;
 use std::io::Write;
 use std::process;
-use std::sync::{Arc, Mutex};
 use rand::Rng;
 use rand::rngs::OsRng;
 use rocket::futures::{SinkExt, StreamExt};
 use rocket::futures::stream::{SplitSink, SplitStream};
-use serde_json::{from_str, json, Value};
+use serde_json::{json, Value};
 use serde::{Serialize, Deserialize};
 
 mod utils;
@@ -18,11 +16,11 @@ mod utils;
 pub mod game;
 mod messages;
 mod message_utils;
+mod lobby;
 
 const MAX_PLAYERS: i32 = 50;
 const MAX_PLAYERS_PER_GAME: i32 = 10;
 const MESSAGE_SEND_ERROR: &'static str = "Error sending message";
-const MESSAGE_READ_ERROR: &'static str = "Error reading message";
 const SERIALIZATION_ERROR: &'static str = "Error serializing message";
 const DESERIALIZATION_ERROR: &'static str = "Error deserializing message";
 
@@ -35,22 +33,20 @@ use rocket::response::status;
 use rocket::tokio::net::TcpListener;
 use rocket::tokio::task;
 use tokio::net::TcpStream;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
 use tokio_tungstenite::{accept_async, WebSocketStream};
 use tokio_tungstenite::tungstenite::protocol::Message;
-use crate::game::Game;
-use crate::messages::{Handshake, HandshakeOk, JoinTable, JoinTableOk, MessageType, QueryTables, StartNewTable, StartNewTableOk, TablesInfo};
+use crate::lobby::{Lobby, LobbyClient};
+use crate::lobby::clock::ClockSettings;
+use crate::lobby::session::{RECONNECT_GRACE_PERIOD, Sessions};
+use crate::messages::{MessageType, ServerError};
 
 #[get("/ws")]
 async fn websocket_handler() -> &'static str {
     "WebSocket server is running"
 }
 
-async fn handle_connection(
-    stream: TcpStream,
-    mut client_ids: Arc<Mutex<HashSet<u128>>>,
-    mut game_ids: Arc<Mutex<HashMap<u128, Game>>>,
-    mut rng: Arc<Mutex<OsRng>>
-) {
+async fn handle_connection(stream: TcpStream, sessions: Sessions, lobby: Lobby) {
 
     let ws_stream = accept_async(stream)
         .await
@@ -59,31 +55,67 @@ async fn handle_connection(
     // Split into read and write streams.
     let (mut write, mut read): (SplitSink<WebSocketStream<TcpStream>, Message>, SplitStream<WebSocketStream<TcpStream>>) = ws_stream.split();
 
-    match client_handshake(&mut client_ids, &mut rng, &mut write, &mut read).await {
-        Ok(client_id) => {
-            start_or_join_table(&mut client_ids, &mut game_ids, rng, &mut write, &mut read, client_id).await;
+    // Each client's messages are written to their socket in order by a task of their own, so their table can send them
+    // messages while this task waits for their next one. Messages sent during the handshake wait in the channel until
+    // the task starts.
+    let (sender, mut receiver) = unbounded_channel::<MessageType>();
+
+    match client_handshake(&sessions, sender, &mut write, &mut read).await {
+        Ok((mut client, session_token)) => {
+            task::spawn(async move {
+                while let Some(message) = receiver.recv().await {
+                    let json_message = serde_json::to_string(&message).expect(SERIALIZATION_ERROR);
+                    if write.send(Message::Text(json_message)).await.is_err() {
+                        break;
+                    }
+                }
+            });
+
+            run_lobby(&lobby, &mut client, &mut read).await;
+
+            // The client disconnected, so hold their session and seat for them in case they reconnect.
+            sessions.park(session_token, client);
         }
         Err(e) => {
             println!("Handshake error: {}", e);
-            // Optionally send an error message to the client
         }
     }
 }
 
-async fn client_handshake<'a>(mut client_ids: &'a Arc<Mutex<HashSet<u128>>>, mut rng: &'a Arc<Mutex<OsRng>>, write: &'a mut SplitSink<WebSocketStream<TcpStream>, Message>, read: &'a mut SplitStream<WebSocketStream<TcpStream>>) -> Result<u128, &'static str> {
+/// Reads the client's `Handshake`, which starts a new session or resumes one after a dropped connection. The client is
+/// sent an `ErrorMessage` if the handshake fails.
+///
+/// # Returns
+///
+/// * `Result<(LobbyClient, String), &'static str>` - The client and their session token.
+async fn client_handshake<'a>(sessions: &'a Sessions, sender: UnboundedSender<MessageType>, write: &'a mut SplitSink<WebSocketStream<TcpStream>, Message>, read: &'a mut SplitStream<WebSocketStream<TcpStream>>) -> Result<(LobbyClient, String), &'static str> {
 
     if let Some(msg) = read.next().await {
         let msg = msg.map_err(|_| "Error reading message")?;
         let text = msg.to_text().map_err(|_| "Failed to convert message to text")?;
 
-        message_utils::deserialize::<Handshake>(text).map_err(|_| DESERIALIZATION_ERROR)?;
-        let client_id = utils::get_unique_client_id(&client_ids, rng).await;
-        println!("Created client id: {}", client_id);
-
-        let message = HandshakeOk::new(client_id);
-        send_message_2(write, message).await;
-
-        Ok(client_id)
+        let handshake = match message_utils::parse_message(text) {
+            Ok(MessageType::Handshake(handshake)) => handshake,
+            Ok(message) => {
+                send_message_2(write, ServerError::UnexpectedMessage { message_type: message.to_string() }).await;
+                return Err("Expected a Handshake");
+            },
+            Err(error) => {
+                send_message_2(write, error).await;
+                return Err(DESERIALIZATION_ERROR);
+            }
+        };
+
+        match sessions.handshake(&handshake, sender, &mut OsRng).await {
+            Ok((client, session_token)) => {
+                println!("Client connected: {}", client.get_client_id());
+                Ok((client, session_token))
+            },
+            Err(error) => {
+                send_message_2(write, error).await;
+                Err("Could not resume the session")
+            }
+        }
     } else {
         Err("No message received")
     }
@@ -183,25 +215,21 @@ async fn client_handshake<'a>(mut client_ids: &'a Arc<Mutex<HashSet<u128>>>, mut
 //     }
 // }
 
-async fn start_or_join_table(client_ids: &mut Arc<Mutex<HashSet<u128>>>, game_ids: &mut Arc<Mutex<HashMap<u128, Game>>>, mut rng: Arc<Mutex<OsRng>>, mut write: &mut SplitSink<WebSocketStream<TcpStream>, Message>, read: &mut SplitStream<WebSocketStream<TcpStream>>, client_id: u128) {
-    let Some(msg) = read.next().await else { todo!() };
-    let msg = msg.expect(MESSAGE_READ_ERROR);
-
-    // Deserialize JSON message into StartNewTable struct
-    if let Ok(start_new_table) = from_str::<StartNewTable>(msg.to_text().expect("Failed to convert to text")) {
-        println!("Received StartNewTable: {:?}", start_new_table);
-
-        let game_id: u128 = utils::get_unique_game_id(&game_ids, rng).await;
-
-        println!("Client {} joined table {}. Game created.", client_id, game_id);
-        let message = StartNewTableOk::new(client_id, game_id);
-        send_message_2(write, message).await;
-    } else {
-        panic!("{}", DESERIALIZATION_ERROR);
+/// Answers messages from `client_id` until they disconnect. Clients can send any number of lobby and game messages in
+/// any order, and every message gets a reply, which is an `ErrorMessage` if the message was invalid or unexpected.
+/// Anything a message causes to be sent to the rest of the client's table is sent as well.
+async fn run_lobby(lobby: &Lobby, client: &mut LobbyClient, read: &mut SplitStream<WebSocketStream<TcpStream>>) {
+    while let Some(msg) = read.next().await {
+        match msg {
+            Ok(Message::Text(text)) => match message_utils::parse_message(&text) {
+                Ok(message) => client.handle_message(lobby, message, &mut OsRng).await,
+                Err(error) => client.send(error)
+            },
+            Ok(Message::Ping(_)) | Ok(Message::Pong(_)) => continue,
+            Ok(Message::Close(_)) | Err(_) => break,
+            Ok(_) => client.send(ServerError::InvalidMessage { reason: String::from("Messages must be JSON text") })
+        }
     }
-
-    dbg!(client_ids);
-    dbg!(game_ids);
 }
 
 
@@ -223,19 +251,15 @@ async fn send_message_2<T>(mut write: &mut SplitSink<WebSocketStream<TcpStream>,
 async fn main()  {
     let addr = "127.0.0.1:9001".to_string();
 
-    let rng: Arc<Mutex<OsRng>> = Arc::new(Mutex::new(OsRng::default()));
-    let client_ids: Arc<Mutex<HashSet<u128>>> = Arc::new(Mutex::new(HashSet::<u128>::new()));
-    let game_ids: Arc<Mutex<HashMap<u128, Game>>> = Arc::new(Mutex::new(HashMap::<u128, Game>::new()));
+    let sessions = Sessions::new(RECONNECT_GRACE_PERIOD);
+    let lobby = Lobby::new(ClockSettings::default());
 
     let listener = TcpListener::bind(&addr).await.unwrap();
     println!("Listening on: {}", addr);
 
     task::spawn(async move {
         while let Ok((stream, _)) = listener.accept().await {
-            let rng_clone = Arc::clone(&rng);
-            let client_ids_clone = Arc::clone(&client_ids);
-            let game_ids_clone = Arc::clone(&game_ids);
-            task::spawn(handle_connection(stream, client_ids_clone, game_ids_clone, rng_clone));
+            task::spawn(handle_connection(stream, sessions.clone(), lobby.clone()));
         }
         });
 
@@ -446,12 +470,14 @@ async fn main()  {
 //
 //     let game_id = utils::get_unique_id(&game_ids);
 //     let mut game = game::Game::new(game_id, 2, 1000);
+//     let mut agents = HashMap::<game::player::PlayerId, Box<dyn game::agent::PlayerAgent>>::new();
 //
 //     for _ in 0..num_players {
 //         let player_id = utils::get_unique_id(&player_ids);
 //         player_ids.insert(player_id);
 //         let player_name = format!("Player#{}", player_id);
 //         game.add_player(game::player::Player::new(player_id, player_name, 1000));
+//         agents.insert(player_id, Box::new(game::agent::TerminalAgent::new()));
 //     }
 //
 //     if debug {
@@ -488,6 +514,6 @@ async fn main()  {
 //         println!("Chip sizes: {:?}", chip_sizes);
 //     }
 //
-//     game.start_game(debug);
+//     game.start_game(&mut agents, None, debug);
 //
 // }
@@ -1,97 +1,93 @@
 use serde::Deserialize;
-use serde_json::{from_str, Value};
-use crate::messages::{Handshake, HandshakeOk, JoinTable, JoinTableOk, MessageType, QueryTables, StartNewTable, StartNewTableOk, TablesInfo};
+use serde::de::DeserializeOwned;
+use serde_json::from_str;
+use crate::messages::{ActionRequest, ActionTaken, AddShuffleEntropy, AddShuffleEntropyOk, ChooseRunCount, ChooseRunCountOk, ClockUpdate, ErrorMessage, GameStarted, HandFinished, HandStarted, Handshake, HandshakeOk, JoinTable, JoinTableOk, LeaveTable, LeaveTableOk, MessageType, PlayerAction, QueryTables, RunCountRequest, ServerError, ShuffleCommitment, StartGame, StartNewTable, StartNewTableOk, TableSnapshot, TablesInfo};
 
-pub(crate) fn deserialize<T>(text: &str) -> Result<T, &'static str>
+/// Just the "type" field of a message, so it can be read before the rest of the message.
+#[derive(Debug, Deserialize)]
+struct MessageTag {
+    #[serde(rename = "type")]
+    message_type: String
+}
+
+/// Reads a message from a client, using its "type" field to pick which message it is.
+///
+/// # Returns
+///
+/// * `Result<MessageType, ServerError>` - The message, or `InvalidMessage` if it is not JSON or is missing fields, or
+///   `UnknownMessageType` if the type is not a message the server knows.
+///
+/// # Notes
+///
+/// The message is read straight into the struct for its type rather than into `MessageType`, because serde cannot
+/// read the `u128` ids through an internally tagged enum.
+pub(crate) fn parse_message(text: &str) -> Result<MessageType, ServerError> {
+    let tag = from_str::<MessageTag>(text)
+        .map_err(|error| ServerError::InvalidMessage { reason: error.to_string() })?;
+
+    match tag.message_type.as_str() {
+        "Handshake" => deserialize::<Handshake>(text),
+        "HandshakeOk" => deserialize::<HandshakeOk>(text),
+        "StartNewTable" => deserialize::<StartNewTable>(text),
+        "StartNewTableOk" => deserialize::<StartNewTableOk>(text),
+        "QueryTables" => deserialize::<QueryTables>(text),
+        "TablesInfo" => deserialize::<TablesInfo>(text),
+        "JoinTable" => deserialize::<JoinTable>(text),
+        "JoinTableOk" => deserialize::<JoinTableOk>(text),
+        "LeaveTable" => deserialize::<LeaveTable>(text),
+        "LeaveTableOk" => deserialize::<LeaveTableOk>(text),
+        "StartGame" => deserialize::<StartGame>(text),
+        "GameStarted" => deserialize::<GameStarted>(text),
+        "HandStarted" => deserialize::<HandStarted>(text),
+        "ActionRequest" => deserialize::<ActionRequest>(text),
+        "ClockUpdate" => deserialize::<ClockUpdate>(text),
+        "PlayerAction" => deserialize::<PlayerAction>(text),
+        "ActionTaken" => deserialize::<ActionTaken>(text),
+        "HandFinished" => deserialize::<HandFinished>(text),
+        "TableSnapshot" => deserialize::<TableSnapshot>(text),
+        "ShuffleCommitment" => deserialize::<ShuffleCommitment>(text),
+        "AddShuffleEntropy" => deserialize::<AddShuffleEntropy>(text),
+        "AddShuffleEntropyOk" => deserialize::<AddShuffleEntropyOk>(text),
+        "RunCountRequest" => deserialize::<RunCountRequest>(text),
+        "ChooseRunCount" => deserialize::<ChooseRunCount>(text),
+        "ChooseRunCountOk" => deserialize::<ChooseRunCountOk>(text),
+        "ErrorMessage" => deserialize::<ErrorMessage>(text),
+        _ => Err(ServerError::UnknownMessageType { message_type: tag.message_type })
+    }
+}
+
+fn deserialize<T>(text: &str) -> Result<MessageType, ServerError>
     where
-        T: Deserialize<'static> + std::fmt::Debug,
+        T: DeserializeOwned + Into<MessageType>,
 {
-    // Deserialize JSON into a generic Value first
-    let value: Value = match from_str(text) {
-        Ok(v) => v,
-        Err(_) => return Err("Failed to deserialize JSON"),
-    };
+    from_str::<T>(text)
+        .map(Into::into)
+        .map_err(|error| ServerError::InvalidMessage { reason: error.to_string() })
+}
 
-    // Extract the type field from the JSON
-    let message_type = match value.get("type") {
-        Some(v) => match v.as_str() {
-            Some(s) => s,
-            None => return Err("Invalid 'type' field in JSON"),
-        },
-        None => return Err("Missing 'type' field in JSON"),
-    };
 
-    // Match against the specific message type and deserialize accordingly
-    let message: MessageType = match message_type {
-        "Handshake" => {
-            let msg: Result<Handshake, _> = serde_json::from_value(value);
-            match msg {
-                Ok(handshake) => MessageType::Handshake(handshake),
-                Err(_) => return Err("Failed to deserialize Handshake"),
-            }
-        }
-        "HandshakeOk" => {
-            let msg: Result<HandshakeOk, _> = serde_json::from_value(value);
-            match msg {
-                Ok(handshake_ok) => MessageType::HandshakeOk(handshake_ok),
-                Err(_) => return Err("Failed to deserialize HandshakeOk"),
-            }
-        }
-        "StartNewTable" => {
-            let msg: Result<StartNewTable, _> = serde_json::from_value(value);
-            match msg {
-                Ok(start_new_table) => MessageType::StartNewTable(start_new_table),
-                Err(_) => return Err("Failed to deserialize StartNewTable"),
-            }
-        }
-        "StartNewTableOk" => {
-            let msg: Result<StartNewTableOk, _> = serde_json::from_value(value);
-            match msg {
-                Ok(start_new_table_ok) => MessageType::StartNewTableOk(start_new_table_ok),
-                Err(_) => return Err("Failed to deserialize StartNewTableOk"),
-            }
-        }
-        "QueryTables" => {
-            let msg: Result<QueryTables, _> = serde_json::from_value(value);
-            match msg {
-                Ok(query_tables) => MessageType::QueryTables(query_tables),
-                Err(_) => return Err("Failed to deserialize QueryTables"),
-            }
-        }
-        "TablesInfo" => {
-            let msg: Result<TablesInfo, _> = serde_json::from_value(value);
-            match msg {
-                Ok(tables_info) => MessageType::TablesInfo(tables_info),
-                Err(_) => return Err("Failed to deserialize TablesInfo"),
-            }
-        }
-        "JoinTable" => {
-            let msg: Result<JoinTable, _> = serde_json::from_value(value);
-            match msg {
-                Ok(join_table) => MessageType::JoinTable(join_table),
-                Err(_) => return Err("Failed to deserialize JoinTable"),
-            }
-        }
-        "JoinTableOk" => {
-            let msg: Result<JoinTableOk, _> = serde_json::from_value(value);
-            match msg {
-                Ok(join_table_ok) => MessageType::JoinTableOk(join_table_ok),
-                Err(_) => return Err("Failed to deserialize JoinTableOk"),
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_message_reads_u128_ids() {
+        let text = serde_json::to_string(&MessageType::from(JoinTable::new(u128::MAX, 7))).unwrap();
+
+        match parse_message(&text) {
+            Ok(MessageType::JoinTable(join_table)) => {
+                assert_eq!(*join_table.client_id(), u128::MAX);
+                assert_eq!(*join_table.table_id(), 7);
+            },
+            other => panic!("Expected a JoinTable message, got {:?}", other)
         }
-        _ => return Err("Unknown message type"),
-    };
+    }
 
-    // Ensure the deserialized message matches the expected type T
-    match message {
-        MessageType::Handshake(m) if message.type() == "Handshake" => Ok(m),
-        MessageType::HandshakeOk(m) if T::type_name() == "HandshakeOk" => Ok(m),
-        MessageType::StartNewTable(m) if T::type_name() == "StartNewTable" => Ok(m),
-        MessageType::StartNewTableOk(m) if T::type_name() == "StartNewTableOk" => Ok(m),
-        MessageType::QueryTables(m) if T::type_name() == "QueryTables" => Ok(m),
-        MessageType::TablesInfo(m) if T::type_name() == "TablesInfo" => Ok(m),
-        MessageType::JoinTable(m) if T::type_name() == "JoinTable" => Ok(m),
-        MessageType::JoinTableOk(m) if T::type_name() == "JoinTableOk" => Ok(m),
-        _ => Err("Deserialized message type does not match expected type"),
+    #[test]
+    fn test_parse_message_rejects_bad_messages() {
+        assert!(matches!(parse_message("not json"), Err(ServerError::InvalidMessage { .. })));
+        assert!(matches!(parse_message(r#"{"client_id": 1}"#), Err(ServerError::InvalidMessage { .. })));
+        assert!(matches!(parse_message(r#"{"type": "JoinTable", "client_id": 1}"#), Err(ServerError::InvalidMessage { .. })));
+        assert_eq!(parse_message(r#"{"type": "Dance"}"#).unwrap_err(), ServerError::UnknownMessageType { message_type: String::from("Dance") });
     }
 }
@@ -1,6 +1,13 @@
 use serde::{Deserialize, Serialize};
 use getset::Getters;
 
+use crate::game::action::{Action, ActionError};
+use crate::game::fair_shuffle::{ShuffleError, ShuffleReveal};
+use crate::game::hand_history::ActionRecord;
+use crate::game::player::PlayerId;
+use crate::game::runout::RunoutError;
+use crate::game::view::{PlayerView, SpectatorView};
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(tag = "type")] // This adds a "type" field to indicate which variant is being serialized
 pub enum MessageType {
@@ -11,11 +18,29 @@ pub enum MessageType {
     QueryTables(QueryTables),
     TablesInfo(TablesInfo),
     JoinTable(JoinTable),
-    JoinTableOk(JoinTableOk)
+    JoinTableOk(JoinTableOk),
+    LeaveTable(LeaveTable),
+    LeaveTableOk(LeaveTableOk),
+    StartGame(StartGame),
+    GameStarted(GameStarted),
+    HandStarted(HandStarted),
+    ActionRequest(ActionRequest),
+    ClockUpdate(ClockUpdate),
+    PlayerAction(PlayerAction),
+    ActionTaken(ActionTaken),
+    HandFinished(HandFinished),
+    TableSnapshot(TableSnapshot),
+    ShuffleCommitment(ShuffleCommitment),
+    AddShuffleEntropy(AddShuffleEntropy),
+    AddShuffleEntropyOk(AddShuffleEntropyOk),
+    RunCountRequest(RunCountRequest),
+    ChooseRunCount(ChooseRunCount),
+    ChooseRunCountOk(ChooseRunCountOk),
+    ErrorMessage(ErrorMessage)
 }
 
 impl MessageType {
-    fn to_string(&self) -> String {
+    pub(crate) fn to_string(&self) -> String {
         match self {
             MessageType::Handshake(_) => String::from("Handshake"),
             MessageType::HandshakeOk(_) => String::from("HandshakeOk"),
@@ -25,16 +50,42 @@ impl MessageType {
             MessageType::TablesInfo(_) => String::from("TablesInfo"),
             MessageType::JoinTable(_) => String::from("JoinTable"),
             MessageType::JoinTableOk(_) => String::from("JoinTableOk"),
+            MessageType::LeaveTable(_) => String::from("LeaveTable"),
+            MessageType::LeaveTableOk(_) => String::from("LeaveTableOk"),
+            MessageType::StartGame(_) => String::from("StartGame"),
+            MessageType::GameStarted(_) => String::from("GameStarted"),
+            MessageType::HandStarted(_) => String::from("HandStarted"),
+            MessageType::ActionRequest(_) => String::from("ActionRequest"),
+            MessageType::ClockUpdate(_) => String::from("ClockUpdate"),
+            MessageType::PlayerAction(_) => String::from("PlayerAction"),
+            MessageType::ActionTaken(_) => String::from("ActionTaken"),
+            MessageType::HandFinished(_) => String::from("HandFinished"),
+            MessageType::TableSnapshot(_) => String::from("TableSnapshot"),
+            MessageType::ShuffleCommitment(_) => String::from("ShuffleCommitment"),
+            MessageType::AddShuffleEntropy(_) => String::from("AddShuffleEntropy"),
+            MessageType::AddShuffleEntropyOk(_) => String::from("AddShuffleEntropyOk"),
+            MessageType::RunCountRequest(_) => String::from("RunCountRequest"),
+            MessageType::ChooseRunCount(_) => String::from("ChooseRunCount"),
+            MessageType::ChooseRunCountOk(_) => String::from("ChooseRunCountOk"),
+            MessageType::ErrorMessage(_) => String::from("ErrorMessage"),
         }
     }
 }
 
+/// Sent by a client when it connects. A client reconnecting after its connection dropped sends the `session_token` it
+/// was given, to get its client_id and seat back.
 #[derive(Debug, Getters, Serialize, Deserialize)]
-pub(crate) struct Handshake { }
+pub(crate) struct Handshake {
+    #[serde(default)]
+    #[getset(get = "pub")]
+    session_token: Option<String>
+}
 
+#[cfg(test)]
 impl Handshake {
-    pub fn new() -> Handshake{
+    pub fn new(session_token: Option<String>) -> Handshake {
         Handshake {
+            session_token
         }
     }
 }
@@ -47,13 +98,17 @@ impl From<Handshake> for MessageType {
 #[derive(Debug, Getters, Serialize, Deserialize)]
 pub(crate) struct HandshakeOk {
     #[getset(get = "pub")]
-    client_id: u128
+    client_id: u128,
+
+    #[getset(get = "pub")]
+    session_token: String
 }
 
 impl HandshakeOk {
-    pub fn new(client_id : u128) -> HandshakeOk {
+    pub fn new(client_id : u128, session_token: String) -> HandshakeOk {
         HandshakeOk {
-            client_id
+            client_id,
+            session_token
         }
     }
 }
@@ -64,16 +119,47 @@ impl From<HandshakeOk> for MessageType {
     }
 }
 
+/// A message to open a new table. The stakes and buy-in are optional, and the table's are used for any left out. If
+/// `provably_fair` is set, every hand at the table is dealt with a commit-reveal shuffle that the players add entropy
+/// to. If `max_runs` is more than 1, players who are all in before the river are asked how many times to run the board,
+/// up to `max_runs`.
 #[derive(Debug, Getters, Serialize, Deserialize)]
 pub(crate) struct StartNewTable {
     #[getset(get = "pub")]
-    client_id: u128
+    client_id: u128,
+
+    #[serde(default)]
+    #[getset(get = "pub")]
+    small_blind: Option<i32>,
+
+    #[serde(default)]
+    #[getset(get = "pub")]
+    big_blind: Option<i32>,
+
+    #[serde(default)]
+    #[getset(get = "pub")]
+    buy_in: Option<i32>,
+
+    #[serde(default)]
+    #[getset(get = "pub")]
+    provably_fair: bool,
+
+    #[serde(default)]
+    #[getset(get = "pub")]
+    max_runs: Option<u32>
 }
 
+#[cfg(test)]
 impl StartNewTable {
-    pub fn new(unique_id : u128) -> StartNewTable {
+    pub fn new(unique_id : u128, small_blind: Option<i32>, big_blind: Option<i32>, buy_in: Option<i32>, provably_fair: bool,
+               max_runs: Option<u32>) -> StartNewTable {
         StartNewTable {
-            client_id: unique_id
+            client_id: unique_id,
+            small_blind,
+            big_blind,
+            buy_in,
+            provably_fair,
+            max_runs
         }
     }
 }
@@ -114,6 +200,7 @@ pub(crate) struct QueryTables {
     client_id: u128,
 }
 
+#[cfg(test)]
 impl QueryTables {
     pub fn new(client_id: u128) -> QueryTables {
         QueryTables {
@@ -131,11 +218,11 @@ impl From<QueryTables> for MessageType {
 #[derive(Debug, Getters, Serialize, Deserialize)]
 pub(crate) struct TablesInfo {
     #[getset(get = "pub")]
-    tables: Vec<u128>,
+    tables: Vec<TableInfo>,
 }
 
 impl TablesInfo {
-    pub fn new(tables: Vec<u128>) -> TablesInfo {
+    pub fn new(tables: Vec<TableInfo>) -> TablesInfo {
         TablesInfo {
             tables
         }
@@ -148,6 +235,46 @@ impl From<TablesInfo> for MessageType {
     }
 }
 
+/// One open table in a `TablesInfo` message.
+#[derive(Debug, Clone, PartialEq, Eq, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub(crate) struct TableInfo {
+    table_id: u128,
+    num_players: i32,
+    num_seats: usize,
+    small_blind: i32,
+    big_blind: i32,
+    buy_in: i32,
+    status: TableStatus,
+    provably_fair: bool,
+    max_runs: u32
+}
+
+impl TableInfo {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(table_id: u128, num_players: i32, num_seats: usize, small_blind: i32, big_blind: i32, buy_in: i32,
+               status: TableStatus, provably_fair: bool, max_runs: u32) -> TableInfo {
+        TableInfo {
+            table_id,
+            num_players,
+            num_seats,
+            small_blind,
+            big_blind,
+            buy_in,
+            status,
+            provably_fair,
+            max_runs
+        }
+    }
+}
+
+/// Whether a table is waiting for players or playing a hand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum TableStatus {
+    Waiting,
+    Playing
+}
+
 #[derive(Debug, Getters, Serialize, Deserialize)]
 pub(crate) struct JoinTable {
     #[getset(get = "pub")]
@@ -157,6 +284,7 @@ pub(crate) struct JoinTable {
     table_id: u128
 }
 
+#[cfg(test)]
 impl JoinTable {
     pub fn new(client_id: u128, table_id: u128) -> JoinTable {
         JoinTable {
@@ -195,3 +323,588 @@ impl From<JoinTableOk> for MessageType {
         MessageType::JoinTableOk(msg)
     }
 }
+
+/// Entropy a player adds to the provably fair shuffle of the next hand at a table.
+#[derive(Debug, Getters, Serialize, Deserialize)]
+pub(crate) struct AddShuffleEntropy {
+    #[getset(get = "pub")]
+    client_id: u128,
+
+    #[getset(get = "pub")]
+    table_id: u128,
+
+    #[getset(get = "pub")]
+    entropy: String
+}
+
+#[cfg(test)]
+impl AddShuffleEntropy {
+    pub fn new(client_id: u128, table_id: u128, entropy: String) -> AddShuffleEntropy {
+        AddShuffleEntropy {
+            client_id,
+            table_id,
+            entropy
+        }
+    }
+}
+
+impl From<AddShuffleEntropy> for MessageType {
+    fn from(msg: AddShuffleEntropy) -> Self {
+        MessageType::AddShuffleEntropy(msg)
+    }
+}
+
+#[derive(Debug, Getters, Serialize, Deserialize)]
+pub(crate) struct AddShuffleEntropyOk {
+    #[getset(get = "pub")]
+    client_id: u128,
+
+    #[getset(get = "pub")]
+    table_id: u128
+}
+
+impl AddShuffleEntropyOk {
+    pub fn new(client_id: u128, table_id: u128) -> AddShuffleEntropyOk {
+        AddShuffleEntropyOk {
+            client_id,
+            table_id
+        }
+    }
+}
+
+impl From<AddShuffleEntropyOk> for MessageType {
+    fn from(msg: AddShuffleEntropyOk) -> Self {
+        MessageType::AddShuffleEntropyOk(msg)
+    }
+}
+
+/// Sent to each player still in a hand when everyone left in it is all in before the river, at a table that can run
+/// the board more than once. They answer with a `ChooseRunCount`. The board is run the fewest times anyone chose, and a
+/// player who has not chosen within `time_ms` runs it once.
+///
+/// # Fields
+///
+/// * `table_id`: The table the player is seated at.
+/// * `max_runs`: The most times the board can be run.
+/// * `time_ms`: The milliseconds the player has left to choose.
+#[derive(Debug, Clone, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub(crate) struct RunCountRequest {
+    table_id: u128,
+    max_runs: u32,
+    time_ms: u64
+}
+
+impl RunCountRequest {
+    pub fn new(table_id: u128, max_runs: u32, time_ms: u64) -> RunCountRequest {
+        RunCountRequest {
+            table_id,
+            max_runs,
+            time_ms
+        }
+    }
+}
+
+impl From<RunCountRequest> for MessageType {
+    fn from(msg: RunCountRequest) -> Self {
+        MessageType::RunCountRequest(msg)
+    }
+}
+
+/// How many times a player agrees to run the board. It can be sent any time during a hand, not only once a
+/// `RunCountRequest` has asked for it.
+#[derive(Debug, Getters, Serialize, Deserialize)]
+pub(crate) struct ChooseRunCount {
+    #[getset(get = "pub")]
+    client_id: u128,
+
+    #[getset(get = "pub")]
+    table_id: u128,
+
+    #[getset(get = "pub")]
+    runs: u32
+}
+
+#[cfg(test)]
+impl ChooseRunCount {
+    pub fn new(client_id: u128, table_id: u128, runs: u32) -> ChooseRunCount {
+        ChooseRunCount {
+            client_id,
+            table_id,
+            runs
+        }
+    }
+}
+
+impl From<ChooseRunCount> for MessageType {
+    fn from(msg: ChooseRunCount) -> Self {
+        MessageType::ChooseRunCount(msg)
+    }
+}
+
+#[derive(Debug, Getters, Serialize, Deserialize)]
+pub(crate) struct ChooseRunCountOk {
+    #[getset(get = "pub")]
+    client_id: u128,
+
+    #[getset(get = "pub")]
+    table_id: u128
+}
+
+impl ChooseRunCountOk {
+    pub fn new(client_id: u128, table_id: u128) -> ChooseRunCountOk {
+        ChooseRunCountOk {
+            client_id,
+            table_id
+        }
+    }
+}
+
+impl From<ChooseRunCountOk> for MessageType {
+    fn from(msg: ChooseRunCountOk) -> Self {
+        MessageType::ChooseRunCountOk(msg)
+    }
+}
+
+/// Sent at a provably fair table with the commitment to the shuffle of the next hand, which is the SHA-256 hash of the
+/// server's secret seed. Clients keep it to check the `ShuffleReveal` in that hand's `HandFinished`.
+///
+/// # Notes
+///
+/// A client is sent the commitment when they sit down or reconnect, and everyone at the table is sent the next one as
+/// soon as a hand has been dealt. Entropy added with `AddShuffleEntropy` goes into the hand the latest commitment is
+/// for.
+#[derive(Debug, Clone, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub(crate) struct ShuffleCommitment {
+    table_id: u128,
+    commitment: String
+}
+
+impl ShuffleCommitment {
+    pub fn new(table_id: u128, commitment: String) -> ShuffleCommitment {
+        ShuffleCommitment {
+            table_id,
+            commitment
+        }
+    }
+}
+
+impl From<ShuffleCommitment> for MessageType {
+    fn from(msg: ShuffleCommitment) -> Self {
+        MessageType::ShuffleCommitment(msg)
+    }
+}
+
+/// A message to leave the table the client is seated at. The next player to have joined becomes the leader if the
+/// leader leaves, and the table is closed once everyone has left.
+#[derive(Debug, Getters, Serialize, Deserialize)]
+pub(crate) struct LeaveTable {
+    #[getset(get = "pub")]
+    client_id: u128,
+
+    #[getset(get = "pub")]
+    table_id: u128
+}
+
+#[cfg(test)]
+impl LeaveTable {
+    pub fn new(client_id: u128, table_id: u128) -> LeaveTable {
+        LeaveTable {
+            client_id,
+            table_id
+        }
+    }
+}
+
+impl From<LeaveTable> for MessageType {
+    fn from(msg: LeaveTable) -> Self {
+        MessageType::LeaveTable(msg)
+    }
+}
+
+#[derive(Debug, Getters, Serialize, Deserialize)]
+pub(crate) struct LeaveTableOk {
+    #[getset(get = "pub")]
+    client_id: u128,
+
+    #[getset(get = "pub")]
+    table_id: u128
+}
+
+impl LeaveTableOk {
+    pub fn new(client_id: u128, table_id: u128) -> LeaveTableOk {
+        LeaveTableOk {
+            client_id,
+            table_id
+        }
+    }
+}
+
+impl From<LeaveTableOk> for MessageType {
+    fn from(msg: LeaveTableOk) -> Self {
+        MessageType::LeaveTableOk(msg)
+    }
+}
+
+/// A message to start playing hands at a table. Only the table's leader can send it.
+#[derive(Debug, Getters, Serialize, Deserialize)]
+pub(crate) struct StartGame {
+    #[getset(get = "pub")]
+    client_id: u128,
+
+    #[getset(get = "pub")]
+    table_id: u128
+}
+
+#[cfg(test)]
+impl StartGame {
+    pub fn new(client_id: u128, table_id: u128) -> StartGame {
+        StartGame {
+            client_id,
+            table_id
+        }
+    }
+}
+
+impl From<StartGame> for MessageType {
+    fn from(msg: StartGame) -> Self {
+        MessageType::StartGame(msg)
+    }
+}
+
+/// Sent to everyone seated at a table when its leader starts the game.
+#[derive(Debug, Getters, Serialize, Deserialize)]
+pub(crate) struct GameStarted {
+    #[getset(get = "pub")]
+    table_id: u128
+}
+
+impl GameStarted {
+    pub fn new(table_id: u128) -> GameStarted {
+        GameStarted {
+            table_id
+        }
+    }
+}
+
+impl From<GameStarted> for MessageType {
+    fn from(msg: GameStarted) -> Self {
+        MessageType::GameStarted(msg)
+    }
+}
+
+/// Sent to each player dealt into a new hand, with their hole cards.
+#[derive(Debug, Getters, Serialize, Deserialize)]
+pub(crate) struct HandStarted {
+    #[getset(get = "pub")]
+    table_id: u128,
+
+    #[getset(get = "pub")]
+    view: PlayerView
+}
+
+impl HandStarted {
+    pub fn new(table_id: u128, view: PlayerView) -> HandStarted {
+        HandStarted {
+            table_id,
+            view
+        }
+    }
+}
+
+impl From<HandStarted> for MessageType {
+    fn from(msg: HandStarted) -> Self {
+        MessageType::HandStarted(msg)
+    }
+}
+
+/// Sent to the player whose turn it is. They answer with a `PlayerAction`.
+#[derive(Debug, Getters, Serialize, Deserialize)]
+pub(crate) struct ActionRequest {
+    #[getset(get = "pub")]
+    table_id: u128,
+
+    #[getset(get = "pub")]
+    view: PlayerView
+}
+
+impl ActionRequest {
+    pub fn new(table_id: u128, view: PlayerView) -> ActionRequest {
+        ActionRequest {
+            table_id,
+            view
+        }
+    }
+}
+
+impl From<ActionRequest> for MessageType {
+    fn from(msg: ActionRequest) -> Self {
+        MessageType::ActionRequest(msg)
+    }
+}
+
+/// A player's action on their turn, such as `{"action": "RaiseTo", "amount": 50}`.
+#[derive(Debug, Getters, Serialize, Deserialize)]
+pub(crate) struct PlayerAction {
+    #[getset(get = "pub")]
+    client_id: u128,
+
+    #[getset(get = "pub")]
+    table_id: u128,
+
+    #[getset(get = "pub")]
+    action: Action
+}
+
+#[cfg(test)]
+impl PlayerAction {
+    pub fn new(client_id: u128, table_id: u128, action: Action) -> PlayerAction {
+        PlayerAction {
+            client_id,
+            table_id,
+            action
+        }
+    }
+}
+
+impl From<PlayerAction> for MessageType {
+    fn from(msg: PlayerAction) -> Self {
+        MessageType::PlayerAction(msg)
+    }
+}
+
+/// Sent to everyone seated at a table when a player is asked for their action, so clients can count down the time the
+/// player has left. A player who runs out of time checks if they can, and otherwise folds.
+///
+/// # Fields
+///
+/// * `table_id`: The table the player is seated at.
+/// * `player_id`: The player whose turn it is.
+/// * `action_time_ms`: The milliseconds the player has left before their time bank starts being used.
+/// * `time_bank_ms`: The milliseconds left in the player's time bank.
+#[derive(Debug, Clone, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub(crate) struct ClockUpdate {
+    table_id: u128,
+    player_id: PlayerId,
+    action_time_ms: u64,
+    time_bank_ms: u64
+}
+
+impl ClockUpdate {
+    pub fn new(table_id: u128, player_id: PlayerId, action_time_ms: u64, time_bank_ms: u64) -> ClockUpdate {
+        ClockUpdate {
+            table_id,
+            player_id,
+            action_time_ms,
+            time_bank_ms
+        }
+    }
+}
+
+impl From<ClockUpdate> for MessageType {
+    fn from(msg: ClockUpdate) -> Self {
+        MessageType::ClockUpdate(msg)
+    }
+}
+
+/// Sent to everyone seated at a table for each blind, ante and action in a hand.
+#[derive(Debug, Getters, Serialize, Deserialize)]
+pub(crate) struct ActionTaken {
+    #[getset(get = "pub")]
+    table_id: u128,
+
+    #[getset(get = "pub")]
+    action: ActionRecord
+}
+
+impl ActionTaken {
+    pub fn new(table_id: u128, action: ActionRecord) -> ActionTaken {
+        ActionTaken {
+            table_id,
+            action
+        }
+    }
+}
+
+impl From<ActionTaken> for MessageType {
+    fn from(msg: ActionTaken) -> Self {
+        MessageType::ActionTaken(msg)
+    }
+}
+
+/// Sent to everyone seated at a table when a hand is over, with the board, the stacks and the hands shown at
+/// showdown. At a provably fair table, `shuffle_reveal` has the server seed, the players' entropy and the order of the
+/// deck, which clients check against the hand's `ShuffleCommitment` with `verify_shuffle`.
+#[derive(Debug, Getters, Serialize, Deserialize)]
+pub(crate) struct HandFinished {
+    #[getset(get = "pub")]
+    table_id: u128,
+
+    #[getset(get = "pub")]
+    view: SpectatorView,
+
+    #[getset(get = "pub")]
+    shuffle_reveal: Option<ShuffleReveal>
+}
+
+impl HandFinished {
+    pub fn new(table_id: u128, view: SpectatorView, shuffle_reveal: Option<ShuffleReveal>) -> HandFinished {
+        HandFinished {
+            table_id,
+            view,
+            shuffle_reveal
+        }
+    }
+}
+
+impl From<HandFinished> for MessageType {
+    fn from(msg: HandFinished) -> Self {
+        MessageType::HandFinished(msg)
+    }
+}
+
+/// Sent to a client who reconnects while seated at a table, with everything they need to pick the game back up. An
+/// `ActionRequest` follows if it is their turn.
+///
+/// # Fields
+///
+/// * `table_id`: The table the client is seated at.
+/// * `info`: The table's status and stakes.
+/// * `view`: What the client's player can see of the table, or `None` if they have busted.
+/// * `spectator_view`: What everyone at the table can see, including the last hand's showdown.
+#[derive(Debug, Getters, Serialize, Deserialize)]
+pub(crate) struct TableSnapshot {
+    #[getset(get = "pub")]
+    table_id: u128,
+
+    #[getset(get = "pub")]
+    info: TableInfo,
+
+    #[getset(get = "pub")]
+    view: Option<PlayerView>,
+
+    #[getset(get = "pub")]
+    spectator_view: SpectatorView
+}
+
+impl TableSnapshot {
+    pub fn new(table_id: u128, info: TableInfo, view: Option<PlayerView>, spectator_view: SpectatorView) -> TableSnapshot {
+        TableSnapshot {
+            table_id,
+            info,
+            view,
+            spectator_view
+        }
+    }
+}
+
+impl From<TableSnapshot> for MessageType {
+    fn from(msg: TableSnapshot) -> Self {
+        MessageType::TableSnapshot(msg)
+    }
+}
+
+/// The reply to a message the server could not act on. `message` describes `error` for people.
+#[derive(Debug, Getters, Serialize, Deserialize)]
+pub(crate) struct ErrorMessage {
+    #[getset(get = "pub")]
+    error: ServerError,
+
+    #[getset(get = "pub")]
+    message: String
+}
+
+impl ErrorMessage {
+    pub fn new(error: ServerError) -> ErrorMessage {
+        ErrorMessage {
+            message: error.to_string(),
+            error
+        }
+    }
+}
+
+impl From<ErrorMessage> for MessageType {
+    fn from(msg: ErrorMessage) -> Self {
+        MessageType::ErrorMessage(msg)
+    }
+}
+
+impl From<ServerError> for MessageType {
+    fn from(error: ServerError) -> Self {
+        MessageType::ErrorMessage(ErrorMessage::new(error))
+    }
+}
+
+/// The reasons the server rejects a client's message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "error")]
+pub(crate) enum ServerError {
+    /// The message is not JSON, has no "type", or is missing fields its type needs.
+    InvalidMessage { reason: String },
+    /// The message's "type" is not a message the server knows.
+    UnknownMessageType { message_type: String },
+    /// The server knows the message, but does not accept it from a client at this point.
+    UnexpectedMessage { message_type: String },
+    /// The message's `client_id` is not the id the server gave this connection.
+    WrongClientId,
+    /// There is no open table with this id.
+    UnknownTable { table_id: u128 },
+    /// Every seat at the table is taken.
+    TableFull { table_id: u128 },
+    /// The client is already seated at a table, and has to leave it first.
+    AlreadyAtTable { table_id: u128 },
+    /// The client is not seated at the table.
+    NotAtTable { table_id: u128 },
+    /// Only the table's leader can start the game.
+    NotLeader { table_id: u128 },
+    /// The game at the table has already started.
+    GameAlreadyStarted { table_id: u128 },
+    /// A game needs at least two players with chips.
+    NotEnoughPlayers { table_id: u128 },
+    /// The game rejected the player's action.
+    InvalidAction { reason: ActionError },
+    /// The game rejected the player's shuffle entropy.
+    InvalidEntropy { reason: ShuffleError },
+    /// The game rejected the player's choice of how many times to run the board.
+    InvalidRunCount { reason: RunoutError },
+    /// A table has to run the board at least once.
+    InvalidMaxRuns,
+    /// The blinds and buy-in have to be positive, the small blind no more than the big blind, and the buy-in at least
+    /// the big blind.
+    InvalidStakes,
+    /// The session token is not one the server gave out, or its session ended when the client did not reconnect in
+    /// time.
+    UnknownSession,
+    /// Another connection is using the session. A client can reconnect once the server has noticed its old connection
+    /// closed.
+    SessionInUse
+}
+
+impl std::fmt::Display for ServerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ServerError::InvalidMessage { reason } => write!(f, "Invalid message: {}", reason),
+            ServerError::UnknownMessageType { message_type } => write!(f, "Unknown message type {}", message_type),
+            ServerError::UnexpectedMessage { message_type } => write!(f, "The server does not accept {} messages here", message_type),
+            ServerError::WrongClientId => write!(f, "The client_id does not belong to this connection"),
+            ServerError::UnknownTable { table_id } => write!(f, "Table {} does not exist", table_id),
+            ServerError::TableFull { table_id } => write!(f, "Table {} is full", table_id),
+            ServerError::AlreadyAtTable { table_id } => write!(f, "You are already at table {}", table_id),
+            ServerError::NotAtTable { table_id } => write!(f, "You are not at table {}", table_id),
+            ServerError::NotLeader { table_id } => write!(f, "Only the leader of table {} can start the game", table_id),
+            ServerError::GameAlreadyStarted { table_id } => write!(f, "The game at table {} has already started", table_id),
+            ServerError::NotEnoughPlayers { table_id } => write!(f, "Table {} needs at least two players to start", table_id),
+            ServerError::InvalidAction { reason } => write!(f, "{}", reason),
+            ServerError::InvalidEntropy { reason } => write!(f, "{}", reason),
+            ServerError::InvalidRunCount { reason } => write!(f, "{}", reason),
+            ServerError::InvalidMaxRuns => write!(f, "A table has to run the board at least once"),
+            ServerError::InvalidStakes => write!(f, "The blinds and buy-in must be positive, with the small blind no more than the big blind and the buy-in at least the big blind"),
+            ServerError::UnknownSession => write!(f, "The session has ended or does not exist"),
+            ServerError::SessionInUse => write!(f, "The session is in use by another connection")
+        }
+    }
+}
+
+impl std::error::Error for ServerError {}
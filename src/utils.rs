@@ -1,59 +1,4 @@
 use std::cmp::max;
-use std::collections::{HashMap, HashSet};
-use std::hash::Hash;
-use std::sync::{Arc, Mutex, MutexGuard};
-use rand::distributions::Standard;
-
-use rand::Rng;
-use rand::rngs::OsRng;
-use crate::game::Game;
-
-pub async fn get_unique_client_id<T>(ids: &Arc<Mutex<HashSet<T>>>, rng: &Arc<Mutex<OsRng>>) -> T
-    where
-        T: Eq + Hash + Clone,
-        Standard: rand::distributions::Distribution<T>,
-{
-
-    let mut rng = rng.lock().expect("Failed to lock RNG");
-    let mut set = ids.lock().expect("Failed to lock ID set");
-    let mut random_value: T;
-
-    loop {
-        random_value = rng.gen();
-        if !set.contains(&random_value) {
-            break;
-        }
-    }
-
-    set.insert(random_value.clone());
-    random_value
-}
-
-pub async fn get_unique_game_id(game_ids: &Arc<Mutex<HashMap<u128, Game>>>, rng: Arc<Mutex<OsRng>>) -> u128
-    // where
-    //     T: Eq + Hash + Clone,
-    //     Standard: rand::distributions::Distribution<u128>,
-{
-
-    let mut rng = rng.lock().expect("Failed to lock RNG");
-    let mut map = game_ids.lock().expect("Failed to lock ID set");
-    let mut random_value: u128;
-
-    loop {
-        random_value = rng.gen();
-        if !map.contains_key(&random_value) {
-            break;
-        }
-    }
-
-    let big_blind = 2;
-    let initial_money = 1000;
-    let game: Game = Game::new(random_value.clone(), big_blind, initial_money);
-
-    map.insert(random_value.clone(), game);
-    random_value
-}
-
 // pub async fn get_unique_key<T>(ids: &MutexGuard<HashMap<u128>>, mut rng: MutexGuard<OsRng>) -> T
 //     where
 //         T: Eq + Hash + Clone,